@@ -1,6 +1,12 @@
 mod bench_process;
 #[cfg(feature = "plot")]
 mod plot;
+#[cfg(any(feature = "glow", feature = "wgpu", feature = "jack"))]
+mod run_gui;
+#[cfg(feature = "jack")]
+mod standalone;
+
+use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
@@ -13,46 +19,34 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Run OctaSine GUI (without audio generation)
-    #[cfg(any(feature = "glow", feature = "wgpu"))]
-    RunGui,
+    /// Run OctaSine GUI, with audio generation and MIDI input through JACK
+    /// if built with jack feature
+    #[cfg(any(feature = "glow", feature = "wgpu", feature = "jack"))]
+    RunGui(run_gui::Args),
     /// Benchmark OctaSine process functions and check output sample accuracy
     BenchProcess,
     /// Plot envelope and LFO curves (useful during development)
     #[cfg(feature = "plot")]
     Plot,
+    /// Write LV2 bundle manifest and plugin description (ttl files) to directory
+    GenerateLv2Ttl {
+        /// Bundle directory, e.g., ~/.lv2/octasine.lv2
+        bundle_dir: PathBuf,
+    },
 }
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        #[cfg(any(feature = "glow", feature = "wgpu"))]
-        Commands::RunGui => {
-            use std::sync::Arc;
-
-            use octasine::{plugin::vst2::editor::Editor, sync::SyncState};
-            use simplelog::{ConfigBuilder, LevelFilter, SimpleLogger};
-            use vst::plugin::HostCallback;
-
-            SimpleLogger::init(
-                LevelFilter::Info,
-                ConfigBuilder::new()
-                    .set_time_offset_to_local()
-                    .unwrap()
-                    .build(),
-            )
-            .unwrap();
-
-            let sync_state = Arc::new(SyncState::<HostCallback>::new(None));
-
-            Editor::open_blocking(sync_state);
-
-            Ok(())
-        }
+        #[cfg(any(feature = "glow", feature = "wgpu", feature = "jack"))]
+        Commands::RunGui(args) => run_gui::run(args),
         Commands::BenchProcess => bench_process::run(),
         #[cfg(feature = "plot")]
         Commands::Plot => plot::run(),
+        Commands::GenerateLv2Ttl { bundle_dir } => {
+            octasine::plugin::lv2::ttl::write_bundle_files(&bundle_dir)
+        }
     }
 }
 
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use octasine::{osc::OscServer, sync::SyncState};
+use simplelog::{ConfigBuilder, LevelFilter, SimpleLogger};
+use vst::plugin::HostCallback;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Listen for OSC messages on UDP address, e.g., 0.0.0.0:9000
+    #[arg(long, value_name = "ADDRESS")]
+    osc: Option<String>,
+    /// Only generate audio, don't open GUI
+    #[cfg(feature = "jack")]
+    #[arg(long)]
+    no_gui: bool,
+    /// JACK MIDI port to receive MIDI from. Can be passed multiple times.
+    /// ALSA MIDI devices are available as JACK ports when running a2jmidid.
+    #[cfg(feature = "jack")]
+    #[arg(long, value_name = "PORT")]
+    midi_input: Vec<String>,
+    /// List JACK MIDI ports that can be passed to --midi-input and exit
+    #[cfg(feature = "jack")]
+    #[arg(long)]
+    list_midi_inputs: bool,
+    /// Don't connect audio outputs to physical playback ports
+    #[cfg(feature = "jack")]
+    #[arg(long)]
+    no_connect_audio: bool,
+    /// Quit after given number of seconds when running without GUI (useful
+    /// for testing, e.g., with JACK dummy driver)
+    #[cfg(feature = "jack")]
+    #[arg(long, value_name = "SECONDS")]
+    quit_after: Option<f64>,
+}
+
+/// Run OctaSine GUI. With jack feature, audio is generated through a JACK
+/// client receiving MIDI input.
+pub fn run(args: Args) -> anyhow::Result<()> {
+    SimpleLogger::init(
+        LevelFilter::Info,
+        ConfigBuilder::new()
+            .set_time_offset_to_local()
+            .unwrap()
+            .build(),
+    )
+    .unwrap();
+
+    let sync_state = Arc::new(SyncState::<HostCallback>::new(None));
+
+    let _opt_osc_server = args
+        .osc
+        .as_deref()
+        .map(|addr| OscServer::start(sync_state.clone(), addr))
+        .transpose()?;
+
+    #[cfg(feature = "jack")]
+    {
+        if args.list_midi_inputs {
+            return crate::standalone::list_midi_inputs();
+        }
+        if args.no_gui || cfg!(not(any(feature = "glow", feature = "wgpu"))) {
+            return run_headless(sync_state, &args);
+        }
+    }
+
+    #[cfg(any(feature = "glow", feature = "wgpu"))]
+    {
+        // Keep JACK client running while GUI is open
+        #[cfg(feature = "jack")]
+        let _opt_standalone = match crate::standalone::start(
+            sync_state.clone(),
+            &args.midi_input,
+            !args.no_connect_audio,
+        ) {
+            Ok(standalone) => Some(standalone),
+            Err(err) => {
+                eprintln!(
+                    "Couldn't start JACK client, running without audio: {:#}",
+                    err
+                );
+
+                None
+            }
+        };
+
+        octasine::plugin::vst2::editor::Editor::open_blocking(sync_state);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "jack")]
+fn run_headless(sync_state: Arc<SyncState<HostCallback>>, args: &Args) -> anyhow::Result<()> {
+    let standalone =
+        crate::standalone::start(sync_state, &args.midi_input, !args.no_connect_audio)?;
+
+    if let Some(seconds) = args.quit_after {
+        ::std::thread::sleep(::std::time::Duration::from_secs_f64(seconds));
+    } else {
+        println!("Generating audio, press enter to quit");
+
+        ::std::io::stdin().read_line(&mut String::new())?;
+    }
+
+    let processed_frames = standalone.processed_frames();
+
+    println!("Processed {} frames", processed_frames);
+
+    if processed_frames == 0 {
+        anyhow::bail!("JACK server didn't call process callback");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use anyhow::Context;
+use jack::{
+    AsyncClient, AudioOut, Client, ClientOptions, Control, MidiIn, Port, PortFlags, ProcessHandler,
+    ProcessScope,
+};
+use octasine::{
+    audio::{gen::process_f32_runtime_select, AudioState},
+    common::{NoteEvent, NoteEventInner, SampleRate},
+    sync::SyncState,
+    utils::update_audio_parameters,
+};
+
+const CLIENT_NAME: &str = "OctaSine";
+const MIDI_PORT_TYPE: &str = "8 bit raw midi";
+const AUDIO_PORT_TYPE: &str = "32 bit float mono audio";
+
+/// Running JACK client. Processing stops when dropped.
+pub struct Standalone<H: Send + Sync + 'static> {
+    _client: AsyncClient<(), Processor<H>>,
+    processed_frames: Arc<AtomicU64>,
+}
+
+impl<H: Send + Sync + 'static> Standalone<H> {
+    pub fn processed_frames(&self) -> u64 {
+        self.processed_frames.load(Ordering::Relaxed)
+    }
+}
+
+/// Print JACK MIDI ports that can be passed to `--midi-input`
+pub fn list_midi_inputs() -> anyhow::Result<()> {
+    let (client, _) = Client::new(CLIENT_NAME, ClientOptions::NO_START_SERVER)
+        .context("connect to JACK server")?;
+
+    for port in client.ports(None, Some(MIDI_PORT_TYPE), PortFlags::IS_OUTPUT) {
+        println!("{}", port);
+    }
+
+    Ok(())
+}
+
+/// Start JACK client generating audio from patches in `sync` and connect
+/// MIDI input to `midi_inputs`. Audio output is connected to the first
+/// two physical playback ports if `connect_audio` is set.
+pub fn start<H: Send + Sync + 'static>(
+    sync: Arc<SyncState<H>>,
+    midi_inputs: &[String],
+    connect_audio: bool,
+) -> anyhow::Result<Standalone<H>> {
+    let (client, _) = Client::new(CLIENT_NAME, ClientOptions::NO_START_SERVER)
+        .context("connect to JACK server")?;
+
+    let midi_in = client.register_port("midi_in", MidiIn)?;
+    let out_left = client.register_port("out_left", AudioOut)?;
+    let out_right = client.register_port("out_right", AudioOut)?;
+
+    let midi_in_name = midi_in.name()?;
+    let out_names = [out_left.name()?, out_right.name()?];
+
+    let mut audio: Box<AudioState> = Default::default();
+
+    audio.set_sample_rate(SampleRate(client.sample_rate() as f64));
+
+    let processed_frames = Arc::new(AtomicU64::new(0));
+
+    let processor = Processor {
+        audio,
+        sync,
+        midi_in,
+        out_left,
+        out_right,
+        processed_frames: processed_frames.clone(),
+    };
+
+    let client = client.activate_async((), processor)?;
+
+    for source in midi_inputs {
+        client
+            .as_client()
+            .connect_ports_by_name(source, &midi_in_name)
+            .with_context(|| format!("connect MIDI input {}", source))?;
+    }
+
+    if connect_audio {
+        let playback_ports = client.as_client().ports(
+            None,
+            Some(AUDIO_PORT_TYPE),
+            PortFlags::IS_INPUT | PortFlags::IS_PHYSICAL,
+        );
+
+        for (out_name, playback_port) in out_names.iter().zip(playback_ports.iter()) {
+            client
+                .as_client()
+                .connect_ports_by_name(out_name, playback_port)
+                .with_context(|| format!("connect audio output to {}", playback_port))?;
+        }
+    }
+
+    Ok(Standalone {
+        _client: client,
+        processed_frames,
+    })
+}
+
+struct Processor<H> {
+    audio: Box<AudioState>,
+    sync: Arc<SyncState<H>>,
+    midi_in: Port<MidiIn>,
+    out_left: Port<AudioOut>,
+    out_right: Port<AudioOut>,
+    processed_frames: Arc<AtomicU64>,
+}
+
+impl<H: Send + Sync + 'static> ProcessHandler for Processor<H> {
+    fn process(&mut self, _client: &Client, process_scope: &ProcessScope) -> Control {
+        self.audio
+            .enqueue_note_events(self.midi_in.iter(process_scope).filter_map(|event| {
+                if event.bytes.is_empty() || event.bytes.len() > 3 {
+                    return None;
+                }
+
+                let mut data = [0; 3];
+
+                data[..event.bytes.len()].copy_from_slice(event.bytes);
+
+                Some(NoteEvent {
+                    delta_frames: event.time,
+                    event: NoteEventInner::Midi { data },
+                })
+            }));
+
+        let lefts = self.out_left.as_mut_slice(process_scope);
+        let rights = self.out_right.as_mut_slice(process_scope);
+
+        process_f32_runtime_select(
+            &mut self.audio,
+            lefts,
+            rights,
+            None,
+            None,
+            0,
+            |audio_state| {
+                update_audio_parameters(audio_state, &self.sync);
+            },
+        );
+
+        self.processed_frames
+            .fetch_add(u64::from(process_scope.n_frames()), Ordering::Relaxed);
+
+        Control::Continue
+    }
+}
@@ -38,6 +38,18 @@ fn main() {
                 lfo_index, p, parameter_index
             )
             .unwrap(),
+            Parameter::ModMatrix(slot_index, p) => writeln!(
+                &mut file,
+                "Parameter::ModMatrix({}, ModMatrixParameter::{:?}) => {},",
+                slot_index, p, parameter_index
+            )
+            .unwrap(),
+            Parameter::Macro(macro_index) => writeln!(
+                &mut file,
+                "Parameter::Macro({}) => {},",
+                macro_index, parameter_index
+            )
+            .unwrap(),
         };
     }
 
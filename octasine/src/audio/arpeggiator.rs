@@ -0,0 +1,377 @@
+use arrayvec::ArrayVec;
+use fastrand::Rng;
+
+use crate::{
+    common::{BeatsPerMinute, IndexMap, TimePerSample},
+    parameters::arpeggiator_mode::ArpeggiatorMode,
+};
+
+use super::{
+    parameters::{common::AudioParameter, AudioParameters},
+    voices::KeyVelocity,
+};
+
+const MAX_OCTAVES: usize = 4;
+/// Lower tempos, including zero or invalid ones reported by hosts, are
+/// replaced with this one to keep step durations finite
+const MIN_BPM: f64 = 1.0;
+
+/// Key and clap note id of notes removed from arpeggiator. Only keys can be
+/// removed, so capacity is never exceeded if emptied after each call.
+pub type EndedClapNotes = ArrayVec<(u8, i32), 128>;
+
+/// Key fed to arpeggiator
+#[derive(Debug, Clone, Copy)]
+struct ArpeggiatorKey {
+    velocity: KeyVelocity,
+    clap_note_id: Option<i32>,
+    /// Key is physically held, as opposed to only being latched
+    held: bool,
+}
+
+/// Key release and key press produced by arpeggiator during one sample
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArpeggiatorOutput {
+    pub opt_key_off: Option<u8>,
+    pub opt_key_on: Option<(u8, KeyVelocity)>,
+}
+
+/// Tempo-synced arpeggiator. It is advanced one sample at a time, so output
+/// is sample accurate regardless of block boundaries.
+pub struct Arpeggiator {
+    /// Keys in the order they were pressed
+    keys: IndexMap<u8, ArpeggiatorKey>,
+    /// Scratch buffer for keys in current pattern
+    pattern: Vec<(u8, KeyVelocity)>,
+    pub ended_clap_notes: EndedClapNotes,
+    step: usize,
+    /// Seconds until next step
+    time_until_step: f64,
+    /// Sounding key and seconds until it is released
+    opt_sounding_key: Option<(u8, f64)>,
+    rng: Rng,
+}
+
+impl Default for Arpeggiator {
+    fn default() -> Self {
+        let mut keys = IndexMap::default();
+
+        keys.reserve(128);
+
+        Self {
+            keys,
+            pattern: Vec::with_capacity(128 * MAX_OCTAVES),
+            ended_clap_notes: EndedClapNotes::new(),
+            step: 0,
+            time_until_step: 0.0,
+            opt_sounding_key: None,
+            rng: Rng::with_seed(0),
+        }
+    }
+}
+
+impl Arpeggiator {
+    pub fn is_idle(&self) -> bool {
+        self.keys.is_empty() && self.opt_sounding_key.is_none()
+    }
+
+    pub fn press_key(
+        &mut self,
+        key: u8,
+        velocity: KeyVelocity,
+        opt_clap_note_id: Option<i32>,
+        latch: bool,
+    ) {
+        if !self.keys.values().any(|k| k.held) {
+            // Pressing keys after releasing all of them replaces latched keys
+            if latch {
+                self.remove_keys(|_| true);
+            }
+            // Start pattern from the beginning on the current sample
+            if self.keys.is_empty() {
+                self.step = 0;
+                self.time_until_step = 0.0;
+            }
+        }
+
+        if let Some(ArpeggiatorKey {
+            clap_note_id: Some(clap_note_id),
+            ..
+        }) = self.keys.shift_remove(&key)
+        {
+            push_ended_clap_note(&mut self.ended_clap_notes, key, clap_note_id);
+        }
+
+        self.keys.insert(
+            key,
+            ArpeggiatorKey {
+                velocity,
+                clap_note_id: opt_clap_note_id,
+                held: true,
+            },
+        );
+    }
+
+    #[cfg(feature = "clap")]
+    pub fn has_clap_note_id(&self, clap_note_id: i32) -> bool {
+        self.keys
+            .values()
+            .any(|k| k.clap_note_id == Some(clap_note_id))
+    }
+
+    /// Returns false if key was not pressed through arpeggiator
+    pub fn release_key(&mut self, key: u8, latch: bool) -> bool {
+        match self.keys.get_mut(&key) {
+            Some(arpeggiator_key) if latch => {
+                arpeggiator_key.held = false;
+            }
+            Some(_) => {
+                self.remove_keys(|(k, _)| k == key);
+            }
+            None => return false,
+        }
+
+        true
+    }
+
+    /// Remove all keys. Returns key to release, if any.
+    pub fn stop(&mut self) -> Option<u8> {
+        self.remove_keys(|_| true);
+
+        self.opt_sounding_key.take().map(|(key, _)| key)
+    }
+
+    pub fn advance_one_sample(
+        &mut self,
+        parameters: &AudioParameters,
+        bpm: BeatsPerMinute,
+        time_per_sample: TimePerSample,
+    ) -> ArpeggiatorOutput {
+        let mut output = ArpeggiatorOutput::default();
+
+        if !parameters.arpeggiator_latch.get_value() {
+            self.remove_keys(|(_, k)| !k.held);
+        }
+
+        if let Some((key, time_until_key_off)) = self.opt_sounding_key {
+            if time_until_key_off <= 0.0 {
+                output.opt_key_off = Some(key);
+
+                self.opt_sounding_key = None;
+            }
+        }
+
+        if !self.keys.is_empty() && self.time_until_step <= 0.0 {
+            let step_duration = self.step_duration(parameters, bpm);
+
+            if let Some((key, _)) = self.opt_sounding_key.take() {
+                output.opt_key_off = Some(key);
+            }
+
+            let (key, velocity) = self.next_key(parameters);
+
+            output.opt_key_on = Some((key, velocity));
+
+            self.opt_sounding_key = Some((
+                key,
+                step_duration * f64::from(parameters.arpeggiator_gate.get_value()),
+            ));
+            self.time_until_step += step_duration;
+            self.step = self.step.wrapping_add(1);
+        }
+
+        if let Some((_, time_until_key_off)) = self.opt_sounding_key.as_mut() {
+            *time_until_key_off -= time_per_sample.0;
+        }
+
+        if !self.keys.is_empty() {
+            self.time_until_step -= time_per_sample.0;
+        }
+
+        output
+    }
+
+    /// Duration of current step in seconds
+    fn step_duration(&self, parameters: &AudioParameters, bpm: BeatsPerMinute) -> f64 {
+        let swing = f64::from(parameters.arpeggiator_swing.get_value()) * 0.5;
+        // Lengthen even steps and shorten odd ones
+        let swing_factor = if self.step % 2 == 1 {
+            1.0 - swing
+        } else {
+            1.0 + swing
+        };
+
+        parameters.arpeggiator_rate.get_value().beats() * 60.0 / bpm.0.max(MIN_BPM) * swing_factor
+    }
+
+    /// Key to play for current step. Must only be called when keys are held.
+    fn next_key(&mut self, parameters: &AudioParameters) -> (u8, KeyVelocity) {
+        let mode = parameters.arpeggiator_mode.get_value();
+        let octaves = parameters.arpeggiator_octaves.get_value();
+
+        self.pattern.clear();
+
+        for octave in 0..octaves {
+            for (key, arpeggiator_key) in self.keys.iter() {
+                let key = u16::from(*key) + 12 * u16::from(octave);
+
+                if key < 128 {
+                    self.pattern.push((key as u8, arpeggiator_key.velocity));
+                }
+            }
+        }
+
+        if mode != ArpeggiatorMode::AsPlayed {
+            self.pattern.sort_by_key(|(key, _)| *key);
+            self.pattern.dedup_by_key(|(key, _)| *key);
+        }
+
+        let len = self.pattern.len();
+
+        let index = match mode {
+            ArpeggiatorMode::Off | ArpeggiatorMode::Up | ArpeggiatorMode::AsPlayed => {
+                self.step % len
+            }
+            ArpeggiatorMode::Down => len - 1 - self.step % len,
+            ArpeggiatorMode::UpDown if len == 1 => 0,
+            ArpeggiatorMode::UpDown => {
+                let period = 2 * len - 2;
+                let index = self.step % period;
+
+                if index < len {
+                    index
+                } else {
+                    period - index
+                }
+            }
+            ArpeggiatorMode::Random => self.rng.usize(..len),
+        };
+
+        self.pattern[index]
+    }
+
+    fn remove_keys<F: Fn((u8, &ArpeggiatorKey)) -> bool>(&mut self, f: F) {
+        let ended_clap_notes = &mut self.ended_clap_notes;
+
+        self.keys.retain(|key, arpeggiator_key| {
+            if f((*key, arpeggiator_key)) {
+                if let Some(clap_note_id) = arpeggiator_key.clap_note_id {
+                    push_ended_clap_note(ended_clap_notes, *key, clap_note_id);
+                }
+
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+fn push_ended_clap_note(ended_clap_notes: &mut EndedClapNotes, key: u8, clap_note_id: i32) {
+    if let Err(err) = ended_clap_notes.try_push((key, clap_note_id)) {
+        ::log::error!("Arpeggiator ended_clap_notes full, couldn't push {:?}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parameters::{
+        arpeggiator_mode::ArpeggiatorModeValue,
+        arpeggiator_rate::{ArpeggiatorRate, ArpeggiatorRateValue},
+        MasterParameter, Parameter, ParameterValue,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_arpeggiator_modes() {
+        const SAMPLES_PER_STEP: usize = 1024;
+
+        for (mode, expected_keys) in [
+            (ArpeggiatorMode::Up, [60, 64, 67, 60, 64]),
+            (ArpeggiatorMode::Down, [67, 64, 60, 67, 64]),
+            (ArpeggiatorMode::UpDown, [60, 64, 67, 64, 60]),
+            (ArpeggiatorMode::AsPlayed, [64, 60, 67, 64, 60]),
+        ] {
+            let mut parameters = AudioParameters::default();
+
+            parameters.set_parameter_from_patch(
+                Parameter::Master(MasterParameter::ArpeggiatorMode),
+                ArpeggiatorModeValue::new_from_audio(mode).to_patch(),
+            );
+            parameters.set_parameter_from_patch(
+                Parameter::Master(MasterParameter::ArpeggiatorRate),
+                ArpeggiatorRateValue::new_from_audio(ArpeggiatorRate::Quarter).to_patch(),
+            );
+
+            let mut arpeggiator = Arpeggiator::default();
+
+            for key in [64, 60, 67] {
+                arpeggiator.press_key(key, KeyVelocity::default(), None, false);
+            }
+
+            let mut key_ons = Vec::new();
+            let mut key_off_samples = Vec::new();
+
+            for sample in 0..SAMPLES_PER_STEP * expected_keys.len() {
+                let output = arpeggiator.advance_one_sample(
+                    &parameters,
+                    BeatsPerMinute(60.0),
+                    TimePerSample(1.0 / SAMPLES_PER_STEP as f64),
+                );
+
+                if let Some((key, _)) = output.opt_key_on {
+                    key_ons.push((sample, key));
+                }
+                if output.opt_key_off.is_some() {
+                    key_off_samples.push(sample);
+                }
+            }
+
+            let expected_key_ons = expected_keys
+                .iter()
+                .enumerate()
+                .map(|(i, key)| (i * SAMPLES_PER_STEP, *key))
+                .collect::<Vec<_>>();
+            let expected_key_off_samples = (0..expected_keys.len())
+                .map(|i| i * SAMPLES_PER_STEP + SAMPLES_PER_STEP / 2)
+                .collect::<Vec<_>>();
+
+            assert_eq!(key_ons, expected_key_ons);
+            assert_eq!(key_off_samples, expected_key_off_samples);
+        }
+    }
+
+    #[test]
+    fn test_arpeggiator_latch() {
+        let mut arpeggiator = Arpeggiator::default();
+
+        arpeggiator.press_key(60, KeyVelocity::default(), Some(1), true);
+        arpeggiator.press_key(64, KeyVelocity::default(), Some(2), true);
+
+        assert!(arpeggiator.release_key(60, true));
+        assert!(arpeggiator.release_key(64, true));
+        assert_eq!(arpeggiator.keys.len(), 2);
+        assert!(arpeggiator.ended_clap_notes.is_empty());
+
+        // New key after all keys were released replaces latched keys
+        arpeggiator.press_key(67, KeyVelocity::default(), Some(3), true);
+
+        assert_eq!(arpeggiator.keys.keys().copied().collect::<Vec<_>>(), [67]);
+        assert_eq!(arpeggiator.ended_clap_notes.as_slice(), [(60, 1), (64, 2)]);
+
+        assert!(!arpeggiator.release_key(72, true));
+    }
+
+    #[test]
+    fn test_arpeggiator_step_duration_min_bpm() {
+        let parameters = AudioParameters::default();
+        let arpeggiator = Arpeggiator::default();
+
+        for bpm in [0.0, -120.0, f64::NAN] {
+            let duration = arpeggiator.step_duration(&parameters, BeatsPerMinute(bpm));
+
+            assert!(duration.is_finite() && duration > 0.0);
+        }
+    }
+}
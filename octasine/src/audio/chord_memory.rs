@@ -0,0 +1,204 @@
+use arrayvec::ArrayVec;
+
+use crate::{
+    common::IndexMap,
+    parameters::chord_memory::{ChordShape, MAX_CHORD_NOTES},
+};
+
+use super::voices::KeyVelocity;
+
+pub type ChordKeys<T> = ArrayVec<T, MAX_CHORD_NOTES>;
+
+/// Key held by user
+#[derive(Debug, Clone)]
+struct HeldKey {
+    velocity: KeyVelocity,
+    /// Keys triggered by this key
+    keys: ChordKeys<u8>,
+}
+
+/// Tracks held keys, expands them to chords when chord memory is active and
+/// learns chord shapes from them
+pub struct ChordMemory {
+    shape: ChordShape,
+    held_keys: IndexMap<u8, HeldKey>,
+    /// Number of held keys that triggered each key. Keys are only released
+    /// once no held key uses them.
+    key_use_counts: [u16; 128],
+    /// Original key of notes that triggered chords, by clap note id
+    clap_note_keys: IndexMap<i32, u8>,
+    /// Shape learned on audio thread, to be stored in patch
+    pub opt_learned_shape: Option<ChordShape>,
+}
+
+impl Default for ChordMemory {
+    fn default() -> Self {
+        let mut held_keys = IndexMap::default();
+        let mut clap_note_keys = IndexMap::default();
+
+        held_keys.reserve(128);
+        clap_note_keys.reserve(128);
+
+        Self {
+            shape: Default::default(),
+            held_keys,
+            key_use_counts: [0; 128],
+            clap_note_keys,
+            opt_learned_shape: None,
+        }
+    }
+}
+
+impl ChordMemory {
+    pub fn set_shape(&mut self, shape: ChordShape) {
+        self.shape = shape;
+    }
+
+    /// Returns keys to press. If chord memory is active and no chord keys
+    /// are in range, nothing is returned and the clap note id isn't stored,
+    /// so the note should be reported as ended right away.
+    pub fn press_key(
+        &mut self,
+        key: u8,
+        velocity: KeyVelocity,
+        opt_clap_note_id: Option<i32>,
+        active: bool,
+    ) -> ChordKeys<(u8, KeyVelocity)> {
+        let mut keys = ChordKeys::new();
+
+        if active && !self.shape.notes().is_empty() {
+            for note in self.shape.notes() {
+                if let Ok(chord_key) = u8::try_from(i16::from(key) + i16::from(note.offset)) {
+                    if chord_key < 128 {
+                        keys.push((chord_key, KeyVelocity(velocity.0 * note.velocity_scale)));
+                    }
+                }
+            }
+
+            if let Some(clap_note_id) = opt_clap_note_id.filter(|_| !keys.is_empty()) {
+                self.clap_note_keys.insert(clap_note_id, key);
+            }
+        } else {
+            keys.push((key, velocity));
+        }
+
+        for (key, _) in keys.iter() {
+            self.key_use_counts[usize::from(*key)] += 1;
+        }
+
+        let held_key = HeldKey {
+            velocity,
+            keys: keys.iter().map(|(key, _)| *key).collect(),
+        };
+
+        // Key pressed again without being released first. Its previous
+        // keys are pressed again or released along with new ones.
+        if let Some(previous) = self.held_keys.insert(key, held_key) {
+            for key in previous.keys {
+                self.key_use_counts[usize::from(key)] -= 1;
+            }
+        }
+
+        keys
+    }
+
+    /// Returns keys to release. Keys still used by chords of other held
+    /// keys are left out.
+    pub fn release_key(&mut self, key: u8) -> ChordKeys<u8> {
+        match self.held_keys.shift_remove(&key) {
+            Some(held_key) => held_key
+                .keys
+                .into_iter()
+                .filter(|key| {
+                    let count = &mut self.key_use_counts[usize::from(*key)];
+
+                    *count -= 1;
+
+                    *count == 0
+                })
+                .collect(),
+            None if self.key_use_counts[usize::from(key)] > 0 => ChordKeys::new(),
+            None => ::std::iter::once(key).collect(),
+        }
+    }
+
+    /// Learn shape from held keys. Returns false if no shape could be
+    /// learned, e.g., because no keys are held.
+    pub fn learn_shape(&mut self) -> bool {
+        let keys = self.held_keys.iter().map(|(key, k)| (*key, k.velocity.0));
+
+        if let Some(shape) = ChordShape::learn(keys) {
+            self.shape = shape.clone();
+            self.opt_learned_shape = Some(shape);
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Original key if clap note id belongs to note that triggered chord
+    pub fn get_clap_note_key(&self, clap_note_id: i32) -> Option<u8> {
+        self.clap_note_keys.get(&clap_note_id).copied()
+    }
+
+    #[cfg(feature = "clap")]
+    pub fn forget_clap_note(&mut self, clap_note_id: i32) {
+        self.clap_note_keys.shift_remove(&clap_note_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chord_memory_keys() {
+        let mut chord_memory = ChordMemory::default();
+
+        chord_memory.set_shape(ChordShape::parse("0, 4:0.5, 7, 12").unwrap());
+
+        let keys = chord_memory.press_key(120, KeyVelocity(1.0), Some(1), true);
+
+        assert_eq!(
+            keys.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+            [120, 124, 127]
+        );
+        assert_eq!(keys[1].1 .0, 0.5);
+        assert_eq!(chord_memory.get_clap_note_key(1), Some(120));
+
+        assert_eq!(chord_memory.release_key(120).as_slice(), [120, 124, 127]);
+        assert_eq!(chord_memory.release_key(60).as_slice(), [60]);
+
+        let keys = chord_memory.press_key(60, KeyVelocity(1.0), None, false);
+
+        assert_eq!(keys.len(), 1);
+    }
+
+    #[test]
+    fn test_chord_memory_no_keys_in_range() {
+        let mut chord_memory = ChordMemory::default();
+
+        chord_memory.set_shape(ChordShape::parse("12, 24").unwrap());
+
+        assert!(chord_memory
+            .press_key(120, KeyVelocity(1.0), Some(1), true)
+            .is_empty());
+        assert_eq!(chord_memory.get_clap_note_key(1), None);
+        assert!(chord_memory.release_key(120).is_empty());
+    }
+
+    #[test]
+    fn test_chord_memory_shared_keys() {
+        let mut chord_memory = ChordMemory::default();
+
+        chord_memory.set_shape(ChordShape::parse("0, 7").unwrap());
+
+        chord_memory.press_key(60, KeyVelocity(1.0), None, true);
+        chord_memory.press_key(67, KeyVelocity(1.0), None, true);
+
+        // Key 67 is still used by chord of held key 67
+        assert_eq!(chord_memory.release_key(60).as_slice(), [60]);
+        assert_eq!(chord_memory.release_key(67).as_slice(), [67, 74]);
+    }
+}
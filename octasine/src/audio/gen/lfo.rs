@@ -3,11 +3,12 @@ use arrayvec::ArrayVec;
 use crate::audio::parameters::{common::AudioParameter, LfoAudioParameters};
 use crate::audio::voices::lfos::VoiceLfo;
 use crate::common::*;
-use crate::parameters::{LfoParameter, PARAMETERS};
+use crate::parameters::{LfoParameter, LFO_TARGETS, PARAMETERS};
 
 pub struct LfoTargetValues {
     values: [Option<f32>; PARAMETERS.len()],
-    set_indices: ArrayVec<u8, NUM_LFOS>,
+    /// Each target is only stored once, so there is room for all of them
+    set_indices: ArrayVec<u8, { LFO_TARGETS.len() }>,
 }
 
 impl Default for LfoTargetValues {
@@ -24,7 +25,7 @@ impl LfoTargetValues {
         self.values[target as usize]
     }
 
-    fn set_or_add(&mut self, target: u8, value: f32) {
+    pub fn set_or_add(&mut self, target: u8, value: f32) {
         match &mut self.values[target as usize] {
             Some(v) => *v += value,
             v @ None => {
@@ -35,7 +36,7 @@ impl LfoTargetValues {
         }
     }
 
-    fn clear_set(&mut self) {
+    pub fn clear_set(&mut self) {
         for i in self.set_indices.iter().copied() {
             self.values[i as usize] = None;
         }
@@ -44,6 +45,8 @@ impl LfoTargetValues {
     }
 }
 
+/// Advance voice LFOs and add their outputs to target values. Call after
+/// `LfoTargetValues::clear_set` and after any mod matrix values are added.
 pub fn update_lfo_target_values(
     lfo_values: &mut LfoTargetValues,
     lfo_parameters: &mut [LfoAudioParameters; NUM_LFOS],
@@ -51,14 +54,13 @@ pub fn update_lfo_target_values(
     sample_rate: SampleRate,
     time_per_sample: TimePerSample,
     bpm_lfo_multiplier: BpmLfoMultiplier,
+    lfos_used_as_mod_matrix_sources: [bool; NUM_LFOS],
 ) {
     const AMOUNT_PARAMETER_INDICES: [u8; NUM_LFOS] = LfoParameter::Amount.index_array();
     const SHAPE_PARAMETER_INDICES: [u8; NUM_LFOS] = LfoParameter::Shape.index_array();
     const RATIO_PARAMETER_INDICES: [u8; NUM_LFOS] = LfoParameter::FrequencyRatio.index_array();
     const FREE_PARAMETER_INDICES: [u8; NUM_LFOS] = LfoParameter::FrequencyFree.index_array();
 
-    lfo_values.clear_set();
-
     for (lfo_index, (voice_lfo, lfo_parameter)) in voice_lfos
         .iter_mut()
         .zip(lfo_parameters.iter_mut())
@@ -67,12 +69,13 @@ pub fn update_lfo_target_values(
     {
         assert!(lfo_index < NUM_LFOS);
 
-        let target_index = lfo_parameter.target.get_value().index();
+        let opt_target_index = lfo_parameter.target.get_value().index();
 
-        let target_index = match (target_index, voice_lfo.is_stopped()) {
-            (None, _) | (_, true) => continue,
-            (Some(index), false) => index,
-        };
+        if voice_lfo.is_stopped()
+            | (opt_target_index.is_none() & !lfos_used_as_mod_matrix_sources[lfo_index])
+        {
+            continue;
+        }
 
         let amount = lfo_parameter.active.get_value()
             * lfo_parameter
@@ -109,6 +112,8 @@ pub fn update_lfo_target_values(
 
         let addition = voice_lfo.get_value(amount);
 
-        lfo_values.set_or_add(target_index, addition);
+        if let Some(target_index) = opt_target_index {
+            lfo_values.set_or_add(target_index, addition);
+        }
     }
 }
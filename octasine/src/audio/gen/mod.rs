@@ -1,4 +1,5 @@
 pub mod lfo;
+pub mod mod_matrix;
 
 use std::f64::consts::TAU;
 
@@ -7,13 +8,15 @@ use ringbuf::ring_buffer::RbBase;
 
 use crate::audio::parameters::{common::AudioParameter, OperatorAudioParameters};
 use crate::audio::voices::log10_table::Log10Table;
-use crate::audio::AudioState;
+use crate::audio::{AudioState, MONOPHONIC_VOICE_INDEX, NUM_VOICES};
 use crate::common::*;
 use crate::parameters::operator_wave_type::WaveType;
+use crate::parameters::sidechain_mod_target::SidechainModTarget;
 use crate::parameters::{MasterParameter, ModTargetStorage, OperatorParameter, Parameter};
 use crate::simd::*;
 
 use lfo::*;
+use mod_matrix::*;
 
 const MASTER_VOLUME_FACTOR: f64 = 0.2;
 const LIMIT: f64 = 10.0;
@@ -26,8 +29,46 @@ pub trait AudioGen {
         rights: &mut [f32],
         position: usize,
     );
+    #[allow(clippy::missing_safety_doc)]
+    unsafe fn process_f64(
+        octasine: &mut AudioState,
+        lefts: &mut [f64],
+        rights: &mut [f64],
+        position: usize,
+    );
 }
 
+/// Sample type of host audio buffers. Audio is always generated in f64.
+pub trait OutputSample: Copy {
+    fn from_f64(sample: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+impl OutputSample for f32 {
+    #[inline(always)]
+    fn from_f64(sample: f64) -> Self {
+        sample as f32
+    }
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        self.into()
+    }
+}
+
+impl OutputSample for f64 {
+    #[inline(always)]
+    fn from_f64(sample: f64) -> Self {
+        sample
+    }
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// Separate stereo output buffers for each operator's mix output
+pub type OperatorOutputs<'a, T> = [(&'a mut [T], &'a mut [T]); NUM_OPERATORS];
+
 /// Audio gen data cache.
 ///
 /// Data is only valid for the duration of the processing of one or two
@@ -35,9 +76,15 @@ pub trait AudioGen {
 pub struct AudioGenData<const W: usize> {
     lfo_target_values: LfoTargetValues,
     volume_velocity_sensitivity: [f64; W],
-    /// Allocate room for data for 128 polyphonic voices as well as the mono
-    /// voice, even if they won't all be used at once in practice.
-    voices: [VoiceData<W>; 129],
+    /// Allocate room for data for all polyphonic voice slots as well as the
+    /// mono voice, even if they won't all be used at once in practice.
+    voices: [VoiceData<W>; NUM_VOICES],
+    /// Mix output per operator, summed over voices. Only generated when
+    /// separate operator outputs are enabled.
+    operator_mix_outs: [[f64; W]; NUM_OPERATORS],
+    /// Audio input multiplied by sidechain mod amount
+    sidechain_mod: [f64; W],
+    sidechain_mod_target: SidechainModTarget,
 }
 
 impl<const W: usize> Default for AudioGenData<W> {
@@ -46,6 +93,27 @@ impl<const W: usize> Default for AudioGenData<W> {
             lfo_target_values: Default::default(),
             volume_velocity_sensitivity: [0.0; W],
             voices: array_init::array_init(|_| Default::default()),
+            operator_mix_outs: [[0.0; W]; NUM_OPERATORS],
+            sidechain_mod: [0.0; W],
+            sidechain_mod_target: Default::default(),
+        }
+    }
+}
+
+impl<const W: usize> AudioGenData<W> {
+    fn write_operator_outputs<T: OutputSample>(
+        &self,
+        operator_outputs: &mut OperatorOutputs<T>,
+        position: usize,
+    ) {
+        for ((lefts, rights), mix_out) in operator_outputs
+            .iter_mut()
+            .zip(self.operator_mix_outs.iter())
+        {
+            for (sample_index, chunk) in mix_out.chunks_exact(2).enumerate() {
+                lefts[position + sample_index] = T::from_f64(chunk[0]);
+                rights[position + sample_index] = T::from_f64(chunk[1]);
+            }
         }
     }
 }
@@ -117,11 +185,25 @@ impl<const W: usize> Default for VoiceOperatorData<W> {
     }
 }
 
+#[duplicate_item(
+    [
+        process_runtime_select [ process_f32_runtime_select ]
+        process_sample_type [ process_f32 ]
+        sample_type [ f32 ]
+    ]
+    [
+        process_runtime_select [ process_f64_runtime_select ]
+        process_sample_type [ process_f64 ]
+        sample_type [ f64 ]
+    ]
+)]
 #[inline]
-pub fn process_f32_runtime_select<F>(
+pub fn process_runtime_select<F>(
     audio_state: &mut AudioState,
-    lefts: &mut [f32],
-    rights: &mut [f32],
+    lefts: &mut [sample_type],
+    rights: &mut [sample_type],
+    opt_inputs: Option<(&[sample_type], &[sample_type])>,
+    mut opt_operator_outputs: Option<OperatorOutputs<sample_type>>,
     frame_offset: usize,
     updater: F,
 ) where
@@ -129,6 +211,8 @@ pub fn process_f32_runtime_select<F>(
 {
     let num_samples = lefts.len();
 
+    audio_state.operator_outputs_enabled = opt_operator_outputs.is_some();
+
     let mut position = 0;
 
     loop {
@@ -142,39 +226,75 @@ pub fn process_f32_runtime_select<F>(
                 (2..) if is_x86_feature_detected!("avx") => {
                     let new_position = position + 2;
 
-                    Avx::process_f32(
+                    audio_state.sidechain.set_frames(
+                        opt_inputs,
+                        position..new_position,
+                        audio_state.time_per_sample,
+                    );
+
+                    Avx::process_sample_type(
                         audio_state,
                         &mut lefts[position..new_position],
                         &mut rights[position..new_position],
                         frame_offset + position,
                     );
 
+                    if let Some(operator_outputs) = opt_operator_outputs.as_mut() {
+                        audio_state
+                            .audio_gen_data_w4
+                            .write_operator_outputs(operator_outputs, position);
+                    }
+
                     position = new_position;
                 }
                 #[cfg(target_arch = "x86_64")]
                 1.. => {
                     let new_position = position + 1;
 
-                    Sse2::process_f32(
+                    audio_state.sidechain.set_frames(
+                        opt_inputs,
+                        position..new_position,
+                        audio_state.time_per_sample,
+                    );
+
+                    Sse2::process_sample_type(
                         audio_state,
                         &mut lefts[position..new_position],
                         &mut rights[position..new_position],
                         frame_offset + position,
                     );
 
+                    if let Some(operator_outputs) = opt_operator_outputs.as_mut() {
+                        audio_state
+                            .audio_gen_data_w2
+                            .write_operator_outputs(operator_outputs, position);
+                    }
+
                     position = new_position;
                 }
                 #[cfg(not(target_arch = "x86_64"))]
                 1.. => {
                     let new_position = position + 1;
 
-                    Fallback::process_f32(
+                    audio_state.sidechain.set_frames(
+                        opt_inputs,
+                        position..new_position,
+                        audio_state.time_per_sample,
+                    );
+
+                    Fallback::process_sample_type(
                         audio_state,
                         &mut lefts[position..new_position],
                         &mut rights[position..new_position],
                         frame_offset + position,
                     );
 
+                    if let Some(operator_outputs) = opt_operator_outputs.as_mut() {
+                        audio_state
+                            .audio_gen_data_w2
+                            .write_operator_outputs(operator_outputs, position);
+                    }
+
                     position = new_position;
                 }
                 0 => {
@@ -224,31 +344,72 @@ mod gen {
             rights: &mut [f32],
             position: usize,
         ) {
-            assert_eq!(lefts.len(), Pd::SAMPLES);
-            assert_eq!(rights.len(), Pd::SAMPLES);
+            process(audio_state, lefts, rights, position);
+        }
 
-            if audio_state.pending_note_events.is_empty()
-                & audio_state.polyphonic_voices.is_empty()
-                & !audio_state.monophonic_voice.active
-            {
-                for (l, r) in lefts.iter_mut().zip(rights.iter_mut()) {
-                    *l = 0.0;
-                    *r = 0.0;
-                }
+        #[target_feature_enable]
+        unsafe fn process_f64(
+            audio_state: &mut AudioState,
+            lefts: &mut [f64],
+            rights: &mut [f64],
+            position: usize,
+        ) {
+            process(audio_state, lefts, rights, position);
+        }
+    }
 
-                return;
+    #[feature_gate]
+    #[target_feature_enable]
+    unsafe fn process<T: OutputSample>(
+        audio_state: &mut AudioState,
+        lefts: &mut [T],
+        rights: &mut [T],
+        position: usize,
+    ) {
+        assert_eq!(lefts.len(), Pd::SAMPLES);
+        assert_eq!(rights.len(), Pd::SAMPLES);
+
+        if audio_state.pending_note_events.is_empty()
+            & audio_state.polyphonic_voices.is_empty()
+            & !audio_state.monophonic_voice.active
+            & audio_state.arpeggiator.is_idle()
+        {
+            for (l, r) in lefts.iter_mut().zip(rights.iter_mut()) {
+                *l = T::from_f64(0.0);
+                *r = T::from_f64(0.0);
             }
 
-            let num_valid_voice_datas = extract_voice_data(audio_state, position);
+            audio_state.audio_gen_data_field.operator_mix_outs = [[0.0; Pd::WIDTH]; NUM_OPERATORS];
 
-            gen_audio(
-                &mut audio_state.rng,
-                audio_state.audio_gen_data_field.volume_velocity_sensitivity,
-                &audio_state.audio_gen_data_field.voices[..num_valid_voice_datas],
-                lefts,
-                rights,
-            );
+            return;
         }
+
+        let num_valid_voice_datas = extract_voice_data(audio_state, position);
+
+        let audio_gen_data = &mut audio_state.audio_gen_data_field;
+
+        let opt_sidechain_mod = match audio_gen_data.sidechain_mod_target {
+            SidechainModTarget::None => None,
+            SidechainModTarget::Operator(index) => {
+                Some((index as usize, Pd::from_arr(audio_gen_data.sidechain_mod)))
+            }
+        };
+
+        let opt_operator_mix_outs = if audio_state.operator_outputs_enabled {
+            Some(&mut audio_gen_data.operator_mix_outs)
+        } else {
+            None
+        };
+
+        gen_audio(
+            &mut audio_state.rng,
+            audio_gen_data.volume_velocity_sensitivity,
+            &audio_gen_data.voices[..num_valid_voice_datas],
+            lefts,
+            rights,
+            opt_sidechain_mod,
+            opt_operator_mix_outs,
+        );
     }
 
     #[feature_gate]
@@ -271,14 +432,31 @@ mod gen {
                     .get_value() as f64,
             );
 
+            let sidechain_frame = audio_state.sidechain.get_frame(sample_index);
+
+            {
+                let audio_gen_data = &mut audio_state.audio_gen_data_field;
+                let amount = audio_state.parameters.sidechain_mod_amount.get_value() as f64;
+
+                audio_gen_data.sidechain_mod[sample_index * 2] = sidechain_frame.left * amount;
+                audio_gen_data.sidechain_mod[sample_index * 2 + 1] = sidechain_frame.right * amount;
+                audio_gen_data.sidechain_mod_target =
+                    audio_state.parameters.sidechain_mod_target.get_value();
+            }
+
+            let velocity_curve = audio_state.parameters.velocity_curve.get_value();
+            let custom_velocity_curve = &audio_state.custom_velocity_curve;
+
             let operators = &mut audio_state.parameters.operators;
             let lfo_values = &mut audio_state.audio_gen_data_field.lfo_target_values;
+            let lfos_used_as_mod_matrix_sources =
+                lfos_used_as_mod_matrix_sources(&audio_state.parameters.mod_matrix);
 
             let voice_iterator = audio_state
                 .polyphonic_voices
                 .iter_mut()
                 .chain(
-                    ::std::iter::once((&128u8, &mut audio_state.monophonic_voice))
+                    ::std::iter::once((&MONOPHONIC_VOICE_INDEX, &mut audio_state.monophonic_voice))
                         .filter(|(_, v)| v.active),
                 )
                 .map(|(k, v)| (*k, v));
@@ -288,35 +466,40 @@ mod gen {
             #[cfg(feature = "clap")]
             let mut ended_clap_notes: ::arrayvec::ArrayVec<
                 (bool, crate::audio::ClapNoteEnded),
-                129,
+                NUM_VOICES,
             > = ::arrayvec::ArrayVec::new();
 
             for (voice_index, voice) in voice_iterator {
-                // Select an appropriate VoiceData item to fill with data
-                let voice_data = if sample_index == 0 {
-                    let voice_data =
-                        &mut audio_state.audio_gen_data_field.voices[num_valid_voice_datas];
-
-                    voice_data.voice_index = voice_index;
-
-                    voice_data.reset_envelope_volumes();
+                // Select an appropriate VoiceData item to fill with data.
+                //
+                // During second sample in AVX mode, look for the relevant voice data cache
+                // among the ones filled while processing sample 1. If it is not found because
+                // the voice was activated this sample, use a new one.
+                let opt_existing_index = if sample_index == 0 {
+                    None
+                } else {
+                    audio_state.audio_gen_data_field.voices[..num_valid_voice_datas]
+                        .iter()
+                        .position(|voice_data| voice_data.voice_index == voice_index)
+                };
 
-                    num_valid_voice_datas += 1;
+                let voice_data_index = match opt_existing_index {
+                    Some(index) => index,
+                    None => {
+                        // Voice indices are unique and there is room for all
+                        // of them, so this should never happen
+                        let voice_data = match audio_state
+                            .audio_gen_data_field
+                            .voices
+                            .get_mut(num_valid_voice_datas)
+                        {
+                            Some(voice_data) => voice_data,
+                            None => {
+                                ::log::error!("no voice data left for voice {}", voice_index);
 
-                    voice_data
-                } else {
-                    // During second sample in AVX mode, look for the relevant voice data cache
-                    // among the ones filled while processing sample 1. If it is not found because
-                    // the voice was activated this sample, use a new one.
-                    if let Some(voice_data) = audio_state.audio_gen_data_field.voices
-                        [..num_valid_voice_datas]
-                        .iter_mut()
-                        .find(|voice_data| voice_data.voice_index == voice_index)
-                    {
-                        voice_data
-                    } else {
-                        let voice_data =
-                            &mut audio_state.audio_gen_data_field.voices[num_valid_voice_datas];
+                                continue;
+                            }
+                        };
 
                         voice_data.voice_index = voice_index;
 
@@ -324,10 +507,12 @@ mod gen {
 
                         num_valid_voice_datas += 1;
 
-                        voice_data
+                        num_valid_voice_datas - 1
                     }
                 };
 
+                let voice_data = &mut audio_state.audio_gen_data_field.voices[voice_data_index];
+
                 voice.advance_interpolators_one_sample(audio_state.sample_rate);
 
                 for (operator_index, operator) in operators.iter_mut().enumerate() {
@@ -341,6 +526,24 @@ mod gen {
                         );
                 }
 
+                lfo_values.clear_set();
+
+                #[cfg(feature = "clap")]
+                for (parameter_index, amount) in audio_state
+                    .clap_global_parameter_modulations
+                    .iter()
+                    .chain(voice.clap_parameter_modulations.iter())
+                {
+                    lfo_values.set_or_add(parameter_index, amount);
+                }
+
+                update_mod_matrix_target_values(
+                    lfo_values,
+                    &mut audio_state.parameters.mod_matrix,
+                    voice,
+                    audio_state.mod_wheel,
+                    sidechain_frame.follower_level as f32,
+                );
                 update_lfo_target_values(
                     lfo_values,
                     &mut audio_state.parameters.lfos,
@@ -348,12 +551,13 @@ mod gen {
                     audio_state.sample_rate,
                     time_per_sample,
                     audio_state.bpm_lfo_multiplier,
+                    lfos_used_as_mod_matrix_sources,
                 );
 
                 set_value_for_both_channels(
                     &mut voice_data.key_velocity,
                     sample_index,
-                    voice.get_key_velocity().0 as f64,
+                    velocity_curve.apply(voice.get_key_velocity().0, custom_velocity_curve) as f64,
                 );
 
                 const MASTER_VOLUME_INDEX: u8 =
@@ -442,6 +646,12 @@ mod gen {
                 }
             }
 
+            // Chord memory voices share clap note ids, so several voices
+            // for the same note might end during this sample
+            #[cfg(feature = "clap")]
+            let mut ended_chord_clap_note_ids: ::arrayvec::ArrayVec<i32, NUM_VOICES> =
+                ::arrayvec::ArrayVec::new();
+
             #[cfg(feature = "clap")]
             for (voice_was_monophonic, event) in ended_clap_notes.drain(..) {
                 /// Avoid sending clap note ended event if we just switched
@@ -454,10 +664,10 @@ mod gen {
                     clap_note_id: i32,
                 ) -> bool {
                     if voice_was_monophonic {
-                        if let Some(voice) = audio_state.polyphonic_voices.get(&key) {
-                            if voice.active && voice.clap_note_id == Some(clap_note_id) {
-                                return false;
-                            }
+                        if audio_state.polyphonic_voices.values().any(|v| {
+                            v.active && v.key() == key && v.clap_note_id == Some(clap_note_id)
+                        }) {
+                            return false;
                         }
                     } else {
                         let v = &audio_state.monophonic_voice;
@@ -470,7 +680,14 @@ mod gen {
                     true
                 }
 
-                use ringbuf::Rb;
+                if ended_chord_clap_note_ids.contains(&event.clap_note_id) {
+                    continue;
+                }
+
+                let is_chord_note = audio_state
+                    .chord_memory
+                    .get_clap_note_key(event.clap_note_id)
+                    .is_some();
 
                 if should_push(
                     audio_state,
@@ -478,9 +695,24 @@ mod gen {
                     event.key,
                     event.clap_note_id,
                 ) {
-                    if let Err(_) = audio_state.clap_ended_notes.push(event) {
-                        // Should never happen
-                        ::log::error!("Clap ended notes buffer full");
+                    audio_state.push_clap_note_ended(
+                        event.key,
+                        Some(event.clap_note_id),
+                        event.sample_index as usize,
+                    );
+
+                    if is_chord_note
+                        && audio_state
+                            .chord_memory
+                            .get_clap_note_key(event.clap_note_id)
+                            .is_none()
+                    {
+                        if let Err(err) = ended_chord_clap_note_ids.try_push(event.clap_note_id) {
+                            ::log::error!(
+                                "ended chord clap note ids ArrayVec full, cant push {:?}",
+                                err.element()
+                            );
+                        }
                     }
                 }
             }
@@ -614,25 +846,38 @@ mod gen {
 
     #[feature_gate]
     #[target_feature_enable]
-    unsafe fn gen_audio(
+    unsafe fn gen_audio<T: OutputSample>(
         rng: &mut fastrand::Rng,
         volume_velocity_sensitivity: [f64; Pd::WIDTH],
         active_voices: &[VoiceData<{ Pd::WIDTH }>],
-        audio_buffer_lefts: &mut [f32],
-        audio_buffer_rights: &mut [f32],
+        audio_buffer_lefts: &mut [T],
+        audio_buffer_rights: &mut [T],
+        opt_sidechain_mod: Option<(usize, Pd)>,
+        opt_operator_mix_outs: Option<&mut [[f64; Pd::WIDTH]; NUM_OPERATORS]>,
     ) {
         // Pd::SAMPLES * 2 because of two channels. Even index = left channel
         let mut total_mix_out = Pd::new_zeroed();
+        let mut operator_mix_outs = [Pd::new_zeroed(); NUM_OPERATORS];
+
+        let generate_operator_mix_outs = opt_operator_mix_outs.is_some();
 
         for voice_data in active_voices.iter() {
             let operator_generate_audio = run_operator_dependency_analysis(voice_data);
 
             // Voice modulation input storage, indexed by operator
             let mut voice_modulation_inputs = [Pd::new_zeroed(); 4];
+
+            if let Some((operator_index, sidechain_mod)) = opt_sidechain_mod {
+                voice_modulation_inputs[operator_index] = sidechain_mod;
+            }
             let mut voice_mix_out = Pd::new_zeroed();
 
             let key_velocity = Pd::from_arr(voice_data.key_velocity);
 
+            let master_volume = Pd::from_arr(voice_data.master_volume);
+            let volume_velocity_factor =
+                velocity_factor(Pd::from_arr(volume_velocity_sensitivity), key_velocity);
+
             // Go through operators downwards, starting with operator 4
             for operator_index in (0..4).map(|i| 3 - i) {
                 // Possibly skip generation based on previous dependency analysis
@@ -651,16 +896,17 @@ mod gen {
 
                 voice_mix_out += mix_out;
 
+                if generate_operator_mix_outs {
+                    operator_mix_outs[operator_index] +=
+                        mix_out * volume_velocity_factor * master_volume;
+                }
+
                 // Add modulation output to target operators' modulation inputs
                 for target in operator_voice_data.modulation_targets.active_indices() {
                     voice_modulation_inputs[target] += mod_out;
                 }
             }
 
-            let master_volume = Pd::from_arr(voice_data.master_volume);
-            let volume_velocity_factor =
-                velocity_factor(Pd::from_arr(volume_velocity_sensitivity), key_velocity);
-
             total_mix_out += voice_mix_out * volume_velocity_factor * master_volume;
         }
 
@@ -669,9 +915,18 @@ mod gen {
             .max(Pd::new(-LIMIT))
             .to_arr();
 
+        if let Some(out) = opt_operator_mix_outs {
+            for (out, mix_out) in out.iter_mut().zip(operator_mix_outs) {
+                *out = (mix_out * Pd::new(MASTER_VOLUME_FACTOR))
+                    .min(Pd::new(LIMIT))
+                    .max(Pd::new(-LIMIT))
+                    .to_arr();
+            }
+        }
+
         for (sample_index, chunk) in total_mix_out_arr.chunks_exact(2).enumerate() {
-            audio_buffer_lefts[sample_index] = chunk[0] as f32;
-            audio_buffer_rights[sample_index] = chunk[1] as f32;
+            audio_buffer_lefts[sample_index] = T::from_f64(chunk[0]);
+            audio_buffer_rights[sample_index] = T::from_f64(chunk[1]);
         }
     }
 
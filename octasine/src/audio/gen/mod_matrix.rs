@@ -0,0 +1,60 @@
+use crate::audio::parameters::{common::AudioParameter, ModMatrixSlotAudioParameters};
+use crate::audio::voices::Voice;
+use crate::common::*;
+use crate::parameters::ModMatrixSource;
+
+use super::lfo::LfoTargetValues;
+
+/// Get which LFOs need to be advanced even if they lack a target
+pub fn lfos_used_as_mod_matrix_sources(
+    slots: &[ModMatrixSlotAudioParameters; NUM_MOD_MATRIX_SLOTS],
+) -> [bool; NUM_LFOS] {
+    let mut used = [false; NUM_LFOS];
+
+    for slot in slots.iter() {
+        if let ModMatrixSource::Lfo(lfo_index) = slot.source.get_value() {
+            used[lfo_index as usize] = true;
+        }
+    }
+
+    used
+}
+
+/// Add mod matrix source values multiplied by amount to target values.
+///
+/// LFO and envelope sources use values from the previous sample, since they
+/// are calculated after mod matrix values are applied.
+pub fn update_mod_matrix_target_values(
+    lfo_values: &mut LfoTargetValues,
+    slots: &mut [ModMatrixSlotAudioParameters; NUM_MOD_MATRIX_SLOTS],
+    voice: &mut Voice,
+    mod_wheel: f32,
+    sidechain_level: f32,
+) {
+    for slot in slots.iter_mut() {
+        let target_index = match slot.target.get_value().index() {
+            Some(index) => index,
+            None => continue,
+        };
+
+        let source_value = match slot.source.get_value() {
+            ModMatrixSource::None => continue,
+            ModMatrixSource::Lfo(index) => voice.lfos[index as usize].get_last_output(),
+            ModMatrixSource::Velocity => voice.get_key_velocity().0,
+            ModMatrixSource::Key => f32::from(voice.key()) * (1.0 / 127.0),
+            ModMatrixSource::Aftertouch => voice.get_aftertouch(),
+            ModMatrixSource::ModWheel => mod_wheel,
+            ModMatrixSource::Envelope(index) => voice.operators[index as usize]
+                .volume_envelope
+                .get_last_volume(),
+            ModMatrixSource::Random => voice.get_random_value(),
+            ModMatrixSource::Sidechain => sidechain_level,
+        };
+
+        let addition = source_value * slot.amount.get_value();
+
+        if addition != 0.0 {
+            lfo_values.set_or_add(target_index, addition);
+        }
+    }
+}
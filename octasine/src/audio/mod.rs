@@ -1,6 +1,9 @@
+mod arpeggiator;
+mod chord_memory;
 pub mod gen;
 mod interpolation;
 pub mod parameters;
+mod sidechain;
 pub mod voices;
 
 use std::mem::MaybeUninit;
@@ -11,7 +14,9 @@ use ringbuf::{LocalRb, Rb};
 use crate::{
     common::*,
     parameters::{
-        glide_active::GlideActive, glide_mode::GlideMode, voice_mode::VoiceMode, Parameter,
+        arpeggiator_mode::ArpeggiatorMode, chord_memory::ChordShape, glide_active::GlideActive,
+        glide_mode::GlideMode, note_priority::NotePriority, velocity_curve::CustomVelocityCurve,
+        voice_mode::VoiceMode, voice_stealing::VoiceStealing, Parameter,
     },
 };
 
@@ -19,7 +24,12 @@ use parameters::*;
 use voices::*;
 
 use self::{
-    gen::AudioGenData, parameters::common::AudioParameter, voices::log10_table::Log10Table,
+    arpeggiator::{Arpeggiator, ArpeggiatorOutput},
+    chord_memory::ChordMemory,
+    gen::AudioGenData,
+    parameters::common::AudioParameter,
+    sidechain::Sidechain,
+    voices::log10_table::Log10Table,
 };
 
 #[cfg(feature = "clap")]
@@ -34,6 +44,25 @@ pub struct ClapNoteEnded {
 pub type ClapEndedNotesRb =
     ringbuf::LocalRb<ClapNoteEnded, Vec<::std::mem::MaybeUninit<ClapNoteEnded>>>;
 
+/// Maximum number of simultaneously allocated polyphonic voices, including
+/// ones being stolen. Leaves room for stolen voices to fade out when
+/// playing at maximum polyphony.
+const NUM_POLYPHONIC_VOICE_SLOTS: u8 = u8::MAX;
+
+/// Voice index of monophonic voice in audio gen data, after polyphonic slots
+const MONOPHONIC_VOICE_INDEX: u8 = NUM_POLYPHONIC_VOICE_SLOTS;
+
+/// Maximum number of voices generating audio at once: all polyphonic voice
+/// slots and the monophonic voice
+const NUM_VOICES: usize = NUM_POLYPHONIC_VOICE_SLOTS as usize + 1;
+
+/// Key held while in monophonic voice mode
+#[derive(Debug, Clone, Copy)]
+struct MonophonicPressedKey {
+    velocity: KeyVelocity,
+    clap_note_id: Option<i32>,
+}
+
 pub struct AudioState {
     sample_rate: SampleRate,
     time_per_sample: TimePerSample,
@@ -41,19 +70,40 @@ pub struct AudioState {
     bpm_lfo_multiplier: BpmLfoMultiplier,
     pub global_pitch_bend: GlobalPitchBend,
     sustain_pedal_on: bool,
+    mod_wheel: f32,
+    /// Latest value of each MIDI controller since last update from sync
+    /// state, used for MIDI learn and CC mappings
+    midi_controller_changes: [Option<u8>; 128],
+    /// Bank select MSB and LSB
+    midi_bank: (u8, u8),
+    /// Patch index selected by MIDI program change since last update from
+    /// sync state
+    opt_program_change_patch_index: Option<usize>,
     parameters: AudioParameters,
+    custom_velocity_curve: CustomVelocityCurve,
     rng: Rng,
     log10table: Log10Table,
+    /// Polyphonic voices by slot index, ordered by when their keys were
+    /// pressed. Several voices can play the same key.
     pub polyphonic_voices: IndexMap<u8, Voice>,
     pub monophonic_voice: Voice,
-    monophonic_pressed_keys: IndexMap<u8, Option<i32>>,
+    monophonic_pressed_keys: IndexMap<u8, MonophonicPressedKey>,
+    chord_memory: ChordMemory,
+    arpeggiator: Arpeggiator,
     pending_note_events: LocalRb<NoteEvent, Vec<MaybeUninit<NoteEvent>>>,
+    pending_parameter_events: LocalRb<ParameterEvent, Vec<MaybeUninit<ParameterEvent>>>,
     opt_last_voice_mode: Option<VoiceMode>,
     audio_gen_data_w2: Box<AudioGenData<2>>,
     #[cfg(target_arch = "x86_64")]
     audio_gen_data_w4: Box<AudioGenData<4>>,
+    /// Whether to generate separate outputs for operator mix outs
+    operator_outputs_enabled: bool,
+    sidechain: Sidechain,
     #[cfg(feature = "clap")]
     pub clap_ended_notes: ClapEndedNotesRb,
+    /// Modulation from clap host not targeting specific voices
+    #[cfg(feature = "clap")]
+    clap_global_parameter_modulations: ClapParameterModulations,
 }
 
 impl Default for AudioState {
@@ -61,7 +111,7 @@ impl Default for AudioState {
         let polyphonic_voices = {
             let mut voices = IndexMap::default();
 
-            voices.reserve(128);
+            voices.reserve(NUM_POLYPHONIC_VOICE_SLOTS.into());
 
             voices
         };
@@ -80,19 +130,31 @@ impl Default for AudioState {
             bpm_lfo_multiplier: BeatsPerMinute::default().into(),
             global_pitch_bend: Default::default(),
             sustain_pedal_on: false,
+            mod_wheel: 0.0,
+            midi_controller_changes: [None; 128],
+            midi_bank: (0, 0),
+            opt_program_change_patch_index: None,
             parameters: AudioParameters::default(),
+            custom_velocity_curve: Default::default(),
             rng: Rng::new(),
             log10table: Default::default(),
             polyphonic_voices,
-            monophonic_voice: Voice::new(MidiPitch::new(0), true),
+            monophonic_voice: Voice::new(MidiPitch::new(0), true, 0),
             monophonic_pressed_keys,
+            chord_memory: Default::default(),
+            arpeggiator: Default::default(),
             pending_note_events: LocalRb::new(1024),
+            pending_parameter_events: LocalRb::new(1024),
             opt_last_voice_mode: None,
             audio_gen_data_w2: Default::default(),
             #[cfg(target_arch = "x86_64")]
             audio_gen_data_w4: Default::default(),
+            operator_outputs_enabled: false,
+            sidechain: Default::default(),
             #[cfg(feature = "clap")]
             clap_ended_notes: ringbuf::LocalRb::new(256),
+            #[cfg(feature = "clap")]
+            clap_global_parameter_modulations: Default::default(),
         }
     }
 }
@@ -102,6 +164,41 @@ impl AudioState {
         self.parameters.set_parameter_from_patch(parameter, value);
     }
 
+    pub fn set_custom_velocity_curve(&mut self, curve: CustomVelocityCurve) {
+        self.custom_velocity_curve = curve;
+    }
+
+    pub fn set_chord_shape(&mut self, shape: ChordShape) {
+        self.chord_memory.set_shape(shape);
+    }
+
+    /// Learn chord shape from held keys
+    pub fn learn_chord_shape(&mut self) {
+        if !self.chord_memory.learn_shape() {
+            ::log::warn!("Couldn't learn chord shape from held keys");
+        }
+    }
+
+    /// Chord shape learned since last call, to be stored in patch
+    pub fn take_learned_chord_shape(&mut self) -> Option<ChordShape> {
+        self.chord_memory.opt_learned_shape.take()
+    }
+
+    /// MIDI controller numbers and values received since last call
+    pub fn take_midi_controller_changes(&mut self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        self.midi_controller_changes
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(controller, opt_value)| {
+                opt_value.take().map(|value| (controller as u8, value))
+            })
+    }
+
+    /// Patch index selected by MIDI program change since last call
+    pub fn take_program_change_patch_index(&mut self) -> Option<usize> {
+        self.opt_program_change_patch_index.take()
+    }
+
     pub fn set_sample_rate(&mut self, sample_rate: SampleRate) {
         self.sample_rate = sample_rate;
         self.time_per_sample = sample_rate.into();
@@ -126,6 +223,13 @@ impl AudioState {
         }
     }
 
+    /// Queue parameter change to be applied at its sample offset
+    pub fn enqueue_parameter_event(&mut self, event: ParameterEvent) {
+        if self.pending_parameter_events.push(event).is_err() {
+            ::log::error!("Audio parameter event buffer full");
+        }
+    }
+
     pub fn advance_one_sample(&mut self) {
         self.parameters.advance_one_sample(self.sample_rate);
 
@@ -152,15 +256,38 @@ impl AudioState {
         self.opt_last_voice_mode = Some(voice_mode);
     }
 
-    #[cfg(feature = "vst2")]
+    #[cfg(any(feature = "vst2", feature = "vst3"))]
     pub fn sort_note_events(&mut self) {
         let (a, b) = self.pending_note_events.as_mut_slices();
 
         a.sort_unstable_by_key(|e| e.delta_frames);
         b.sort_unstable_by_key(|e| e.delta_frames);
+
+        let (a, b) = self.pending_parameter_events.as_mut_slices();
+
+        a.sort_unstable_by_key(|e| e.delta_frames);
+        b.sort_unstable_by_key(|e| e.delta_frames);
     }
 
     fn process_events_for_sample(&mut self, buffer_offset: usize) {
+        // Apply parameter changes first, so that notes starting at the same
+        // sample use the new values
+        loop {
+            match self
+                .pending_parameter_events
+                .iter()
+                .next()
+                .map(|e| e.delta_frames as usize)
+            {
+                Some(event_delta_frames) if event_delta_frames == buffer_offset => {
+                    let event = self.pending_parameter_events.pop().unwrap();
+
+                    self.set_parameter_from_patch(event.parameter, event.value);
+                }
+                _ => break,
+            }
+        }
+
         loop {
             match self
                 .pending_note_events
@@ -176,6 +303,8 @@ impl AudioState {
                 _ => break,
             }
         }
+
+        self.advance_arpeggiator(buffer_offset);
     }
 
     fn process_note_event(&mut self, event: NoteEventInner, sample_index: usize) {
@@ -185,16 +314,38 @@ impl AudioState {
                 data[0] >>= 4;
 
                 match data {
-                    [0b_1000, key, _] => self.key_off(key, sample_index),
-                    [0b_1001, key, 0] => self.key_off(key, sample_index),
-                    [0b_1001, key, velocity] => {
-                        self.key_on(key, KeyVelocity::from_midi_velocity(velocity), None)
-                    }
+                    [0b_1000, key, _] => self.note_off(key, None, sample_index),
+                    [0b_1001, key, 0] => self.note_off(key, None, sample_index),
+                    [0b_1001, key, velocity] => self.note_on(
+                        key,
+                        KeyVelocity::from_midi_velocity(velocity),
+                        None,
+                        sample_index,
+                    ),
                     [0b_1010, key, pressure] => {
-                        self.aftertouch(key, KeyVelocity::from_midi_velocity(pressure));
+                        self.aftertouch(key, f32::from(pressure) / 127.0);
                     }
-                    [0b_1011, 64, v] => {
-                        self.sustain_pedal_on = v >= 64;
+                    [0b_1011, controller, v] => {
+                        match controller {
+                            0 => self.midi_bank.0 = v,
+                            1 => self.mod_wheel = f32::from(v) / 127.0,
+                            32 => self.midi_bank.1 = v,
+                            64 => self.sustain_pedal_on = v >= 64,
+                            _ => (),
+                        }
+
+                        if let Some(change) = self
+                            .midi_controller_changes
+                            .get_mut(usize::from(controller))
+                        {
+                            *change = Some(v);
+                        }
+                    }
+                    [0b_1100, program, _] => {
+                        self.program_change(program);
+                    }
+                    [0b_1101, pressure, _] => {
+                        self.channel_aftertouch(f32::from(pressure) / 127.0);
                     }
                     [0b_1110, lsb, msb] => {
                         self.global_pitch_bend.update_from_midi(lsb, msb);
@@ -207,21 +358,150 @@ impl AudioState {
                 velocity,
                 clap_note_id,
             } => {
-                self.key_on(key, KeyVelocity(velocity as f32), Some(clap_note_id));
+                self.note_on(
+                    key,
+                    KeyVelocity(velocity as f32),
+                    Some(clap_note_id),
+                    sample_index,
+                );
             }
             NoteEventInner::ClapNotePressure { key, pressure } => {
-                self.aftertouch(key, KeyVelocity(pressure as f32));
+                self.aftertouch(key, pressure as f32);
             }
-            NoteEventInner::ClapNoteOff { key } => {
-                self.key_off(key, sample_index);
+            NoteEventInner::ClapNoteOff { key, clap_note_id } => {
+                self.note_off(key, Some(clap_note_id), sample_index);
             }
             NoteEventInner::ClapBpm { bpm } => {
                 self.set_bpm(bpm);
             }
+            #[cfg(feature = "clap")]
+            NoteEventInner::ClapParameterModulation {
+                parameter,
+                clap_note_id,
+                key,
+                amount,
+            } => {
+                self.set_clap_parameter_modulation(parameter, clap_note_id, key, amount as f32);
+            }
+        }
+    }
+
+    /// Select patch by program number within bank set by bank select
+    /// messages. Each bank holds 128 patches.
+    fn program_change(&mut self, program: u8) {
+        let (msb, lsb) = self.midi_bank;
+        let bank = usize::from(msb) * 128 + usize::from(lsb);
+
+        self.opt_program_change_patch_index = Some(bank * 128 + usize::from(program));
+    }
+
+    /// Handle incoming key press, expanding it to chord if chord memory is
+    /// active. Chord notes share the clap note id of the pressed key.
+    fn note_on(
+        &mut self,
+        key: u8,
+        velocity: KeyVelocity,
+        opt_clap_note_id: Option<i32>,
+        sample_index: usize,
+    ) {
+        let chord_memory_active = self.parameters.chord_memory.get_value();
+
+        let keys =
+            self.chord_memory
+                .press_key(key, velocity, opt_clap_note_id, chord_memory_active);
+
+        // No chord keys in range, so note won't trigger any voices
+        if keys.is_empty() {
+            self.push_clap_note_ended(key, opt_clap_note_id, sample_index);
+        }
+
+        for (key, velocity) in keys {
+            self.arpeggiate_or_key_on(key, velocity, opt_clap_note_id, sample_index);
+        }
+    }
+
+    /// Handle incoming key release, releasing all keys of its chord
+    fn note_off(&mut self, key: u8, opt_clap_note_id: Option<i32>, sample_index: usize) {
+        for key in self.chord_memory.release_key(key) {
+            self.arpeggiate_or_key_off(key, opt_clap_note_id, sample_index);
+        }
+    }
+
+    /// Pass key press through arpeggiator if active
+    fn arpeggiate_or_key_on(
+        &mut self,
+        key: u8,
+        velocity: KeyVelocity,
+        opt_clap_note_id: Option<i32>,
+        sample_index: usize,
+    ) {
+        if self.parameters.arpeggiator_mode.get_value() == ArpeggiatorMode::Off {
+            self.key_on(key, velocity, opt_clap_note_id, sample_index);
+        } else {
+            let latch = self.parameters.arpeggiator_latch.get_value();
+
+            self.arpeggiator
+                .press_key(key, velocity, opt_clap_note_id, latch);
+
+            self.push_arpeggiator_ended_clap_notes(sample_index);
+        }
+    }
+
+    /// Pass key release through arpeggiator. Keys pressed before it was
+    /// activated are released directly.
+    fn arpeggiate_or_key_off(
+        &mut self,
+        key: u8,
+        opt_clap_note_id: Option<i32>,
+        sample_index: usize,
+    ) {
+        let latch = self.parameters.arpeggiator_latch.get_value();
+
+        if self.arpeggiator.release_key(key, latch) {
+            self.push_arpeggiator_ended_clap_notes(sample_index);
+        } else {
+            self.key_off(key, opt_clap_note_id, sample_index);
         }
     }
 
-    fn key_on(&mut self, key: u8, velocity: KeyVelocity, opt_clap_note_id: Option<i32>) {
+    fn advance_arpeggiator(&mut self, sample_index: usize) {
+        let output = if self.parameters.arpeggiator_mode.get_value() == ArpeggiatorMode::Off {
+            if self.arpeggiator.is_idle() {
+                return;
+            }
+
+            ArpeggiatorOutput {
+                opt_key_off: self.arpeggiator.stop(),
+                opt_key_on: None,
+            }
+        } else {
+            self.arpeggiator
+                .advance_one_sample(&self.parameters, self.bpm, self.time_per_sample)
+        };
+
+        if let Some(key) = output.opt_key_off {
+            self.key_off(key, None, sample_index);
+        }
+        if let Some((key, velocity)) = output.opt_key_on {
+            self.key_on(key, velocity, None, sample_index);
+        }
+
+        self.push_arpeggiator_ended_clap_notes(sample_index);
+    }
+
+    fn push_arpeggiator_ended_clap_notes(&mut self, sample_index: usize) {
+        while let Some((key, clap_note_id)) = self.arpeggiator.ended_clap_notes.pop() {
+            self.push_clap_note_ended(key, Some(clap_note_id), sample_index);
+        }
+    }
+
+    fn key_on(
+        &mut self,
+        key: u8,
+        velocity: KeyVelocity,
+        opt_clap_note_id: Option<i32>,
+        sample_index: usize,
+    ) {
         let voice_mode = self.parameters.voice_mode.get_value();
         let glide_active = self.parameters.glide_active.get_value();
         let glide_retrigger = self.parameters.glide_retrigger.get_value();
@@ -232,8 +512,8 @@ impl AudioState {
                     .polyphonic_voices
                     .iter()
                     .rev()
-                    .filter(|(k, v)| **k != key && v.key_pressed)
-                    .map(|(key, _)| *key);
+                    .filter(|(_, v)| v.key() != key && v.key_pressed)
+                    .map(|(_, v)| v.key());
 
                 let opt_glide_from_key = match glide_active {
                     GlideActive::Off => None,
@@ -243,19 +523,40 @@ impl AudioState {
                             // Additionally look at voices in release phase. Don't filter out
                             // current voice here, since if is most recently added, we want to
                             // return None later instead of gliding from next one
-                            .chain(self.polyphonic_voices.iter().rev().map(|(key, _)| *key))
+                            .chain(self.polyphonic_voices.values().rev().map(|v| v.key()))
                             .next()
                             .filter(|k| *k != key)
                     }
                 };
 
-                let voice = if let Some(voice) = self.polyphonic_voices.shift_remove(&key) {
-                    // Shift voice to last position (most recently pressed)
-                    self.polyphonic_voices.entry(key).or_insert(voice)
+                // Unless voices may overlap, restart the voice already
+                // playing this key, if any
+                let opt_reused_slot = if self.parameters.overlapping_voices.get_value() {
+                    None
                 } else {
                     self.polyphonic_voices
-                        .entry(key)
-                        .or_insert(Voice::new(MidiPitch::new(key), false))
+                        .iter()
+                        .rev()
+                        .find(|(_, v)| v.key() == key)
+                        .map(|(slot, _)| *slot)
+                };
+
+                self.steal_polyphonic_voices_if_necessary(opt_reused_slot);
+
+                let voice = if let Some(slot) = opt_reused_slot {
+                    let voice = self.polyphonic_voices.shift_remove(&slot).unwrap();
+
+                    // Shift voice to last position (most recently pressed)
+                    self.polyphonic_voices.entry(slot).or_insert(voice)
+                } else {
+                    let slot = self.free_polyphonic_voice_slot(sample_index);
+                    let seed = self.rng.u64(..);
+
+                    self.polyphonic_voices.entry(slot).or_insert(Voice::new(
+                        MidiPitch::new(key),
+                        false,
+                        seed,
+                    ))
                 };
 
                 if let Some(glide_from_key) = opt_glide_from_key {
@@ -285,7 +586,19 @@ impl AudioState {
             }
             VoiceMode::Monophonic => {
                 self.monophonic_pressed_keys.shift_remove(&key);
-                self.monophonic_pressed_keys.insert(key, opt_clap_note_id);
+                self.monophonic_pressed_keys.insert(
+                    key,
+                    MonophonicPressedKey {
+                        velocity,
+                        clap_note_id: opt_clap_note_id,
+                    },
+                );
+
+                // Key doesn't have priority over keys already held, so
+                // don't play it for now
+                if self.monophonic_priority_key() != Some(key) {
+                    return;
+                }
 
                 if glide_active == GlideActive::Off || !self.monophonic_voice.active {
                     self.monophonic_voice.press_key(
@@ -372,96 +685,317 @@ impl AudioState {
         }
     }
 
-    fn key_off(
-        &mut self,
-        key: u8,
-        #[cfg_attr(not(feature = "clap"), allow(unused_variables))] sample_index: usize,
-    ) {
+    fn key_off(&mut self, key: u8, opt_clap_note_id: Option<i32>, sample_index: usize) {
         let voice_mode = self.parameters.voice_mode.get_value();
         let glide_mode = self.parameters.glide_active.get_value();
         let glide_retrigger = self.parameters.glide_retrigger.get_value();
 
         match voice_mode {
             VoiceMode::Polyphonic => {
-                if let Some(voice) = self.polyphonic_voices.get_mut(&key) {
-                    voice.release_key();
+                if let Some(slot) = self.polyphonic_voice_to_release(key, opt_clap_note_id) {
+                    self.polyphonic_voices[&slot].release_key();
                 }
             }
             VoiceMode::Monophonic => {
-                let key_was_most_recently_pressed = self
-                    .monophonic_pressed_keys
-                    .last()
-                    .map(|(k, _)| *k == key)
-                    .unwrap_or(false);
-
-                #[cfg_attr(not(feature = "clap"), allow(unused_variables))]
-                let opt_removed_clap_note_id =
-                    self.monophonic_pressed_keys.shift_remove(&key).flatten();
-
-                if key_was_most_recently_pressed {
-                    if let Some(next_most_recently_pressed_key) =
-                        self.monophonic_pressed_keys.last().map(|(k, _)| *k)
-                    {
-                        // FIXME: maybe previous velocity should be stored in pressed_keys?
-                        let current_velocity = self.monophonic_voice.get_key_velocity();
-
-                        if let GlideActive::Off = glide_mode {
-                            self.monophonic_voice.press_key(
-                                &self.parameters,
-                                current_velocity,
-                                Some(next_most_recently_pressed_key),
-                                None,
-                                opt_removed_clap_note_id,
-                            );
-                        } else {
-                            let glide = VoiceGlide {
-                                to_key: next_most_recently_pressed_key,
-                                time: Self::glide_time(
-                                    &self.parameters,
-                                    self.bpm,
-                                    key,
-                                    next_most_recently_pressed_key,
-                                ),
-                                retrigger_envelopes: glide_retrigger,
-                                retrigger_lfos: glide_retrigger,
-                            };
-
-                            self.monophonic_voice.press_key(
-                                &self.parameters,
-                                current_velocity,
-                                None,
-                                Some(glide),
-                                opt_removed_clap_note_id,
-                            );
-                        };
+                let opt_sounding_key = self.monophonic_priority_key();
 
-                        #[cfg(feature = "clap")]
-                        if let Some(clap_note_id) = opt_removed_clap_note_id {
-                            if let Err(err) = self.clap_ended_notes.push(ClapNoteEnded {
-                                key,
-                                clap_note_id,
-                                sample_index: sample_index as u32,
-                            }) {
-                                ::log::error!(
-                                    "clap_ended_notes buffer full, couldn't push {:?}",
-                                    err
-                                );
-                            }
-                        }
+                let removed = if let Some(removed) = self.monophonic_pressed_keys.shift_remove(&key)
+                {
+                    removed
+                } else {
+                    return;
+                };
+
+                if opt_sounding_key != Some(key) {
+                    // Voice is playing another key, so just end note
+                    self.push_clap_note_ended(key, removed.clap_note_id, sample_index);
+
+                    return;
+                }
+
+                if let Some(next_key) = self.monophonic_priority_key() {
+                    let next = self.monophonic_pressed_keys[&next_key];
+
+                    if let GlideActive::Off = glide_mode {
+                        self.monophonic_voice.press_key(
+                            &self.parameters,
+                            next.velocity,
+                            Some(next_key),
+                            None,
+                            next.clap_note_id,
+                        );
                     } else {
-                        self.monophonic_voice.release_key();
-                    }
+                        let glide = VoiceGlide {
+                            to_key: next_key,
+                            time: Self::glide_time(&self.parameters, self.bpm, key, next_key),
+                            retrigger_envelopes: glide_retrigger,
+                            retrigger_lfos: glide_retrigger,
+                        };
+
+                        self.monophonic_voice.press_key(
+                            &self.parameters,
+                            next.velocity,
+                            None,
+                            Some(glide),
+                            next.clap_note_id,
+                        );
+                    };
+
+                    self.push_clap_note_ended(key, removed.clap_note_id, sample_index);
+                } else {
+                    self.monophonic_voice.release_key();
+                }
+            }
+        }
+    }
+
+    /// Held key that monophonic voice should play according to note priority
+    fn monophonic_priority_key(&self) -> Option<u8> {
+        let keys = self.monophonic_pressed_keys.keys().copied();
+
+        match self.parameters.note_priority.get_value() {
+            NotePriority::Last => keys.last(),
+            NotePriority::Lowest => keys.min(),
+            NotePriority::Highest => keys.max(),
+        }
+    }
+
+    #[cfg_attr(not(feature = "clap"), allow(unused_variables))]
+    fn push_clap_note_ended(
+        &mut self,
+        key: u8,
+        opt_clap_note_id: Option<i32>,
+        sample_index: usize,
+    ) {
+        #[cfg(feature = "clap")]
+        if let Some(clap_note_id) = opt_clap_note_id {
+            // Chord memory notes share clap note id of the key that
+            // triggered them, so only end note once all of them have ended
+            let key = match self.chord_memory.get_clap_note_key(clap_note_id) {
+                Some(_) if self.clap_note_id_in_use(clap_note_id) => return,
+                Some(original_key) => {
+                    self.chord_memory.forget_clap_note(clap_note_id);
+
+                    original_key
                 }
+                None => key,
+            };
+
+            if let Err(err) = self.clap_ended_notes.push(ClapNoteEnded {
+                key,
+                clap_note_id,
+                sample_index: sample_index as u32,
+            }) {
+                ::log::error!("clap_ended_notes buffer full, couldn't push {:?}", err);
+            }
+        }
+    }
+
+    /// Set modulation offset for voices with clap note id, or if it is -1,
+    /// for voices with key. If both are -1, modulate all voices.
+    #[cfg(feature = "clap")]
+    fn set_clap_parameter_modulation(
+        &mut self,
+        parameter: Parameter,
+        clap_note_id: i32,
+        key: i16,
+        amount: f32,
+    ) {
+        // Only parameters that can be modulated per voice are supported
+        if !crate::parameters::is_lfo_target(parameter) {
+            return;
+        }
+
+        let parameter_index = parameter.to_index();
+
+        if (clap_note_id == -1) & (key == -1) {
+            if !self
+                .clap_global_parameter_modulations
+                .set(parameter_index, amount)
+            {
+                ::log::error!("Clap parameter modulation storage full");
+            }
+
+            return;
+        }
+
+        let voices = self
+            .polyphonic_voices
+            .values_mut()
+            .chain(::std::iter::once(&mut self.monophonic_voice).filter(|voice| voice.active));
+
+        for voice in voices {
+            let matches = if clap_note_id == -1 {
+                i16::from(voice.key()) == key
+            } else {
+                voice.clap_note_id == Some(clap_note_id)
+            };
+
+            if matches
+                && !voice
+                    .clap_parameter_modulations
+                    .set(parameter_index, amount)
+            {
+                ::log::error!("Clap parameter modulation storage full");
             }
         }
     }
 
-    #[allow(unused_variables)]
-    fn aftertouch(&mut self, key: u8, velocity: KeyVelocity) {
-        // Disabled for now
-        // if let Some(voice) = self.voices.get_mut(&key) {
-        //     voice.aftertouch(velocity);
-        // }
+    #[cfg(feature = "clap")]
+    fn clap_note_id_in_use(&self, clap_note_id: i32) -> bool {
+        let id = Some(clap_note_id);
+
+        self.polyphonic_voices
+            .values()
+            .any(|v| v.active && v.clap_note_id == id)
+            || (self.monophonic_voice.active && self.monophonic_voice.clap_note_id == id)
+            || self
+                .monophonic_pressed_keys
+                .values()
+                .any(|k| k.clap_note_id == id)
+            || self.arpeggiator.has_clap_note_id(clap_note_id)
+    }
+
+    /// Find slot of pressed voice for key. Prefer the voice started by the
+    /// same CLAP note, and otherwise the one pressed first.
+    #[cfg_attr(not(feature = "clap"), allow(unused_variables))]
+    fn polyphonic_voice_to_release(&self, key: u8, opt_clap_note_id: Option<i32>) -> Option<u8> {
+        let mut candidates = self
+            .polyphonic_voices
+            .iter()
+            .filter(|(_, v)| v.key() == key && v.key_pressed);
+
+        #[cfg(feature = "clap")]
+        if let Some(clap_note_id) = opt_clap_note_id {
+            if let Some((slot, _)) = candidates
+                .clone()
+                .find(|(_, v)| v.clap_note_id == Some(clap_note_id))
+            {
+                return Some(*slot);
+            }
+        }
+
+        candidates.next().map(|(slot, _)| *slot)
+    }
+
+    /// Get unused polyphonic voice slot.
+    ///
+    /// Voice stealing keeps the number of voices not being stolen within
+    /// maximum polyphony, so slots only run out if a very large number of
+    /// voices are stolen before they have faded out. In that case, remove
+    /// the quietest voice being stolen.
+    #[cfg_attr(not(feature = "clap"), allow(unused_variables))]
+    fn free_polyphonic_voice_slot(&mut self, sample_index: usize) -> u8 {
+        if let Some(slot) =
+            (0..NUM_POLYPHONIC_VOICE_SLOTS).find(|slot| !self.polyphonic_voices.contains_key(slot))
+        {
+            return slot;
+        }
+
+        let index = self
+            .polyphonic_voices
+            .values()
+            .enumerate()
+            .filter(|(_, v)| v.is_stolen())
+            .min_by(|(_, a), (_, b)| {
+                a.get_envelope_volume_sum()
+                    .total_cmp(&b.get_envelope_volume_sum())
+            })
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+
+        let (slot, voice) = self.polyphonic_voices.shift_remove_index(index).unwrap();
+
+        #[cfg(feature = "clap")]
+        self.push_clap_note_ended(voice.key(), voice.clap_note_id, sample_index);
+
+        slot
+    }
+
+    /// Make room for a new voice by killing voices as needed. Slot of
+    /// voice that will be restarted for the new key is not counted.
+    fn steal_polyphonic_voices_if_necessary(&mut self, opt_reused_slot: Option<u8>) {
+        let max_polyphony = self.parameters.max_polyphony.get_value();
+        let voice_stealing = self.parameters.voice_stealing.get_value();
+
+        loop {
+            let num_playing_voices = self
+                .polyphonic_voices
+                .iter()
+                .filter(|(slot, v)| Some(**slot) != opt_reused_slot && !v.is_stolen())
+                .count();
+
+            if num_playing_voices < max_polyphony {
+                break;
+            }
+
+            match Self::select_voice_to_steal(
+                &self.polyphonic_voices,
+                opt_reused_slot,
+                voice_stealing,
+            )
+            .and_then(|slot| self.polyphonic_voices.get_mut(&slot))
+            {
+                Some(voice) => voice.steal(),
+                None => break,
+            }
+        }
+    }
+
+    fn select_voice_to_steal(
+        voices: &IndexMap<u8, Voice>,
+        opt_reused_slot: Option<u8>,
+        voice_stealing: VoiceStealing,
+    ) -> Option<u8> {
+        fn select<'a>(
+            mut candidates: impl Iterator<Item = (&'a u8, &'a Voice)>,
+            voice_stealing: VoiceStealing,
+        ) -> Option<u8> {
+            let opt_candidate = match voice_stealing {
+                // Voices are ordered by when their keys were pressed
+                VoiceStealing::Oldest => candidates.next(),
+                VoiceStealing::Quietest => candidates.min_by(|(_, a), (_, b)| {
+                    a.get_envelope_volume_sum()
+                        .total_cmp(&b.get_envelope_volume_sum())
+                }),
+                VoiceStealing::Lowest => candidates.min_by_key(|(_, v)| v.key()),
+                VoiceStealing::Highest => candidates.max_by_key(|(_, v)| v.key()),
+            };
+
+            opt_candidate.map(|(slot, _)| *slot)
+        }
+
+        let candidates = voices
+            .iter()
+            .filter(move |(slot, v)| Some(**slot) != opt_reused_slot && !v.is_stolen());
+
+        // Prefer stealing voices in release stage
+        select(
+            candidates.clone().filter(|(_, v)| !v.key_pressed),
+            voice_stealing,
+        )
+        .or_else(|| select(candidates, voice_stealing))
+    }
+
+    fn aftertouch(&mut self, key: u8, pressure: f32) {
+        for voice in self
+            .polyphonic_voices
+            .values_mut()
+            .filter(|v| v.key() == key)
+        {
+            voice.aftertouch(pressure);
+        }
+        if self.monophonic_voice.key() == key {
+            self.monophonic_voice.aftertouch(pressure);
+        }
+    }
+
+    fn channel_aftertouch(&mut self, pressure: f32) {
+        for voice in self.polyphonic_voices.values_mut() {
+            voice.aftertouch(pressure);
+        }
+
+        self.monophonic_voice.aftertouch(pressure);
     }
 
     #[cfg(test)]
@@ -531,7 +1065,16 @@ impl GlobalPitchBend {
 
 #[cfg(test)]
 mod tests {
-    use super::GlobalPitchBend;
+    use crate::parameters::{
+        chord_memory::ChordMemoryValue, max_polyphony::MaxPolyphonyValue,
+        note_priority::NotePriorityValue, overlapping_voices::OverlappingVoicesValue,
+        voice_mode::VoiceModeValue, voice_stealing::VoiceStealingValue, MasterParameter,
+        ParameterValue,
+    };
+
+    use std::collections::BTreeSet;
+
+    use super::*;
 
     #[test]
     fn test_global_pitch_bend_from_midi() {
@@ -546,4 +1089,448 @@ mod tests {
         pitch_bend.update_from_midi(127, 127);
         assert_eq!(pitch_bend.factor, 1.0);
     }
+
+    #[test]
+    fn test_program_change() {
+        let sync = crate::sync::SyncState::<()>::new(None);
+        let mut audio_state = AudioState::default();
+
+        let send_midi = |audio_state: &mut AudioState, data: [u8; 3]| {
+            audio_state.process_note_event(NoteEventInner::Midi { data }, 0);
+
+            crate::utils::update_audio_parameters(audio_state, &sync);
+        };
+
+        send_midi(&mut audio_state, [0xC0, 5, 0]);
+
+        assert_eq!(sync.patches.get_patch_index(), 5);
+        assert!(sync.patches.has_patch_index_changed_from_audio());
+        assert!(!sync.patches.has_patch_index_changed_from_audio());
+
+        // Patch bank only holds one bank of patches
+        send_midi(&mut audio_state, [0xB0, 32, 1]);
+        send_midi(&mut audio_state, [0xC0, 6, 0]);
+
+        assert_eq!(sync.patches.get_patch_index(), 5);
+        assert!(!sync.patches.has_patch_index_changed_from_audio());
+
+        send_midi(&mut audio_state, [0xB0, 32, 0]);
+        send_midi(&mut audio_state, [0xC0, 6, 0]);
+
+        assert_eq!(sync.patches.get_patch_index(), 6);
+    }
+
+    #[test]
+    fn test_voice_stealing() {
+        fn stolen_keys(audio_state: &AudioState) -> BTreeSet<u8> {
+            audio_state
+                .polyphonic_voices
+                .iter()
+                .filter(|(_, v)| v.is_stolen())
+                .map(|(_, v)| v.key())
+                .collect::<BTreeSet<_>>()
+        }
+
+        for (voice_stealing, expected_stolen_keys) in [
+            (VoiceStealing::Oldest, [60, 62]),
+            (VoiceStealing::Lowest, [60, 62]),
+            (VoiceStealing::Highest, [62, 64]),
+        ] {
+            let mut audio_state = AudioState::default();
+
+            audio_state.set_parameter_from_patch(
+                Parameter::Master(MasterParameter::MaxPolyphony),
+                MaxPolyphonyValue::new_from_audio(2).to_patch(),
+            );
+            audio_state.set_parameter_from_patch(
+                Parameter::Master(MasterParameter::VoiceStealing),
+                VoiceStealingValue::new_from_audio(voice_stealing).to_patch(),
+            );
+
+            for key in [62, 60, 64, 65] {
+                audio_state.key_on(key, KeyVelocity::default(), None, 0);
+            }
+
+            assert_eq!(stolen_keys(&audio_state), expected_stolen_keys.into());
+        }
+    }
+
+    #[test]
+    fn test_voice_stealing_at_max_polyphony() {
+        let mut audio_state = AudioState::default();
+
+        audio_state.set_parameter_from_patch(
+            Parameter::Master(MasterParameter::OverlappingVoices),
+            OverlappingVoicesValue::new_from_audio(true).to_patch(),
+        );
+
+        for _ in 0..200 {
+            audio_state.key_on(60, KeyVelocity::default(), None, 0);
+        }
+
+        // Voices beyond maximum polyphony fade out instead of being removed
+        let num_stolen = audio_state
+            .polyphonic_voices
+            .values()
+            .filter(|v| v.is_stolen())
+            .count();
+
+        assert_eq!(audio_state.polyphonic_voices.len(), 200);
+        assert_eq!(num_stolen, 200 - 128);
+    }
+
+    #[test]
+    fn test_overlapping_voices_random_values_differ() {
+        let mut audio_state = AudioState::default();
+
+        audio_state.set_parameter_from_patch(
+            Parameter::Master(MasterParameter::OverlappingVoices),
+            OverlappingVoicesValue::new_from_audio(true).to_patch(),
+        );
+
+        audio_state.key_on(60, KeyVelocity::default(), None, 0);
+        audio_state.key_on(60, KeyVelocity::default(), None, 0);
+
+        let values = audio_state
+            .polyphonic_voices
+            .values()
+            .map(|v| v.get_random_value())
+            .collect::<Vec<_>>();
+
+        assert_eq!(values.len(), 2);
+        assert_ne!(values[0], values[1]);
+    }
+
+    #[test]
+    fn test_render_more_voices_than_max_polyphony() {
+        let mut audio_state = AudioState::default();
+
+        audio_state.set_parameter_from_patch(
+            Parameter::Master(MasterParameter::OverlappingVoices),
+            OverlappingVoicesValue::new_from_audio(true).to_patch(),
+        );
+
+        for _ in 0..200 {
+            audio_state.key_on(60, KeyVelocity::default(), None, 0);
+        }
+
+        let mut lefts = [0.0f32; 64];
+        let mut rights = [0.0f32; 64];
+
+        gen::process_f32_runtime_select(
+            &mut audio_state,
+            &mut lefts,
+            &mut rights,
+            None,
+            None,
+            0,
+            |_| {},
+        );
+
+        // Stolen voices are still fading out
+        assert!(audio_state.polyphonic_voices.len() > 129);
+        assert!(lefts.iter().chain(rights.iter()).all(|s| s.is_finite()));
+        assert!(lefts.iter().any(|s| *s != 0.0));
+    }
+
+    #[test]
+    fn test_monophonic_note_priority() {
+        for (note_priority, expected_keys) in [
+            (NotePriority::Last, [60, 48, 55]),
+            (NotePriority::Lowest, [48, 48, 55]),
+            (NotePriority::Highest, [60, 55, 55]),
+        ] {
+            let mut audio_state = AudioState::default();
+
+            audio_state.set_parameter_from_patch(
+                Parameter::Master(MasterParameter::VoiceMode),
+                VoiceModeValue::new_from_audio(VoiceMode::Monophonic).to_patch(),
+            );
+            audio_state.set_parameter_from_patch(
+                Parameter::Master(MasterParameter::NotePriority),
+                NotePriorityValue::new_from_audio(note_priority).to_patch(),
+            );
+
+            let mut keys = Vec::new();
+
+            audio_state.key_on(55, KeyVelocity::default(), None, 0);
+            audio_state.key_on(48, KeyVelocity::default(), None, 0);
+            audio_state.key_on(60, KeyVelocity::default(), None, 0);
+
+            keys.push(audio_state.monophonic_voice.key());
+
+            audio_state.key_off(60, None, 0);
+
+            keys.push(audio_state.monophonic_voice.key());
+
+            audio_state.key_off(48, None, 0);
+
+            keys.push(audio_state.monophonic_voice.key());
+
+            assert_eq!(keys, expected_keys);
+            assert!(audio_state.monophonic_voice.key_pressed);
+        }
+    }
+
+    #[test]
+    fn test_overlapping_voices() {
+        fn pressed_states(audio_state: &AudioState) -> Vec<bool> {
+            audio_state
+                .polyphonic_voices
+                .values()
+                .map(|v| v.key_pressed)
+                .collect()
+        }
+
+        for (overlapping_voices, expected_states) in
+            [(false, vec![true]), (true, vec![false, true])]
+        {
+            let mut audio_state = AudioState::default();
+
+            audio_state.set_parameter_from_patch(
+                Parameter::Master(MasterParameter::OverlappingVoices),
+                OverlappingVoicesValue::new_from_audio(overlapping_voices).to_patch(),
+            );
+
+            audio_state.key_on(60, KeyVelocity::default(), None, 0);
+            audio_state.key_off(60, None, 0);
+            audio_state.key_on(60, KeyVelocity::default(), None, 0);
+
+            assert_eq!(pressed_states(&audio_state), expected_states);
+        }
+
+        let mut audio_state = AudioState::default();
+
+        audio_state.set_parameter_from_patch(
+            Parameter::Master(MasterParameter::OverlappingVoices),
+            OverlappingVoicesValue::new_from_audio(true).to_patch(),
+        );
+
+        // Keys pressed twice without release are released in order
+        audio_state.key_on(60, KeyVelocity::default(), None, 0);
+        audio_state.key_on(60, KeyVelocity::default(), None, 0);
+        audio_state.key_off(60, None, 0);
+
+        assert_eq!(pressed_states(&audio_state), vec![false, true]);
+    }
+
+    #[test]
+    fn test_chord_memory() {
+        let mut audio_state = AudioState::default();
+
+        audio_state.set_parameter_from_patch(
+            Parameter::Master(MasterParameter::ChordMemory),
+            ChordMemoryValue::new_from_audio(true).to_patch(),
+        );
+        audio_state.set_chord_shape(ChordShape::parse("0, 4, 7").unwrap());
+
+        audio_state.note_on(60, KeyVelocity::default(), None, 0);
+
+        let pressed_keys = audio_state
+            .polyphonic_voices
+            .values()
+            .filter(|v| v.key_pressed)
+            .map(|v| v.key())
+            .collect::<Vec<_>>();
+
+        assert_eq!(pressed_keys, [60, 64, 67]);
+
+        audio_state.note_off(60, None, 0);
+
+        assert!(audio_state
+            .polyphonic_voices
+            .values()
+            .all(|v| !v.key_pressed));
+    }
+
+    #[test]
+    fn test_parameter_events_applied_at_sample_offset() {
+        let mut audio_state = AudioState::default();
+
+        audio_state.enqueue_parameter_event(ParameterEvent {
+            delta_frames: 2,
+            parameter: Parameter::Master(MasterParameter::VoiceMode),
+            value: VoiceModeValue::new_from_audio(VoiceMode::Monophonic).to_patch(),
+        });
+
+        for sample_index in 0..4 {
+            audio_state.process_events_for_sample(sample_index);
+
+            let expected = if sample_index < 2 {
+                VoiceMode::Polyphonic
+            } else {
+                VoiceMode::Monophonic
+            };
+
+            assert_eq!(audio_state.parameters.voice_mode.get_value(), expected);
+        }
+    }
+
+    #[test]
+    fn test_parameter_events_dont_crowd_out_note_events() {
+        let mut audio_state = AudioState::default();
+
+        for _ in 0..2048 {
+            audio_state.enqueue_parameter_event(ParameterEvent {
+                delta_frames: 0,
+                parameter: Parameter::Master(MasterParameter::Volume),
+                value: 0.5,
+            });
+        }
+
+        audio_state.enqueue_note_event(NoteEvent {
+            delta_frames: 0,
+            event: NoteEventInner::Midi {
+                data: [0b_1001_0000, 60, 100],
+            },
+        });
+
+        audio_state.process_events_for_sample(0);
+
+        assert!(audio_state
+            .polyphonic_voices
+            .values()
+            .any(|v| v.key_pressed));
+    }
+
+    #[test]
+    fn test_operator_outputs_sum_to_main_output() {
+        const NUM_SAMPLES: usize = 64;
+
+        let mut audio_state = AudioState::default();
+
+        audio_state.key_on(60, KeyVelocity::default(), None, 0);
+
+        let mut lefts = [0.0f64; NUM_SAMPLES];
+        let mut rights = [0.0f64; NUM_SAMPLES];
+        let mut operator_buffers = [[[0.0f64; NUM_SAMPLES]; 2]; NUM_OPERATORS];
+
+        let operator_outputs = operator_buffers
+            .each_mut()
+            .map(|[lefts, rights]| (&mut lefts[..], &mut rights[..]));
+
+        gen::process_f64_runtime_select(
+            &mut audio_state,
+            &mut lefts,
+            &mut rights,
+            None,
+            Some(operator_outputs),
+            0,
+            |_| (),
+        );
+
+        assert!(lefts.iter().any(|sample| *sample != 0.0));
+
+        for (channel_index, main_output) in [lefts, rights].into_iter().enumerate() {
+            for (sample_index, sample) in main_output.into_iter().enumerate() {
+                let operator_sum: f64 = operator_buffers
+                    .iter()
+                    .map(|buffers| buffers[channel_index][sample_index])
+                    .sum();
+
+                assert!((operator_sum - sample).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sidechain_phase_modulation() {
+        use crate::parameters::{
+            sidechain_mod_amount::SidechainModAmountValue,
+            sidechain_mod_target::{SidechainModTarget, SidechainModTargetValue},
+        };
+
+        const NUM_SAMPLES: usize = 64;
+
+        let inputs: [f64; NUM_SAMPLES] = ::std::array::from_fn(|i| (i as f64 * 0.3).sin());
+
+        let render = |sidechain_mod_target: SidechainModTarget| {
+            let mut audio_state = AudioState::default();
+
+            audio_state.set_parameter_from_patch(
+                Parameter::Master(MasterParameter::SidechainModTarget),
+                SidechainModTargetValue::new_from_audio(sidechain_mod_target).to_patch(),
+            );
+            audio_state.set_parameter_from_patch(
+                Parameter::Master(MasterParameter::SidechainModAmount),
+                SidechainModAmountValue::new_from_audio(1.0).to_patch(),
+            );
+            audio_state.key_on(60, KeyVelocity::default(), None, 0);
+
+            let mut lefts = [0.0f64; NUM_SAMPLES];
+            let mut rights = [0.0f64; NUM_SAMPLES];
+
+            gen::process_f64_runtime_select(
+                &mut audio_state,
+                &mut lefts,
+                &mut rights,
+                Some((&inputs, &inputs)),
+                None,
+                0,
+                |_| (),
+            );
+
+            lefts
+        };
+
+        let unmodulated = render(SidechainModTarget::None);
+        let modulated = render(SidechainModTarget::Operator(0));
+
+        assert!(unmodulated.iter().any(|sample| *sample != 0.0));
+        assert!(unmodulated
+            .iter()
+            .zip(modulated.iter())
+            .any(|(a, b)| (a - b).abs() > 1e-6));
+    }
+
+    #[cfg(feature = "clap")]
+    #[test]
+    fn test_clap_parameter_modulation() {
+        let parameter = Parameter::Master(MasterParameter::Volume);
+        let index = parameter.to_index();
+
+        let modulations = |audio_state: &AudioState| {
+            audio_state
+                .polyphonic_voices
+                .values()
+                .map(|v| v.clap_parameter_modulations.iter().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+
+        let mut audio_state = AudioState::default();
+
+        audio_state.key_on(60, KeyVelocity::default(), Some(1), 0);
+        audio_state.key_on(64, KeyVelocity::default(), Some(2), 0);
+
+        audio_state.set_clap_parameter_modulation(parameter, 2, -1, 0.5);
+        audio_state.set_clap_parameter_modulation(parameter, -1, 60, 0.25);
+
+        assert_eq!(
+            modulations(&audio_state),
+            [vec![(index, 0.25)], vec![(index, 0.5)]]
+        );
+
+        // Modulation ends with note
+        audio_state.key_off(64, Some(2), 0);
+        audio_state.key_on(64, KeyVelocity::default(), Some(3), 0);
+
+        assert_eq!(modulations(&audio_state), [vec![(index, 0.25)], vec![]]);
+
+        // Parameters that can't be modulated per voice are ignored
+        audio_state.set_clap_parameter_modulation(
+            Parameter::Master(MasterParameter::VoiceMode),
+            -1,
+            -1,
+            0.5,
+        );
+        audio_state.set_clap_parameter_modulation(parameter, -1, -1, 0.1);
+
+        assert_eq!(
+            audio_state
+                .clap_global_parameter_modulations
+                .iter()
+                .collect::<Vec<_>>(),
+            [(index, 0.1)]
+        );
+    }
 }
@@ -5,6 +5,7 @@ mod lfo_frequency_free;
 mod lfo_target;
 mod master_frequency;
 mod master_volume;
+mod mod_matrix_amount;
 mod operator_active;
 mod operator_frequency_fine;
 mod operator_frequency_free;
@@ -16,7 +17,14 @@ mod operator_volume;
 
 use array_init::array_init;
 
-use crate::common::{SampleRate, NUM_LFOS, NUM_OPERATORS};
+use crate::common::{SampleRate, NUM_LFOS, NUM_MOD_MATRIX_SLOTS, NUM_OPERATORS};
+use crate::parameters::arpeggiator_gate::ArpeggiatorGateValue;
+use crate::parameters::arpeggiator_latch::ArpeggiatorLatchValue;
+use crate::parameters::arpeggiator_mode::ArpeggiatorModeValue;
+use crate::parameters::arpeggiator_octaves::ArpeggiatorOctavesValue;
+use crate::parameters::arpeggiator_rate::ArpeggiatorRateValue;
+use crate::parameters::arpeggiator_swing::ArpeggiatorSwingValue;
+use crate::parameters::chord_memory::ChordMemoryValue;
 use crate::parameters::glide_active::GlideActiveValue;
 use crate::parameters::glide_bpm_sync::GlideBpmSyncValue;
 use crate::parameters::glide_mode::GlideModeValue;
@@ -25,8 +33,15 @@ use crate::parameters::glide_time::GlideTimeValue;
 use crate::parameters::master_pitch_bend_range::{
     MasterPitchBendRangeDownValue, MasterPitchBendRangeUpValue,
 };
+use crate::parameters::max_polyphony::MaxPolyphonyValue;
+use crate::parameters::note_priority::NotePriorityValue;
+use crate::parameters::overlapping_voices::OverlappingVoicesValue;
+use crate::parameters::sidechain_mod_amount::SidechainModAmountValue;
+use crate::parameters::sidechain_mod_target::SidechainModTargetValue;
+use crate::parameters::velocity_curve::VelocityCurveValue;
 use crate::parameters::velocity_sensitivity::VelocitySensitivityValue;
 use crate::parameters::voice_mode::VoiceModeValue;
+use crate::parameters::voice_stealing::VoiceStealingValue;
 use crate::parameters::*;
 
 use self::common::{AudioParameter, InterpolatableAudioParameter, SimpleAudioParameter};
@@ -36,6 +51,7 @@ use self::lfo_frequency_free::LfoFrequencyFreeAudioParameter;
 use self::lfo_target::LfoTargetAudioParameter;
 use self::master_frequency::MasterFrequencyAudioParameter;
 use self::master_volume::MasterVolumeAudioParameter;
+use self::mod_matrix_amount::ModMatrixAmountAudioParameter;
 use self::operator_frequency_fine::OperatorFrequencyFineAudioParameter;
 use self::operator_frequency_free::OperatorFrequencyFreeAudioParameter;
 use self::operator_mix::OperatorMixAudioParameter;
@@ -75,8 +91,23 @@ pub struct AudioParameters {
     pub glide_bpm_sync: SimpleAudioParameter<GlideBpmSyncValue>,
     pub glide_mode: SimpleAudioParameter<GlideModeValue>,
     pub glide_retrigger: SimpleAudioParameter<GlideRetriggerValue>,
+    pub velocity_curve: SimpleAudioParameter<VelocityCurveValue>,
+    pub max_polyphony: SimpleAudioParameter<MaxPolyphonyValue>,
+    pub voice_stealing: SimpleAudioParameter<VoiceStealingValue>,
+    pub note_priority: SimpleAudioParameter<NotePriorityValue>,
+    pub overlapping_voices: SimpleAudioParameter<OverlappingVoicesValue>,
+    pub arpeggiator_mode: SimpleAudioParameter<ArpeggiatorModeValue>,
+    pub arpeggiator_octaves: SimpleAudioParameter<ArpeggiatorOctavesValue>,
+    pub arpeggiator_rate: SimpleAudioParameter<ArpeggiatorRateValue>,
+    pub arpeggiator_swing: SimpleAudioParameter<ArpeggiatorSwingValue>,
+    pub arpeggiator_gate: SimpleAudioParameter<ArpeggiatorGateValue>,
+    pub arpeggiator_latch: SimpleAudioParameter<ArpeggiatorLatchValue>,
+    pub chord_memory: SimpleAudioParameter<ChordMemoryValue>,
+    pub sidechain_mod_target: SimpleAudioParameter<SidechainModTargetValue>,
+    pub sidechain_mod_amount: InterpolatableAudioParameter<SidechainModAmountValue>,
     pub operators: [OperatorAudioParameters; NUM_OPERATORS],
     pub lfos: [LfoAudioParameters; NUM_LFOS],
+    pub mod_matrix: [ModMatrixSlotAudioParameters; NUM_MOD_MATRIX_SLOTS],
 }
 
 impl Default for AudioParameters {
@@ -93,8 +124,23 @@ impl Default for AudioParameters {
             glide_bpm_sync: Default::default(),
             glide_mode: Default::default(),
             glide_retrigger: Default::default(),
+            velocity_curve: Default::default(),
+            max_polyphony: Default::default(),
+            voice_stealing: Default::default(),
+            note_priority: Default::default(),
+            overlapping_voices: Default::default(),
+            arpeggiator_mode: Default::default(),
+            arpeggiator_octaves: Default::default(),
+            arpeggiator_rate: Default::default(),
+            arpeggiator_swing: Default::default(),
+            arpeggiator_gate: Default::default(),
+            arpeggiator_latch: Default::default(),
+            chord_memory: Default::default(),
+            sidechain_mod_target: Default::default(),
+            sidechain_mod_amount: Default::default(),
             operators: array_init(OperatorAudioParameters::new),
             lfos: array_init(LfoAudioParameters::new),
+            mod_matrix: Default::default(),
         }
     }
 }
@@ -122,6 +168,24 @@ macro_rules! impl_patch_interaction {
                     MasterParameter::GlideBpmSync => $f(&mut self.glide_bpm_sync, input),
                     MasterParameter::GlideMode => $f(&mut self.glide_mode, input),
                     MasterParameter::GlideRetrigger => $f(&mut self.glide_retrigger, input),
+                    MasterParameter::VelocityCurve => $f(&mut self.velocity_curve, input),
+                    MasterParameter::MaxPolyphony => $f(&mut self.max_polyphony, input),
+                    MasterParameter::VoiceStealing => $f(&mut self.voice_stealing, input),
+                    MasterParameter::NotePriority => $f(&mut self.note_priority, input),
+                    MasterParameter::OverlappingVoices => $f(&mut self.overlapping_voices, input),
+                    MasterParameter::ArpeggiatorMode => $f(&mut self.arpeggiator_mode, input),
+                    MasterParameter::ArpeggiatorOctaves => $f(&mut self.arpeggiator_octaves, input),
+                    MasterParameter::ArpeggiatorRate => $f(&mut self.arpeggiator_rate, input),
+                    MasterParameter::ArpeggiatorSwing => $f(&mut self.arpeggiator_swing, input),
+                    MasterParameter::ArpeggiatorGate => $f(&mut self.arpeggiator_gate, input),
+                    MasterParameter::ArpeggiatorLatch => $f(&mut self.arpeggiator_latch, input),
+                    MasterParameter::ChordMemory => $f(&mut self.chord_memory, input),
+                    MasterParameter::SidechainModTarget => {
+                        $f(&mut self.sidechain_mod_target, input)
+                    }
+                    MasterParameter::SidechainModAmount => {
+                        $f(&mut self.sidechain_mod_amount, input)
+                    }
                 },
                 Parameter::Operator(index, p) => {
                     use OperatorParameter::*;
@@ -182,6 +246,17 @@ macro_rules! impl_patch_interaction {
                         LfoParameter::KeySync => $f(&mut lfo.key_sync, input),
                     }
                 }
+                // Macros are applied to their targets in sync code
+                Parameter::Macro(_) => None,
+                Parameter::ModMatrix(index, p) => {
+                    let slot = &mut self.mod_matrix[index as usize];
+
+                    match p {
+                        ModMatrixParameter::Source => $f(&mut slot.source, input),
+                        ModMatrixParameter::Target => $f(&mut slot.target, input),
+                        ModMatrixParameter::Amount => $f(&mut slot.amount, input),
+                    }
+                }
             }
         }
     };
@@ -211,6 +286,7 @@ impl AudioParameters {
         self.master_frequency.advance_one_sample(sample_rate);
         self.volume_velocity_sensitivity
             .advance_one_sample(sample_rate);
+        self.sidechain_mod_amount.advance_one_sample(sample_rate);
 
         for operator in self.operators.iter_mut() {
             operator.advance_one_sample(sample_rate);
@@ -219,6 +295,10 @@ impl AudioParameters {
         for lfo in self.lfos.iter_mut() {
             lfo.advance_one_sample(sample_rate);
         }
+
+        for slot in self.mod_matrix.iter_mut() {
+            slot.advance_one_sample(sample_rate);
+        }
     }
 }
 
@@ -347,6 +427,21 @@ impl LfoAudioParameters {
     }
 }
 
+#[derive(Default)]
+pub struct ModMatrixSlotAudioParameters {
+    pub source: SimpleAudioParameter<ModMatrixSourceValue>,
+    pub target: SimpleAudioParameter<ModMatrixTargetValue>,
+    pub amount: ModMatrixAmountAudioParameter,
+}
+
+impl ModMatrixSlotAudioParameters {
+    fn advance_one_sample(&mut self, sample_rate: SampleRate) {
+        self.source.advance_one_sample(sample_rate);
+        self.target.advance_one_sample(sample_rate);
+        self.amount.advance_one_sample(sample_rate);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use assert_approx_eq::assert_approx_eq;
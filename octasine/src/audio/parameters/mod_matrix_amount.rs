@@ -0,0 +1,45 @@
+use crate::audio::interpolation::{InterpolationDuration, Interpolator};
+use crate::common::SampleRate;
+use crate::parameters::{ModMatrixAmountValue, ParameterValue};
+
+use super::common::AudioParameter;
+
+/// Interpolates patch value, since Interpolator doesn't support negative
+/// values
+#[derive(Debug, Clone)]
+pub struct ModMatrixAmountAudioParameter(Interpolator);
+
+impl Default for ModMatrixAmountAudioParameter {
+    fn default() -> Self {
+        Self(Interpolator::new(
+            ModMatrixAmountValue::default().to_patch(),
+            InterpolationDuration::approx_1ms(),
+        ))
+    }
+}
+
+impl AudioParameter for ModMatrixAmountAudioParameter {
+    type ParameterValue = ModMatrixAmountValue;
+
+    fn advance_one_sample(&mut self, sample_rate: SampleRate) {
+        self.0.advance_one_sample(sample_rate, &mut |_| ())
+    }
+    fn get_value(&self) -> <Self::ParameterValue as ParameterValue>::Value {
+        Self::ParameterValue::new_from_patch(self.0.get_value()).get()
+    }
+    fn set_from_patch(&mut self, value: f32) {
+        self.0.set_value(value)
+    }
+    fn get_value_with_lfo_addition(
+        &mut self,
+        lfo_addition: Option<f32>,
+    ) -> <Self::ParameterValue as ParameterValue>::Value {
+        if let Some(lfo_addition) = lfo_addition {
+            let patch_value = (self.0.get_value() + lfo_addition).clamp(0.0, 1.0);
+
+            Self::ParameterValue::new_from_patch(patch_value).get()
+        } else {
+            self.get_value()
+        }
+    }
+}
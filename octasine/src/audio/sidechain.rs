@@ -0,0 +1,103 @@
+use std::ops::Range;
+
+use crate::common::TimePerSample;
+
+use super::gen::OutputSample;
+
+const FOLLOWER_ATTACK_SECONDS: f64 = 0.005;
+const FOLLOWER_RELEASE_SECONDS: f64 = 0.15;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SidechainFrame {
+    pub left: f64,
+    pub right: f64,
+    /// Amplitude follower level after this frame, in range 0.0 to 1.0
+    pub follower_level: f64,
+}
+
+/// Stereo audio input from host, used as phase modulation source and as
+/// amplitude follower modulation source
+#[derive(Debug, Default)]
+pub struct Sidechain {
+    /// Frames for the samples currently being processed (one or two,
+    /// depending on SIMD instruction width)
+    frames: [SidechainFrame; 2],
+    follower_level: f64,
+}
+
+impl Sidechain {
+    /// Store input frames for range of samples about to be processed and
+    /// advance amplitude follower. Missing input is treated as silence.
+    pub fn set_frames<T: OutputSample>(
+        &mut self,
+        opt_inputs: Option<(&[T], &[T])>,
+        range: Range<usize>,
+        time_per_sample: TimePerSample,
+    ) {
+        for (frame, sample_index) in self.frames.iter_mut().zip(range) {
+            let (left, right) = match opt_inputs {
+                Some((lefts, rights)) => {
+                    (lefts[sample_index].to_f64(), rights[sample_index].to_f64())
+                }
+                None => (0.0, 0.0),
+            };
+
+            let level = left.abs().max(right.abs()).min(1.0);
+
+            let time_constant = if level > self.follower_level {
+                FOLLOWER_ATTACK_SECONDS
+            } else {
+                FOLLOWER_RELEASE_SECONDS
+            };
+
+            self.follower_level +=
+                (level - self.follower_level) * (1.0 - (-time_per_sample.0 / time_constant).exp());
+
+            *frame = SidechainFrame {
+                left,
+                right,
+                follower_level: self.follower_level,
+            };
+        }
+    }
+
+    pub fn get_frame(&self, sample_index: usize) -> SidechainFrame {
+        self.frames[sample_index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidechain_follower() {
+        let time_per_sample = TimePerSample(1.0 / 44100.0);
+
+        let mut sidechain = Sidechain::default();
+
+        let ones = [1.0f32; 4410];
+
+        for sample_index in 0..ones.len() {
+            sidechain.set_frames(
+                Some((&ones[..], &ones[..])),
+                sample_index..sample_index + 1,
+                time_per_sample,
+            );
+        }
+
+        let frame = sidechain.get_frame(0);
+
+        assert_eq!(frame.left, 1.0);
+        assert!(frame.follower_level > 0.99);
+
+        for _ in 0..44100 {
+            sidechain.set_frames::<f32>(None, 0..1, time_per_sample);
+        }
+
+        let frame = sidechain.get_frame(0);
+
+        assert_eq!(frame.left, 0.0);
+        assert!(frame.follower_level < 0.01);
+    }
+}
@@ -144,6 +144,10 @@ impl VoiceOperatorVolumeEnvelope {
         self.last_volume
     }
 
+    pub fn get_last_volume(&self) -> f32 {
+        self.last_volume
+    }
+
     fn duration_since_stage_change(&self) -> f64 {
         self.duration.0 - self.duration_at_stage_change.0
     }
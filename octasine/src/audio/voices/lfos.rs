@@ -4,7 +4,10 @@ use crate::{
         parameters::{common::AudioParameter, LfoAudioParameters},
     },
     common::*,
-    parameters::{lfo_mode::LfoMode, lfo_shape::LfoShape},
+    parameters::{
+        lfo_mode::LfoMode,
+        lfo_shape::{LfoRandomValues, LfoShape},
+    },
 };
 
 const INTERPOLATION_DURATION_SHORT: InterpolationDuration = InterpolationDuration::exactly_10ms();
@@ -28,24 +31,38 @@ pub struct VoiceLfo {
     current_shape: Option<LfoShape>,
     phase: Phase,
     last_value: f32,
+    /// Last value with amount applied
+    last_output: f32,
     sample_rate: SampleRate,
+    /// Seeded per voice LFO so that renders are repeatable
+    rng: fastrand::Rng,
+    random_values: LfoRandomValues,
 }
 
 impl Default for VoiceLfo {
     fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl VoiceLfo {
+    pub fn new(seed: u64) -> Self {
         let sample_rate = SampleRate::default();
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let random_values = LfoRandomValues::new(&mut rng);
 
         Self {
             stage: LfoStage::Stopped,
             current_shape: None,
             phase: Phase(0.0),
             last_value: 0.0,
+            last_output: 0.0,
             sample_rate,
+            rng,
+            random_values,
         }
     }
-}
 
-impl VoiceLfo {
     pub fn advance_one_sample(
         &mut self,
         sample_rate: SampleRate,
@@ -82,6 +99,12 @@ impl VoiceLfo {
 
         self.phase.0 = new_phase.fract();
 
+        if new_phase >= 1.0 {
+            self.random_values.advance_cycle(&mut self.rng);
+        }
+
+        self.random_values.advance_sample(&mut self.rng);
+
         match self.stage {
             LfoStage::Interpolate {
                 from_value,
@@ -121,7 +144,8 @@ impl VoiceLfo {
                     } else {
                         match (self.current_shape, shape) {
                             (Some(LfoShape::Sine), LfoShape::Sine)
-                            | (Some(LfoShape::ReverseSine), LfoShape::ReverseSine) => {}
+                            | (Some(LfoShape::ReverseSine), LfoShape::ReverseSine)
+                            | (Some(LfoShape::SmoothRandom), LfoShape::SmoothRandom) => {}
                             _ => {
                                 self.current_shape = Some(shape);
 
@@ -161,9 +185,10 @@ impl VoiceLfo {
             } => {
                 let progress = samples_done as f32 / samples_to_interpolate as f32;
 
-                progress * shape.calculate(self.phase) + (1.0 - progress) * from_value
+                progress * shape.calculate(self.phase, self.random_values)
+                    + (1.0 - progress) * from_value
             }
-            LfoStage::Running => shape.calculate(self.phase),
+            LfoStage::Running => shape.calculate(self.phase, self.random_values),
             LfoStage::OneshotComplete => self.last_value,
             LfoStage::Stopped => {
                 unreachable!()
@@ -171,17 +196,23 @@ impl VoiceLfo {
         };
 
         self.last_value = value;
+        self.last_output = value * amount;
+
+        self.last_output
+    }
 
-        value * amount
+    pub fn get_last_output(&self) -> f32 {
+        self.last_output
     }
 
     pub fn restart(&mut self, parameters: &LfoAudioParameters) {
         self.phase = if parameters.key_sync.get_value() {
             Phase(0.0)
         } else {
-            Phase(fastrand::f64())
+            Phase(self.rng.f64())
         };
         self.current_shape = None;
+        self.random_values = LfoRandomValues::new(&mut self.rng);
 
         match self.stage {
             LfoStage::Stopped => {
@@ -211,6 +242,7 @@ impl VoiceLfo {
     pub fn envelope_ended(&mut self) {
         self.stage = LfoStage::Stopped;
         self.last_value = 0.0;
+        self.last_output = 0.0;
     }
 
     pub fn is_stopped(&self) -> bool {
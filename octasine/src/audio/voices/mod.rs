@@ -17,6 +17,9 @@ use super::{
 const VELOCITY_INTERPOLATION_DURATION: InterpolationDuration =
     InterpolationDuration::exactly_10ms();
 
+#[cfg(feature = "clap")]
+const MAX_CLAP_PARAMETER_MODULATIONS: usize = 32;
+
 #[derive(Debug, Copy, Clone)]
 pub struct VoiceDuration(pub f64);
 
@@ -64,6 +67,39 @@ impl MidiPitch {
     }
 }
 
+/// Non-destructive parameter modulation offsets from clap host, in patch
+/// value units, by parameter index
+#[cfg(feature = "clap")]
+#[derive(Debug, Clone, Default)]
+pub struct ClapParameterModulations(arrayvec::ArrayVec<(u8, f32), MAX_CLAP_PARAMETER_MODULATIONS>);
+
+#[cfg(feature = "clap")]
+impl ClapParameterModulations {
+    /// Replace offset for parameter. Returns false if there is no room for it.
+    pub fn set(&mut self, parameter_index: u8, amount: f32) -> bool {
+        match self.0.iter().position(|(i, _)| *i == parameter_index) {
+            Some(i) if amount == 0.0 => {
+                self.0.swap_remove(i);
+            }
+            Some(i) => {
+                self.0[i].1 = amount;
+            }
+            None if amount == 0.0 => (),
+            None => return self.0.try_push((parameter_index, amount)).is_ok(),
+        }
+
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, f32)> + '_ {
+        self.0.iter().copied()
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct VoiceGlide {
     pub to_key: u8,
@@ -94,23 +130,37 @@ pub struct Voice {
     pub active: bool,
     pub midi_pitch: MidiPitch,
     pub key_pressed: bool,
+    /// Voice is being killed to free up room for another voice
+    stolen: bool,
     pub pitch_interpolator: Interpolator,
     key_velocity_interpolator: Interpolator,
+    aftertouch_interpolator: Interpolator,
+    /// Bipolar random value drawn on each key press, used as modulation
+    /// matrix source
+    random_value: f32,
+    rng: fastrand::Rng,
     pub operators: [VoiceOperator; NUM_OPERATORS],
     pub lfos: [VoiceLfo; NUM_LFOS],
     #[cfg(feature = "clap")]
     pub clap_note_id: Option<i32>,
+    /// Modulation targeting this voice by clap note id or key
+    #[cfg(feature = "clap")]
+    pub clap_parameter_modulations: ClapParameterModulations,
 }
 
 impl Voice {
-    pub fn new(midi_pitch: MidiPitch, is_monophonic: bool) -> Self {
+    /// Voices playing the same key at once (overlapping voices) need
+    /// different seeds to not produce identical random sequences
+    pub fn new(midi_pitch: MidiPitch, is_monophonic: bool, seed: u64) -> Self {
         let operators = [VoiceOperator::default(); NUM_OPERATORS];
+        let mut rng = fastrand::Rng::with_seed(seed);
 
         Self {
             is_monophonic,
             active: false,
             midi_pitch,
             key_pressed: false,
+            stolen: false,
             pitch_interpolator: Interpolator::new(
                 midi_pitch.frequency_factor as f32,
                 InterpolationDuration::exactly_1s(),
@@ -119,16 +169,23 @@ impl Voice {
                 KeyVelocity::default().0,
                 VELOCITY_INTERPOLATION_DURATION,
             ),
+            aftertouch_interpolator: Interpolator::new(0.0, VELOCITY_INTERPOLATION_DURATION),
+            random_value: 0.0,
+            lfos: array_init(|_| VoiceLfo::new(rng.u64(..))),
+            rng,
             operators,
-            lfos: array_init(|_| VoiceLfo::default()),
             #[cfg(feature = "clap")]
             clap_note_id: None,
+            #[cfg(feature = "clap")]
+            clap_parameter_modulations: Default::default(),
         }
     }
 
     pub fn advance_interpolators_one_sample(&mut self, sample_rate: SampleRate) {
         self.key_velocity_interpolator
             .advance_one_sample(sample_rate, &mut |_| ());
+        self.aftertouch_interpolator
+            .advance_one_sample(sample_rate, &mut |_| ());
         self.pitch_interpolator
             .advance_one_sample(sample_rate, &mut |_| ());
     }
@@ -137,6 +194,14 @@ impl Voice {
         KeyVelocity(self.key_velocity_interpolator.get_value())
     }
 
+    pub fn get_aftertouch(&self) -> f32 {
+        self.aftertouch_interpolator.get_value()
+    }
+
+    pub fn get_random_value(&self) -> f32 {
+        self.random_value
+    }
+
     #[inline]
     pub fn press_key(
         &mut self,
@@ -149,9 +214,12 @@ impl Voice {
         if self.active {
             self.key_velocity_interpolator.set_value(velocity.0)
         } else {
-            self.key_velocity_interpolator.force_set_value(velocity.0)
+            self.key_velocity_interpolator.force_set_value(velocity.0);
+            self.aftertouch_interpolator.force_set_value(0.0);
         }
 
+        self.random_value = self.rng.f32() * 2.0 - 1.0;
+
         if let Some(key) = initial_key {
             self.change_pitch(key, None);
         }
@@ -185,10 +253,16 @@ impl Voice {
 
         #[cfg(feature = "clap")]
         {
+            // Per-note modulation ends with the note
+            if opt_clap_note_id.is_none() | (opt_clap_note_id != self.clap_note_id) {
+                self.clap_parameter_modulations.clear();
+            }
+
             self.clap_note_id = opt_clap_note_id;
         }
 
         self.key_pressed = true;
+        self.stolen = false;
         self.active = true;
     }
 
@@ -207,8 +281,8 @@ impl Voice {
         }
     }
 
-    pub fn aftertouch(&mut self, velocity: KeyVelocity) {
-        self.key_velocity_interpolator.set_value(velocity.0)
+    pub fn aftertouch(&mut self, pressure: f32) {
+        self.aftertouch_interpolator.set_value(pressure)
     }
 
     pub fn key(&self) -> u8 {
@@ -226,6 +300,26 @@ impl Voice {
         }
     }
 
+    /// Quickly fade out voice
+    pub fn steal(&mut self) {
+        self.key_pressed = false;
+        self.stolen = true;
+
+        self.kill_envelopes();
+    }
+
+    pub fn is_stolen(&self) -> bool {
+        self.stolen
+    }
+
+    /// Sum of operator envelope volumes, used to find quiet voices
+    pub fn get_envelope_volume_sum(&self) -> f32 {
+        self.operators
+            .iter()
+            .map(|operator| operator.volume_envelope.get_last_volume())
+            .sum()
+    }
+
     #[inline]
     pub fn deactivate_if_envelopes_ended(&mut self) -> bool {
         let all_envelopes_ended = self
@@ -1,7 +1,9 @@
-use crate::parameters::ParameterKey;
+use crate::parameters::{Parameter, ParameterKey};
 
 pub const NUM_OPERATORS: usize = 4;
 pub const NUM_LFOS: usize = 4;
+pub const NUM_MOD_MATRIX_SLOTS: usize = 8;
+pub const NUM_MACROS: usize = 8;
 
 pub const OPERATOR_MOD_INDEX_STEPS: [f32; 16] = [
     0.0, 0.01, 0.1, 0.2, 0.5, 1.0, 2.0, 3.0, 5.0, 10.0, 20.0, 35.0, 50.0, 75.0, 100.0, 1000.0,
@@ -77,6 +79,16 @@ pub struct NoteEvent {
     pub event: NoteEventInner,
 }
 
+/// Parameter change to apply at a sample offset. Kept separate from note
+/// events so that dense automation can't crowd out note offs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParameterEvent {
+    pub delta_frames: u32,
+    pub parameter: Parameter,
+    /// Patch value
+    pub value: f32,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum NoteEventInner {
     Midi {
@@ -89,6 +101,7 @@ pub enum NoteEventInner {
     },
     ClapNoteOff {
         key: u8,
+        clap_note_id: i32,
     },
     ClapNotePressure {
         key: u8,
@@ -98,6 +111,15 @@ pub enum NoteEventInner {
     ClapBpm {
         bpm: BeatsPerMinute,
     },
+    /// Non-destructive parameter modulation. Note id and key are -1 when
+    /// not targeting specific voices.
+    #[cfg(feature = "clap")]
+    ClapParameterModulation {
+        parameter: Parameter,
+        clap_note_id: i32,
+        key: i16,
+        amount: f64,
+    },
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -106,5 +128,7 @@ pub enum EventToHost {
     Automate(ParameterKey, f32),
     EndAutomating(ParameterKey),
     RescanValues,
+    /// Parameter names changed, e.g., due to macro target changes
+    RescanInfo,
     StateChanged,
 }
@@ -1,13 +1,17 @@
 use std::borrow::Cow;
 
+use iced_aw::native::ContextMenu;
 use iced_baseview::{
+    alignment::Horizontal,
     widget::Column,
     widget::Row,
     widget::Space,
-    widget::{tooltip::Position, Container, Tooltip},
+    widget::{tooltip::Position, Button, Container, Text, Tooltip},
     Element, Length,
 };
 
+use crate::parameters::{midi_learn::MidiLearnScope, WrappedParameter};
+
 use super::LINE_HEIGHT;
 
 use super::{
@@ -92,3 +96,50 @@ pub fn tooltip<'a>(
         .style(ContainerStyle::Tooltip)
         .padding(theme.tooltip_padding())
 }
+
+/// Show MIDI learn and randomizer lock menu for parameter when contents are
+/// right-clicked
+pub fn parameter_context_menu<'a>(
+    theme: &Theme,
+    parameter: WrappedParameter,
+    contents: impl Into<Element<'a, Message, Theme>>,
+) -> Element<'a, Message, Theme> {
+    let theme = *theme;
+
+    ContextMenu::new(contents, move || {
+        let button = |text, message| {
+            Button::new(
+                Text::new(text)
+                    .font(theme.font_regular())
+                    .height(Length::Fixed(LINE_HEIGHT.into()))
+                    .horizontal_alignment(Horizontal::Center),
+            )
+            .width(Length::Fill)
+            .padding(theme.button_padding())
+            .on_press(message)
+        };
+
+        Container::new(
+            Column::new()
+                .spacing(LINE_HEIGHT / 4)
+                .width(Length::Fixed(f32::from(LINE_HEIGHT * 10)))
+                .push(button(
+                    "LEARN MIDI CC",
+                    Message::MidiLearn(parameter, MidiLearnScope::Patch),
+                ))
+                .push(button(
+                    "LEARN MIDI CC (ALL PATCHES)",
+                    Message::MidiLearn(parameter, MidiLearnScope::Global),
+                ))
+                .push(button("CLEAR MIDI CC", Message::MidiLearnClear(parameter)))
+                .push(button(
+                    "TOGGLE RANDOMIZER LOCK",
+                    Message::ToggleRandomizerLock(parameter),
+                )),
+        )
+        .padding(LINE_HEIGHT / 4)
+        .style(ContainerStyle::L3)
+        .into()
+    })
+    .into()
+}
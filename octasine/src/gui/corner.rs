@@ -27,7 +27,10 @@ use super::{
     boolean_button::{
         glide_bpm_sync_button, glide_mode_button, glide_retrigger_button, BooleanButton,
     },
-    common::{container_l1, container_l2, container_l3, space_l3, tooltip, triple_container},
+    common::{
+        container_l1, container_l2, container_l3, parameter_context_menu, space_l3, tooltip,
+        triple_container,
+    },
     knob::{self, OctaSineKnob},
     mod_matrix::ModulationMatrix,
     patch_picker::PatchPicker,
@@ -212,6 +215,11 @@ impl CornerWidgets {
             .text_size(FONT_SIZE)
             .padding(theme.picklist_padding())
             .width(Length::Fixed(f32::from(LINE_HEIGHT * 3)));
+            let glide_active_picker = parameter_context_menu(
+                theme,
+                Parameter::Master(MasterParameter::GlideActive).into(),
+                glide_active_picker,
+            );
 
             Container::new(
                 Column::new()
@@ -20,7 +20,7 @@ use crate::parameters::{
 };
 use crate::sync::GuiSyncHandle;
 
-use super::common::tooltip;
+use super::common::{parameter_context_menu, tooltip};
 use super::style::knob::KnobStyle;
 use super::style::Theme;
 use super::value_text::ValueText;
@@ -472,7 +472,7 @@ where
                 .align_items(Alignment::Center)
                 .push(title)
                 .push(Space::with_height(Length::Fixed(LINE_HEIGHT.into())))
-                .push(knob)
+                .push(parameter_context_menu(theme, parameter, knob))
                 .push(Space::with_height(Length::Fixed(LINE_HEIGHT.into())))
                 .push(self.value_text.view(theme)),
         )
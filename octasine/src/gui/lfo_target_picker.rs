@@ -8,7 +8,7 @@ use crate::parameters::{
     WrappedParameter,
 };
 
-use super::{style::Theme, GuiSyncHandle, Message, FONT_SIZE};
+use super::{common::parameter_context_menu, style::Theme, GuiSyncHandle, Message, FONT_SIZE};
 
 #[derive(Clone, PartialEq, Eq)]
 struct LfoTarget {
@@ -80,7 +80,7 @@ impl LfoTargetPicker {
         let lfo_index = self.lfo_index;
         let parameter = self.parameter;
 
-        PickList::new(
+        let picker = PickList::new(
             &self.options[..],
             Some(self.options[self.selected].clone()),
             move |option| {
@@ -98,7 +98,8 @@ impl LfoTargetPicker {
         .font(theme.font_regular())
         .text_size(FONT_SIZE)
         .padding(theme.picklist_padding())
-        .width(Length::Fill)
-        .into()
+        .width(Length::Fill);
+
+        parameter_context_menu(theme, parameter, picker)
     }
 }
@@ -10,6 +10,7 @@ mod mod_target_picker;
 mod operator;
 mod patch_picker;
 pub mod style;
+mod undo;
 mod value_text;
 mod wave_display;
 mod wave_picker;
@@ -25,6 +26,7 @@ use iced_aw::native::{Card, Modal};
 use iced_baseview::alignment::Horizontal;
 use iced_baseview::command::Action;
 use iced_baseview::widget::{Button, PickList, Text};
+use iced_baseview::{event, keyboard, subscription, Event};
 use iced_baseview::{executor, window::WindowSubs, Application, Command, Subscription};
 use iced_baseview::{
     widget::Column, widget::Container, widget::Row, widget::Space, window::WindowQueue, Element,
@@ -32,7 +34,11 @@ use iced_baseview::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::common::NUM_OPERATORS;
+use crate::common::{NUM_MACROS, NUM_OPERATORS};
+use crate::parameters::chord_memory::{ChordMemoryValue, ChordShape};
+use crate::parameters::macros::MacroTarget;
+use crate::parameters::midi_learn::MidiLearnScope;
+use crate::parameters::velocity_curve::{CustomVelocityCurve, VelocityCurve, VelocityCurveValue};
 use crate::parameters::*;
 use crate::sync::GuiSyncHandle;
 
@@ -44,6 +50,7 @@ use style::Theme;
 use self::corner::CornerWidgets;
 use self::operator::ModTargetPicker;
 use self::style::container::ContainerStyle;
+use self::undo::{UndoEntry, UndoHistory};
 
 use crate::settings::Settings;
 
@@ -136,12 +143,29 @@ pub enum Message {
     ModalYes,
     /// Currently not used
     ModalSetParameterByChoicesUpdate(CompactString),
+    EditMacroTargets(usize),
+    EditVelocityCurve,
+    EditChordMemory,
+    /// Map parameter to next incoming MIDI CC
+    MidiLearn(WrappedParameter, MidiLearnScope),
+    MidiLearnClear(WrappedParameter),
+    Undo,
+    Redo,
+    /// Switch between A/B snapshots of current patch
+    SwitchSnapshot,
+    CopySnapshotAToB,
+    /// Toggle whether randomize and mutate actions leave parameter alone
+    ToggleRandomizerLock(WrappedParameter),
+    RandomizePatch,
+    MutatePatch,
 }
 
 #[derive(Debug, Clone)]
 pub enum ModalAction {
     ClearPatch,
     ClearBank,
+    /// List macros and their targets
+    Macros,
     /// Currently not used
     SetParameterByChoices {
         parameter: WrappedParameter,
@@ -163,6 +187,7 @@ pub struct OctaSineIcedApplication<H: GuiSyncHandle> {
     lfo_4: LfoWidgets,
     corner: CornerWidgets,
     modal_action: Option<ModalAction>,
+    undo_history: UndoHistory,
 }
 
 impl<H: GuiSyncHandle> OctaSineIcedApplication<H> {
@@ -196,6 +221,24 @@ impl<H: GuiSyncHandle> OctaSineIcedApplication<H> {
             Parameter::Master(MasterParameter::GlideRetrigger) => {
                 self.corner.glide_retrigger.set_value(v)
             }
+            // Velocity curve is edited through patch actions and not
+            // displayed
+            Parameter::Master(MasterParameter::VelocityCurve) => (),
+            // Currently only editable through the host
+            Parameter::Master(MasterParameter::MaxPolyphony)
+            | Parameter::Master(MasterParameter::VoiceStealing)
+            | Parameter::Master(MasterParameter::NotePriority)
+            | Parameter::Master(MasterParameter::OverlappingVoices)
+            | Parameter::Master(MasterParameter::ArpeggiatorMode)
+            | Parameter::Master(MasterParameter::ArpeggiatorOctaves)
+            | Parameter::Master(MasterParameter::ArpeggiatorRate)
+            | Parameter::Master(MasterParameter::ArpeggiatorSwing)
+            | Parameter::Master(MasterParameter::ArpeggiatorGate)
+            | Parameter::Master(MasterParameter::ArpeggiatorLatch) => (),
+            // Toggled through chord memory patch action
+            Parameter::Master(MasterParameter::ChordMemory)
+            | Parameter::Master(MasterParameter::SidechainModTarget)
+            | Parameter::Master(MasterParameter::SidechainModAmount) => (),
             outer_p @ Parameter::Operator(index, p) => {
                 self.operator_1.wave_display.set_value(outer_p, v);
                 self.operator_2.wave_display.set_value(outer_p, v);
@@ -319,6 +362,10 @@ impl<H: GuiSyncHandle> OctaSineIcedApplication<H> {
                     LfoParameter::KeySync => lfo.key_sync.set_value(v),
                 }
             }
+            // Modulation matrix is currently only editable through host
+            Parameter::ModMatrix(_, _) => (),
+            // Macro targets are updated by sync code
+            Parameter::Macro(_) => (),
         }
     }
 
@@ -340,6 +387,9 @@ impl<H: GuiSyncHandle> OctaSineIcedApplication<H> {
         let settings = Settings {
             schema_version: 1,
             gui: GuiSettings { theme: self.theme },
+            midi_mappings: self.sync_handle.get_global_midi_mappings(),
+            // Not editable in GUI, so keep value from settings file
+            osc: Settings::load_or_default().osc,
         };
 
         if let Err(err) = settings.save() {
@@ -410,6 +460,57 @@ impl<H: GuiSyncHandle> OctaSineIcedApplication<H> {
         self.update_envelope_group_statuses();
     }
 
+    fn get_patch_index(&self) -> usize {
+        self.sync_handle.get_patches().0
+    }
+
+    /// Record change of single parameter made outside of a gesture
+    fn record_parameter_change(&mut self, parameter: WrappedParameter, value: f32) {
+        let patch_index = self.get_patch_index();
+        let before = self.sync_handle.get_parameter(parameter);
+
+        self.undo_history
+            .push_parameter_change(patch_index, parameter, before, value);
+    }
+
+    /// Run change affecting whole bank, recording it in undo history
+    fn change_bank(&mut self, f: impl FnOnce(&H)) {
+        let before = self.sync_handle.export_bank();
+
+        f(&self.sync_handle);
+
+        let after = self.sync_handle.export_bank();
+
+        self.undo_history.push_bank_change(before, after);
+    }
+
+    fn apply_undo_entry(&mut self, entry: UndoEntry, undo: bool) {
+        match entry {
+            UndoEntry::Parameters {
+                patch_index,
+                changes,
+            } => {
+                if self.get_patch_index() != patch_index {
+                    self.sync_handle.set_patch_index(patch_index);
+                }
+
+                for change in changes {
+                    let value = if undo { change.before } else { change.after };
+
+                    self.sync_handle
+                        .set_parameter_immediate(change.parameter, value);
+                    self.set_value(change.parameter.parameter(), value, true);
+                }
+
+                self.update_envelope_group_statuses();
+            }
+            UndoEntry::Bank { before, after } => {
+                self.sync_handle
+                    .import_bank(if undo { &before } else { &after });
+            }
+        }
+    }
+
     fn update_envelope_group_statuses(&mut self) {
         for group in [OperatorEnvelopeGroupValue::A, OperatorEnvelopeGroupValue::B] {
             let mut any_modified_by_automation = false;
@@ -497,6 +598,7 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
             lfo_4,
             corner,
             modal_action: None,
+            undo_history: UndoHistory::default(),
         };
 
         (app, Command::none())
@@ -508,7 +610,24 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
     ) -> Subscription<Self::Message> {
         window_subs.on_frame = Some(|| Message::Frame);
 
-        Subscription::none()
+        subscription::events_with(|event, status| {
+            if let event::Status::Captured = status {
+                return None;
+            }
+
+            match event {
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                }) if modifiers.command() => match key_code {
+                    keyboard::KeyCode::Z if modifiers.shift() => Some(Message::Redo),
+                    keyboard::KeyCode::Z => Some(Message::Undo),
+                    keyboard::KeyCode::Y => Some(Message::Redo),
+                    _ => None,
+                },
+                _ => None,
+            }
+        })
     }
 
     #[cfg(feature = "wgpu")]
@@ -546,6 +665,12 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
                     self.corner.patch_picker = PatchPicker::new(&self.sync_handle);
                 }
                 self.update_widgets_from_parameters();
+
+                self.sync_handle.apply_midi_learn_result();
+
+                if self.sync_handle.have_global_midi_mappings_changed() {
+                    self.save_settings();
+                }
             }
             Message::NoOp => {}
             Message::EnvelopeChangeViewport {
@@ -570,10 +695,19 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
                 }
             }
             Message::ChangeSingleParameterBegin(parameter) => {
+                self.undo_history
+                    .begin_gesture(parameter, self.sync_handle.get_parameter(parameter));
+
                 self.sync_handle.begin_edit(parameter);
             }
             Message::ChangeSingleParameterEnd(parameter) => {
                 self.sync_handle.end_edit(parameter);
+
+                let patch_index = self.get_patch_index();
+                let sync_handle = &self.sync_handle;
+
+                self.undo_history
+                    .end_gesture(patch_index, &[parameter], |p| sync_handle.get_parameter(p));
             }
             Message::ChangeSingleParameterSetValue(parameter, value) => {
                 self.set_value(parameter.parameter(), value, true);
@@ -581,6 +715,8 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
                 self.sync_handle.set_parameter(parameter, value);
             }
             Message::ChangeSingleParameterImmediate(parameter, value) => {
+                self.record_parameter_change(parameter, value);
+
                 self.set_value(parameter.parameter(), value, true);
 
                 self.sync_handle.set_parameter_immediate(parameter, value);
@@ -602,12 +738,29 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
                 }
 
                 self.sync_envelopes(operator_index, true);
+
+                let patch_index = self.get_patch_index();
+                let sync_handle = &self.sync_handle;
+
+                self.undo_history
+                    .end_gesture(patch_index, &envelope_parameters(), |p| {
+                        sync_handle.get_parameter(p)
+                    });
             }
             Message::ChangeEnvelopeParametersSetValue {
                 operator_index,
                 parameter_1,
                 parameter_2,
             } => {
+                // Group members may change too, so remember all envelope
+                // values at start of drag
+                if !self.undo_history.is_in_gesture(parameter_1.0) {
+                    for p in envelope_parameters() {
+                        self.undo_history
+                            .begin_gesture(p, self.sync_handle.get_parameter(p));
+                    }
+                }
+
                 self.set_value(parameter_1.0.parameter(), parameter_1.1, true);
 
                 self.sync_handle
@@ -826,7 +979,9 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
                 }
             }
             Message::LoadBankOrPatchesFromPaths(paths) => {
-                self.sync_handle.import_bank_or_patches_from_paths(&paths);
+                self.change_bank(|sync_handle| {
+                    sync_handle.import_bank_or_patches_from_paths(&paths)
+                });
             }
             Message::ChangeParameterByTextInput {
                 parameter,
@@ -844,6 +999,8 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
                         .sync_handle
                         .parse_parameter_from_text(parameter, &new_text_value)
                     {
+                        self.record_parameter_change(parameter, value_patch);
+
                         self.sync_handle
                             .set_parameter_immediate(parameter, value_patch);
                         self.set_value(parameter.parameter(), value_patch, true);
@@ -858,10 +1015,10 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
             }
             Message::ModalYes => match self.modal_action.take() {
                 Some(ModalAction::ClearBank) => {
-                    self.sync_handle.clear_bank();
+                    self.change_bank(|sync_handle| sync_handle.clear_bank());
                 }
                 Some(ModalAction::ClearPatch) => {
-                    self.sync_handle.clear_patch();
+                    self.change_bank(|sync_handle| sync_handle.clear_patch());
                 }
                 Some(ModalAction::SetParameterByChoices {
                     parameter, choice, ..
@@ -876,7 +1033,7 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
                         self.set_value(parameter.parameter(), value_patch, true);
                     }
                 }
-                None => (),
+                Some(ModalAction::Macros) | None => (),
             },
             Message::ModalSetParameterByChoicesUpdate(new_choice) => {
                 if let Some(ModalAction::SetParameterByChoices { choice, .. }) =
@@ -885,6 +1042,177 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
                     *choice = new_choice.into();
                 }
             }
+            Message::EditMacroTargets(macro_index) => {
+                let targets = self.sync_handle.get_macro_targets(macro_index);
+
+                let mut current_text = MacroTarget::format_list(&targets);
+
+                // Show parse errors and ask again, keeping the entered text,
+                // until targets are valid or user cancels
+                while let Some(text) = tinyfiledialogs::input_box(
+                    "Change OctaSine macro targets",
+                    &format!(
+                        "Please provide targets for {}, separated by semicolons, e.g., \"OP 1 vol = 0.0..1.0 lin; LFO 1 amount = 1.0..0.0 exp\"",
+                        Parameter::Macro(macro_index as u8).name()
+                    ),
+                    &current_text,
+                ) {
+                    match MacroTarget::parse_list(&text) {
+                        Ok(targets) => {
+                            self.sync_handle.set_macro_targets(macro_index, targets);
+
+                            break;
+                        }
+                        Err(err) => {
+                            tinyfiledialogs::message_box_ok(
+                                "Invalid OctaSine macro targets",
+                                &format!("Couldn't parse macro targets: {:#}", err),
+                                tinyfiledialogs::MessageBoxIcon::Error,
+                            );
+
+                            current_text = text;
+                        }
+                    }
+                }
+            }
+            Message::EditVelocityCurve => {
+                let parameter: WrappedParameter =
+                    Parameter::Master(MasterParameter::VelocityCurve).into();
+
+                let curve =
+                    VelocityCurveValue::new_from_patch(self.sync_handle.get_parameter(parameter))
+                        .get();
+
+                let current_text = match curve {
+                    VelocityCurve::Custom => {
+                        self.sync_handle.get_custom_velocity_curve().to_string()
+                    }
+                    curve => VelocityCurveValue::new_from_audio(curve)
+                        .get_formatted()
+                        .to_lowercase(),
+                };
+
+                if let Some(text) = tinyfiledialogs::input_box(
+                    "Change OctaSine velocity curve",
+                    "Please provide a curve (soft, linear or hard) or custom breakpoints in format velocity:value, e.g., \"0.25:0.5, 0.75:0.9\"",
+                    &current_text,
+                ) {
+                    let opt_value_patch = if let Some(value) = VelocityCurveValue::new_from_text(&text) {
+                        Some(value.to_patch())
+                    } else if let Some(custom_curve) = CustomVelocityCurve::parse(&text) {
+                        self.sync_handle.set_custom_velocity_curve(custom_curve);
+
+                        Some(VelocityCurveValue::new_from_audio(VelocityCurve::Custom).to_patch())
+                    } else {
+                        ::log::warn!("Couldn't parse velocity curve: {}", text);
+
+                        None
+                    };
+
+                    if let Some(value_patch) = opt_value_patch {
+                        self.sync_handle
+                            .set_parameter_immediate(parameter, value_patch);
+                        self.set_value(parameter.parameter(), value_patch, true);
+                    }
+                }
+            }
+            Message::EditChordMemory => {
+                let parameter: WrappedParameter =
+                    Parameter::Master(MasterParameter::ChordMemory).into();
+
+                let active =
+                    ChordMemoryValue::new_from_patch(self.sync_handle.get_parameter(parameter))
+                        .get();
+
+                let current_text = if active {
+                    self.sync_handle.get_chord_shape().to_string()
+                } else {
+                    "off".to_string()
+                };
+
+                if let Some(text) = tinyfiledialogs::input_box(
+                    "Change OctaSine chord memory",
+                    "Please provide semitone offsets with optional velocity scales, e.g., \"0, 4:0.8, 7\", \"learn\" to use currently held keys or \"off\"",
+                    &current_text,
+                ) {
+                    let opt_active = match text.trim().to_lowercase().as_str() {
+                        "off" => Some(false),
+                        "learn" => {
+                            self.sync_handle.request_chord_learn();
+
+                            Some(true)
+                        }
+                        _ => {
+                            if let Some(shape) = ChordShape::parse(&text) {
+                                self.sync_handle.set_chord_shape(shape);
+
+                                Some(true)
+                            } else {
+                                ::log::warn!("Couldn't parse chord shape: {}", text);
+
+                                None
+                            }
+                        }
+                    };
+
+                    if let Some(active) = opt_active {
+                        let value_patch = ChordMemoryValue::new_from_audio(active).to_patch();
+
+                        self.sync_handle
+                            .set_parameter_immediate(parameter, value_patch);
+                        self.set_value(parameter.parameter(), value_patch, true);
+                    }
+                }
+            }
+            Message::MidiLearn(parameter, scope) => {
+                self.sync_handle.request_midi_learn(parameter, scope);
+            }
+            Message::MidiLearnClear(parameter) => {
+                self.sync_handle.clear_midi_mapping(parameter);
+            }
+            Message::Undo => {
+                if let Some(entry) = self.undo_history.undo() {
+                    self.apply_undo_entry(entry, true);
+                }
+            }
+            Message::Redo => {
+                if let Some(entry) = self.undo_history.redo() {
+                    self.apply_undo_entry(entry, false);
+                }
+            }
+            Message::SwitchSnapshot => {
+                self.sync_handle.switch_snapshot();
+            }
+            Message::CopySnapshotAToB => {
+                self.sync_handle.copy_snapshot_a_to_b();
+            }
+            Message::ToggleRandomizerLock(parameter) => {
+                let locked = self.sync_handle.is_parameter_locked(parameter);
+
+                self.sync_handle.set_parameter_locked(parameter, !locked);
+            }
+            Message::RandomizePatch => {
+                self.change_bank(|sync_handle| sync_handle.randomize_patch());
+            }
+            Message::MutatePatch => {
+                if let Some(text) = tinyfiledialogs::input_box(
+                    "Mutate OctaSine patch",
+                    "Please provide amount of change in percent (parameters locked through their right-click menus are left alone)",
+                    "20",
+                ) {
+                    match text.trim().trim_end_matches('%').parse::<f32>() {
+                        // Parsing accepts "nan" and "inf"
+                        Ok(percent) if percent.is_finite() => {
+                            self.change_bank(|sync_handle| {
+                                sync_handle.mutate_patch(percent / 100.0)
+                            });
+                        }
+                        _ => {
+                            ::log::warn!("Couldn't parse mutation amount: {}", text);
+                        }
+                    }
+                }
+            }
         }
 
         Command::none()
@@ -934,6 +1262,7 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
             let heading = match modal_action {
                 ModalAction::ClearBank => "CLEAR ENTIRE PATCH BANK?".into(),
                 ModalAction::ClearPatch => "CLEAR CURRENT PATCH?".into(),
+                ModalAction::Macros => "MACROS".into(),
                 ModalAction::SetParameterByChoices { parameter, .. } => {
                     format!("SET {}", parameter.parameter().name().to_uppercase())
                 }
@@ -960,6 +1289,55 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
                         .padding(LINE_HEIGHT as f32)
                         .into()
                 }
+                ModalAction::Macros => {
+                    let mut body = Column::new().spacing(LINE_HEIGHT / 2);
+
+                    for macro_index in 0..NUM_MACROS {
+                        let targets = self.sync_handle.get_macro_targets(macro_index);
+
+                        let targets_text = if targets.is_empty() {
+                            "-".to_string()
+                        } else {
+                            MacroTarget::format_list(&targets)
+                        };
+
+                        body = body.push(
+                            Row::new()
+                                .spacing(LINE_HEIGHT / 2)
+                                .push(
+                                    Text::new(
+                                        Parameter::Macro(macro_index as u8).name().to_uppercase(),
+                                    )
+                                    .width(Length::Fixed(f32::from(LINE_HEIGHT * 4))),
+                                )
+                                .push(Text::new(targets_text).width(Length::Fill))
+                                .push(
+                                    Button::new(
+                                        Text::new("EDIT").horizontal_alignment(Horizontal::Center),
+                                    )
+                                    .width(Length::Fixed(f32::from(LINE_HEIGHT * 4)))
+                                    .on_press(Message::EditMacroTargets(macro_index)),
+                                ),
+                        );
+                    }
+
+                    body = body
+                        .push(Text::new(
+                            "Target values are derived from macro values and aren't reported to the host. Automate macros rather than their targets.",
+                        ))
+                        .push(
+                            Button::new(
+                                Text::new("CLOSE").horizontal_alignment(Horizontal::Center),
+                            )
+                            .width(Length::Fill)
+                            .on_press(Message::ModalClose),
+                        );
+
+                    Card::new(Text::new(heading), body)
+                        .max_width(LINE_HEIGHT as f32 * 48.0)
+                        .padding(LINE_HEIGHT as f32)
+                        .into()
+                }
                 ModalAction::SetParameterByChoices {
                     options, choice, ..
                 } => {
@@ -1013,6 +1391,20 @@ impl<H: GuiSyncHandle> Application for OctaSineIcedApplication<H> {
     }
 }
 
+fn envelope_parameters() -> Vec<WrappedParameter> {
+    (0..NUM_OPERATORS as u8)
+        .flat_map(|index| {
+            [
+                OperatorParameter::AttackDuration,
+                OperatorParameter::DecayDuration,
+                OperatorParameter::SustainVolume,
+                OperatorParameter::ReleaseDuration,
+            ]
+            .map(|p| Parameter::Operator(index, p).into())
+        })
+        .collect()
+}
+
 fn save_data_to_file(path_buf: PathBuf, mut bytes: Vec<u8>) -> anyhow::Result<()> {
     let mut file = ::std::fs::File::create(&path_buf)
         .with_context(|| format!("create file {}", path_buf.display()))?;
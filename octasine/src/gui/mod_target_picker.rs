@@ -11,7 +11,7 @@ use crate::parameters::{
 };
 use crate::sync::GuiSyncHandle;
 
-use super::common::tooltip;
+use super::common::{parameter_context_menu, tooltip};
 use super::style::Theme;
 use super::{Message, FONT_SIZE, LINE_HEIGHT};
 
@@ -113,7 +113,7 @@ where
             .align_items(Alignment::Center)
             .push(title)
             .push(Space::with_height(Length::Fixed(LINE_HEIGHT.into())))
-            .push(checkboxes)
+            .push(parameter_context_menu(theme, self.parameter, checkboxes))
             .into()
     }
 }
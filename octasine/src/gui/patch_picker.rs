@@ -3,11 +3,13 @@ use std::fmt::Display;
 use compact_str::CompactString;
 use iced_baseview::alignment::Horizontal;
 use iced_baseview::widget::tooltip::Position;
-use iced_baseview::widget::{PickList, Row};
+use iced_baseview::widget::{Button, PickList, Row};
 use iced_baseview::{
     widget::Column, widget::Container, widget::Space, widget::Text, Element, Length,
 };
 
+use crate::sync::snapshots::SnapshotSlot;
+
 use super::boolean_button::{voice_mode_button, BooleanButton};
 use super::common::tooltip;
 use super::LINE_HEIGHT;
@@ -20,6 +22,11 @@ const ACTIONS: &[Action] = &[
     Action::OpenPatchesOrBank,
     Action::ClearPatch,
     Action::ClearBank,
+    Action::EditMacros,
+    Action::EditVelocityCurve,
+    Action::EditChordMemory,
+    Action::RandomizePatch,
+    Action::MutatePatch,
 ];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -30,6 +37,14 @@ enum Action {
     OpenPatchesOrBank,
     ClearPatch,
     ClearBank,
+    EditMacros,
+    EditVelocityCurve,
+    EditChordMemory,
+    RandomizePatch,
+    MutatePatch,
+    /// Switch to given A/B snapshot slot
+    SwitchSnapshot(SnapshotSlot),
+    CopySnapshotAToB,
 }
 
 impl Action {
@@ -41,6 +56,13 @@ impl Action {
             Self::OpenPatchesOrBank => Message::LoadBankOrPatch,
             Self::ClearPatch => Message::ClearPatch,
             Self::ClearBank => Message::ClearBank,
+            Self::EditMacros => Message::ModalOpen(super::ModalAction::Macros),
+            Self::EditVelocityCurve => Message::EditVelocityCurve,
+            Self::EditChordMemory => Message::EditChordMemory,
+            Self::RandomizePatch => Message::RandomizePatch,
+            Self::MutatePatch => Message::MutatePatch,
+            Self::SwitchSnapshot(_) => Message::SwitchSnapshot,
+            Self::CopySnapshotAToB => Message::CopySnapshotAToB,
         }
     }
 }
@@ -54,6 +76,14 @@ impl Display for Action {
             Self::OpenPatchesOrBank => write!(f, "OPEN PATCHES/BANK"),
             Self::ClearPatch => write!(f, "CLEAR PATCH"),
             Self::ClearBank => write!(f, "CLEAR BANK"),
+            Self::EditMacros => write!(f, "EDIT MACROS"),
+            Self::EditVelocityCurve => write!(f, "VELOCITY CURVE"),
+            Self::EditChordMemory => write!(f, "CHORD MEMORY"),
+            Self::RandomizePatch => write!(f, "RANDOMIZE"),
+            Self::MutatePatch => write!(f, "MUTATE.."),
+            Self::SwitchSnapshot(SnapshotSlot::A) => write!(f, "A/B: SWITCH TO A"),
+            Self::SwitchSnapshot(SnapshotSlot::B) => write!(f, "A/B: SWITCH TO B"),
+            Self::CopySnapshotAToB => write!(f, "A/B: COPY A TO B"),
         }
     }
 }
@@ -71,6 +101,7 @@ impl Display for Patch {
 }
 
 pub struct PatchPicker {
+    actions: Vec<Action>,
     patch_options: Vec<Patch>,
     patch_index: usize,
     pub voice_mode_button: BooleanButton,
@@ -86,9 +117,19 @@ impl PatchPicker {
             .map(|(index, title)| Patch { index, title })
             .collect();
 
+        let actions = ACTIONS
+            .iter()
+            .copied()
+            .chain([
+                Action::SwitchSnapshot(sync_handle.get_active_snapshot().other()),
+                Action::CopySnapshotAToB,
+            ])
+            .collect();
+
         let voice_mode_button = voice_mode_button(sync_handle);
 
         Self {
+            actions,
             patch_options,
             patch_index,
             voice_mode_button,
@@ -110,7 +151,7 @@ impl PatchPicker {
         .padding(theme.picklist_padding())
         .width(Length::Fill);
 
-        let action_picker = PickList::new(ACTIONS, None, Action::to_message)
+        let action_picker = PickList::new(&self.actions[..], None, Action::to_message)
             .font(theme.font_regular())
             .text_size(FONT_SIZE)
             .padding(theme.picklist_padding())
@@ -124,9 +165,45 @@ impl PatchPicker {
             self.voice_mode_button.view(),
         );
 
+        let undo_button = tooltip(
+            theme,
+            "Undo (Ctrl/Cmd+Z)",
+            Position::Top,
+            Button::new(
+                Text::new("UNDO")
+                    .font(theme.font_regular())
+                    .height(Length::Fixed(LINE_HEIGHT.into()))
+                    .horizontal_alignment(Horizontal::Center),
+            )
+            .padding(theme.button_padding())
+            .on_press(Message::Undo),
+        );
+        let redo_button = tooltip(
+            theme,
+            "Redo (Ctrl/Cmd+Shift+Z)",
+            Position::Top,
+            Button::new(
+                Text::new("REDO")
+                    .font(theme.font_regular())
+                    .height(Length::Fixed(LINE_HEIGHT.into()))
+                    .horizontal_alignment(Horizontal::Center),
+            )
+            .padding(theme.button_padding())
+            .on_press(Message::Redo),
+        );
+
         Container::new(
             Column::new()
-                .push(action_picker)
+                // Undo and redo buttons don't fit next to heading, so they
+                // share row with action picker
+                .push(
+                    Row::new()
+                        .push(action_picker)
+                        .push(Space::with_width(Length::Fixed(4.0)))
+                        .push(undo_button)
+                        .push(Space::with_width(Length::Fixed(4.0)))
+                        .push(redo_button),
+                )
                 .push(Space::with_height(Length::Fixed(f32::from(
                     LINE_HEIGHT / 2 + LINE_HEIGHT / 4,
                 ))))
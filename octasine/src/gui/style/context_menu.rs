@@ -0,0 +1,15 @@
+use iced_aw::native::context_menu::StyleSheet;
+use iced_aw::style::context_menu::Appearance;
+use iced_baseview::Color;
+
+use super::Theme;
+
+impl StyleSheet for Theme {
+    type Style = ();
+
+    fn active(&self, _style: Self::Style) -> Appearance {
+        Appearance {
+            background: Color::TRANSPARENT.into(),
+        }
+    }
+}
@@ -7,6 +7,7 @@ pub mod card;
 pub mod checkbox;
 pub mod colors;
 pub mod container;
+pub mod context_menu;
 pub mod envelope;
 pub mod knob;
 pub mod menu;
@@ -0,0 +1,305 @@
+use std::collections::VecDeque;
+
+use crate::parameters::WrappedParameter;
+
+/// Maximum number of undoable actions kept around
+const MAX_ENTRIES: usize = 64;
+/// Maximum number of bank entries kept around. They hold two full bank
+/// exports each, so keep this low.
+const MAX_BANK_ENTRIES: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ParameterChange {
+    pub parameter: WrappedParameter,
+    pub before: f32,
+    pub after: f32,
+}
+
+#[derive(Debug, Clone)]
+pub enum UndoEntry {
+    /// Parameter changes made in a single gesture
+    Parameters {
+        patch_index: usize,
+        changes: Vec<ParameterChange>,
+    },
+    /// Exported bank before and after clearing or importing patches
+    Bank { before: Vec<u8>, after: Vec<u8> },
+}
+
+#[derive(Default)]
+pub struct UndoHistory {
+    undo_stack: VecDeque<UndoEntry>,
+    redo_stack: Vec<UndoEntry>,
+    /// Parameter values at start of ongoing gestures
+    gesture_start_values: Vec<(WrappedParameter, f32)>,
+}
+
+impl UndoHistory {
+    pub fn is_in_gesture(&self, parameter: WrappedParameter) -> bool {
+        self.gesture_start_values
+            .iter()
+            .any(|(p, _)| p.index() == parameter.index())
+    }
+
+    /// Remember parameter value at start of gesture. Ignored if parameter is
+    /// already part of an ongoing gesture.
+    pub fn begin_gesture(&mut self, parameter: WrappedParameter, value: f32) {
+        if !self.is_in_gesture(parameter) {
+            self.gesture_start_values.push((parameter, value));
+        }
+    }
+
+    /// End gesture for parameters, pushing an entry if any of them changed
+    #[allow(clippy::float_cmp)]
+    pub fn end_gesture(
+        &mut self,
+        patch_index: usize,
+        parameters: &[WrappedParameter],
+        get_value: impl Fn(WrappedParameter) -> f32,
+    ) {
+        let mut changes = Vec::new();
+
+        self.gesture_start_values.retain(|(parameter, before)| {
+            if !parameters.iter().any(|p| p.index() == parameter.index()) {
+                return true;
+            }
+
+            let after = get_value(*parameter);
+
+            if after != *before {
+                changes.push(ParameterChange {
+                    parameter: *parameter,
+                    before: *before,
+                    after,
+                });
+            }
+
+            false
+        });
+
+        self.push_parameter_changes(patch_index, changes);
+    }
+
+    #[allow(clippy::float_cmp)]
+    pub fn push_parameter_change(
+        &mut self,
+        patch_index: usize,
+        parameter: WrappedParameter,
+        before: f32,
+        after: f32,
+    ) {
+        if after != before {
+            self.push_parameter_changes(
+                patch_index,
+                vec![ParameterChange {
+                    parameter,
+                    before,
+                    after,
+                }],
+            );
+        }
+    }
+
+    pub fn push_parameter_changes(&mut self, patch_index: usize, changes: Vec<ParameterChange>) {
+        if !changes.is_empty() {
+            self.push(UndoEntry::Parameters {
+                patch_index,
+                changes,
+            });
+        }
+    }
+
+    pub fn push_bank_change(&mut self, before: Vec<u8>, after: Vec<u8>) {
+        if before != after {
+            self.push(UndoEntry::Bank { before, after });
+        }
+    }
+
+    /// Pop entry to revert, moving it to the redo stack
+    pub fn undo(&mut self) -> Option<UndoEntry> {
+        let entry = self.undo_stack.pop_back()?;
+
+        self.redo_stack.push(entry.clone());
+
+        Some(entry)
+    }
+
+    /// Pop entry to reapply, moving it back to the undo stack
+    pub fn redo(&mut self) -> Option<UndoEntry> {
+        let entry = self.redo_stack.pop()?;
+
+        self.undo_stack.push_back(entry.clone());
+
+        Some(entry)
+    }
+
+    fn push(&mut self, entry: UndoEntry) {
+        if self.undo_stack.len() == MAX_ENTRIES {
+            self.undo_stack.pop_front();
+        }
+
+        self.undo_stack.push_back(entry);
+        self.redo_stack.clear();
+
+        let num_bank_entries = self
+            .undo_stack
+            .iter()
+            .filter(|entry| matches!(entry, UndoEntry::Bank { .. }))
+            .count();
+
+        // Drop history up to and including oldest bank entry, since older
+        // entries can't be undone without it
+        if num_bank_entries > MAX_BANK_ENTRIES {
+            while let Some(entry) = self.undo_stack.pop_front() {
+                if let UndoEntry::Bank { .. } = entry {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parameters::{MasterParameter, OperatorParameter, Parameter};
+
+    use super::*;
+
+    fn num_undo_entries(history: &mut UndoHistory) -> usize {
+        let mut num = 0;
+
+        while history.undo().is_some() {
+            num += 1;
+        }
+
+        num
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_gesture() {
+        let volume: WrappedParameter = Parameter::Master(MasterParameter::Volume).into();
+
+        let mut history = UndoHistory::default();
+
+        history.begin_gesture(volume, 0.5);
+        history.begin_gesture(volume, 0.6);
+
+        assert!(history.is_in_gesture(volume));
+
+        history.end_gesture(3, &[volume], |_| 0.8);
+
+        assert!(!history.is_in_gesture(volume));
+
+        // Repeated begin calls are merged, start value is kept
+        match history.undo() {
+            Some(UndoEntry::Parameters {
+                patch_index,
+                changes,
+            }) => {
+                assert_eq!(patch_index, 3);
+                assert_eq!(changes.len(), 1);
+                assert_eq!(changes[0].before, 0.5);
+                assert_eq!(changes[0].after, 0.8);
+            }
+            _ => panic!("expected parameter entry"),
+        }
+
+        assert!(history.undo().is_none());
+
+        // Gestures not changing value don't create entries
+        history.begin_gesture(volume, 0.5);
+        history.end_gesture(0, &[volume], |_| 0.5);
+
+        assert!(history.redo().is_some());
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_envelope_gesture() {
+        let envelope_parameters: Vec<WrappedParameter> = (0..2)
+            .flat_map(|index| {
+                [
+                    OperatorParameter::AttackDuration,
+                    OperatorParameter::DecayDuration,
+                ]
+                .map(|p| Parameter::Operator(index, p).into())
+            })
+            .collect();
+
+        let mut history = UndoHistory::default();
+
+        for p in envelope_parameters.iter() {
+            history.begin_gesture(*p, 0.0);
+        }
+
+        // Attack of dragged envelope and of group member changed
+        history.end_gesture(0, &envelope_parameters, |p| match p.parameter() {
+            Parameter::Operator(_, OperatorParameter::AttackDuration) => 1.0,
+            _ => 0.0,
+        });
+
+        for p in envelope_parameters.iter() {
+            assert!(!history.is_in_gesture(*p));
+        }
+
+        match history.undo() {
+            Some(UndoEntry::Parameters { changes, .. }) => {
+                assert_eq!(changes.len(), 2);
+
+                for change in changes {
+                    assert!(matches!(
+                        change.parameter.parameter(),
+                        Parameter::Operator(0 | 1, OperatorParameter::AttackDuration)
+                    ));
+                    assert_eq!(change.before, 0.0);
+                    assert_eq!(change.after, 1.0);
+                }
+            }
+            _ => panic!("expected parameter entry"),
+        }
+    }
+
+    #[test]
+    fn test_redo_cleared_on_push() {
+        let volume: WrappedParameter = Parameter::Master(MasterParameter::Volume).into();
+
+        let mut history = UndoHistory::default();
+
+        history.push_parameter_change(0, volume, 0.0, 0.5);
+        history.push_parameter_change(0, volume, 0.5, 1.0);
+
+        assert!(history.undo().is_some());
+
+        history.push_parameter_change(0, volume, 0.5, 0.2);
+
+        assert!(history.redo().is_none());
+        assert_eq!(num_undo_entries(&mut history), 2);
+    }
+
+    #[test]
+    fn test_max_entries() {
+        let volume: WrappedParameter = Parameter::Master(MasterParameter::Volume).into();
+
+        let mut history = UndoHistory::default();
+
+        for i in 0..MAX_ENTRIES + 10 {
+            history.push_parameter_change(0, volume, 0.0, (i + 1) as f32);
+        }
+
+        assert_eq!(num_undo_entries(&mut history), MAX_ENTRIES);
+
+        let mut history = UndoHistory::default();
+
+        history.push_parameter_change(0, volume, 0.0, 1.0);
+
+        for i in 0..MAX_BANK_ENTRIES + 1 {
+            history.push_bank_change(vec![0], vec![i as u8 + 1]);
+            history.push_parameter_change(0, volume, 0.0, 1.0);
+        }
+
+        // Oldest bank entry is dropped along with everything before it
+        assert_eq!(num_undo_entries(&mut history), MAX_BANK_ENTRIES * 2 + 1);
+    }
+}
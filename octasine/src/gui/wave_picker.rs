@@ -11,7 +11,7 @@ use crate::common::{Phase, WaveformChoices};
 use crate::parameters::{Parameter, ParameterValue, WrappedParameter};
 use crate::sync::GuiSyncHandle;
 
-use super::common::tooltip;
+use super::common::{parameter_context_menu, tooltip};
 use super::style::Theme;
 use super::value_text::ValueText;
 use super::{Message, LINE_HEIGHT};
@@ -86,6 +86,9 @@ where
             .font(theme.font_bold())
             .height(Length::Fixed(LINE_HEIGHT.into()));
         let title = tooltip(theme, "Wave form", Position::Top, title);
+        // Right-clicking canvas selects previous wave form, so context menu
+        // is opened from title instead
+        let title = parameter_context_menu(theme, self.canvas.parameter, title);
 
         Column::new()
             .width(Length::Fixed(f32::from(LINE_HEIGHT * 4)))
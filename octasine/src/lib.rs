@@ -1,6 +1,7 @@
 pub mod audio;
 pub mod common;
 pub mod math;
+pub mod osc;
 pub mod parameters;
 pub mod plugin;
 pub mod settings;
@@ -15,13 +16,85 @@ pub mod gui;
 #[no_mangle]
 pub static clap_entry: ::clap_sys::entry::clap_plugin_entry = plugin::clap::CLAP_ENTRY;
 
+#[cfg(feature = "lv2")]
+#[no_mangle]
+pub extern "C" fn lv2_descriptor(index: u32) -> *const plugin::lv2::bindings::LV2_Descriptor {
+    if index == 0 {
+        &plugin::lv2::DESCRIPTOR
+    } else {
+        ::std::ptr::null()
+    }
+}
+
+#[cfg(all(feature = "lv2", feature = "gui", target_os = "linux"))]
+#[no_mangle]
+pub extern "C" fn lv2ui_descriptor(index: u32) -> *const plugin::lv2::bindings::LV2UI_Descriptor {
+    if index == 0 {
+        &plugin::lv2::ui::UI_DESCRIPTOR
+    } else {
+        ::std::ptr::null()
+    }
+}
+
 #[cfg(feature = "vst2")]
 ::vst::plugin_main!(plugin::vst2::OctaSine);
 
+#[cfg(feature = "vst3")]
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn GetPluginFactory() -> *mut ::std::ffi::c_void {
+    plugin::vst3::factory::get_plugin_factory()
+}
+
+#[cfg(all(feature = "vst3", target_os = "windows"))]
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn InitDll() -> bool {
+    true
+}
+
+#[cfg(all(feature = "vst3", target_os = "windows"))]
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn ExitDll() -> bool {
+    true
+}
+
+#[cfg(all(feature = "vst3", target_os = "macos"))]
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn bundleEntry(_bundle: *mut ::std::ffi::c_void) -> bool {
+    true
+}
+
+#[cfg(all(feature = "vst3", target_os = "macos"))]
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn bundleExit() -> bool {
+    true
+}
+
+#[cfg(all(feature = "vst3", target_os = "linux"))]
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn ModuleEntry(_library_handle: *mut ::std::ffi::c_void) -> bool {
+    true
+}
+
+#[cfg(all(feature = "vst3", target_os = "linux"))]
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn ModuleExit() -> bool {
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        audio::AudioState, common::SampleRate, parameters::PARAMETERS, sync::SyncState,
+        audio::AudioState,
+        common::SampleRate,
+        parameters::{Parameter, PARAMETERS},
+        sync::SyncState,
         utils::update_audio_parameters,
     };
 
@@ -52,6 +125,11 @@ mod tests {
         for (i, parameter) in PARAMETERS.iter().copied().enumerate() {
             assert_eq!(i, parameter.to_index() as usize);
 
+            // Macros have no audio parameter
+            if let Parameter::Macro(_) = parameter {
+                continue;
+            }
+
             let values_approx_eq = audio.compare_parameter_patch_value(parameter, patch_values[i]);
 
             if !values_approx_eq {
@@ -0,0 +1,399 @@
+//! OSC control server
+//!
+//! Listens for OSC messages over UDP. Parameter addresses are listed by
+//! [Parameter::osc_address](crate::parameters::Parameter::osc_address),
+//! e.g., "/octasine/op/1/volume". A numeric argument sets the parameter
+//! value (in range 0.0 to 1.0), a string argument sets it from text and no
+//! arguments just queries it. The sender is always replied to with the
+//! current value and its text representation at "<address>/text".
+//!
+//! "/octasine/patch" with an integer argument selects the patch with that
+//! index (starting at 0). It and "/octasine/sync" are replied to with the
+//! patch index, patch name and all parameter values.
+//!
+//! Replies are sent as bundles, with as many messages as fit in each.
+//!
+//! Parameter and patch changes are reported to the host on the next audio
+//! processing call, like changes caused by mapped MIDI controllers, so that
+//! they can be recorded as automation.
+//!
+//! Plugin instances start the server if it is enabled in the settings file.
+//! Only one instance at a time can listen on the configured address.
+
+pub mod protocol;
+
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
+
+use crate::{parameters::PARAMETERS, settings::Settings, sync::SyncState};
+
+use protocol::{decode_packet, encode_bundles, OscArgument, OscMessage};
+
+pub const PATCH_ADDRESS: &str = "/octasine/patch";
+pub const PATCH_NAME_ADDRESS: &str = "/octasine/patch/name";
+pub const SYNC_ADDRESS: &str = "/octasine/sync";
+
+/// Max size of reply packets. Kept well below max UDP payload size, since
+/// clients might use smaller receive buffers.
+const MAX_REPLY_SIZE: usize = 8192;
+
+/// Max time between checks of whether server should stop
+const SOCKET_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OscSettings {
+    /// Start OSC server in plugin instances
+    pub enabled: bool,
+    /// UDP address to listen on
+    pub address: String,
+}
+
+impl Default for OscSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:9000".into(),
+        }
+    }
+}
+
+/// Running OSC server. Stops when dropped.
+pub struct OscServer {
+    local_addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl OscServer {
+    pub fn start<H, A>(sync: Arc<SyncState<H>>, addr: A) -> anyhow::Result<Self>
+    where
+        H: Send + Sync + 'static,
+        A: ToSocketAddrs,
+    {
+        let socket = UdpSocket::bind(addr)?;
+
+        socket.set_read_timeout(Some(SOCKET_READ_TIMEOUT))?;
+
+        let local_addr = socket.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let handler = OscHandler::new(sync);
+
+        let thread = {
+            let stop = stop.clone();
+
+            ::std::thread::Builder::new()
+                .name("octasine-osc".into())
+                .spawn(move || run(socket, handler, stop))?
+        };
+
+        ::log::info!("OSC server listening on {}", local_addr);
+
+        Ok(Self {
+            local_addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Start server if enabled in settings. Errors are logged.
+    pub fn start_if_enabled<H>(sync: Arc<SyncState<H>>) -> Option<Self>
+    where
+        H: Send + Sync + 'static,
+    {
+        let settings = Settings::load_or_default().osc;
+
+        if !settings.enabled {
+            return None;
+        }
+
+        match Self::start(sync, settings.address.as_str()) {
+            Ok(server) => Some(server),
+            Err(err) => {
+                ::log::error!(
+                    "Couldn't start OSC server on {}: {:#}",
+                    settings.address,
+                    err
+                );
+
+                None
+            }
+        }
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for OscServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run<H>(socket: UdpSocket, handler: OscHandler<H>, stop: Arc<AtomicBool>) {
+    // Max UDP payload size
+    let mut buffer = vec![0u8; 65507];
+
+    while !stop.load(Ordering::SeqCst) {
+        let (len, sender) = match socket.recv_from(&mut buffer) {
+            Ok(result) => result,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ::std::io::ErrorKind::WouldBlock | ::std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue;
+            }
+            Err(err) => {
+                ::log::error!("OSC server: receive: {:#}", err);
+
+                continue;
+            }
+        };
+
+        let messages = match decode_packet(&buffer[..len]) {
+            Ok(messages) => messages,
+            Err(err) => {
+                ::log::warn!("OSC server: invalid packet from {}: {:#}", sender, err);
+
+                continue;
+            }
+        };
+
+        let replies: Vec<OscMessage> = messages
+            .iter()
+            .flat_map(|message| handler.handle_message(message))
+            .collect();
+
+        for bundle in encode_bundles(&replies, MAX_REPLY_SIZE) {
+            if let Err(err) = socket.send_to(&bundle, sender) {
+                ::log::warn!("OSC server: reply to {}: {:#}", sender, err);
+            }
+        }
+    }
+}
+
+struct OscHandler<H> {
+    sync: Arc<SyncState<H>>,
+    parameter_indices: HashMap<CompactString, usize>,
+}
+
+impl<H> OscHandler<H> {
+    fn new(sync: Arc<SyncState<H>>) -> Self {
+        let parameter_indices = PARAMETERS
+            .iter()
+            .enumerate()
+            .map(|(index, parameter)| (parameter.osc_address(), index))
+            .collect();
+
+        Self {
+            sync,
+            parameter_indices,
+        }
+    }
+
+    /// Apply message and return replies
+    fn handle_message(&self, message: &OscMessage) -> Vec<OscMessage> {
+        match message.address.as_str() {
+            PATCH_ADDRESS => {
+                if let Some(index) = message.arguments.first().and_then(|a| a.as_f64()) {
+                    if index >= 0.0 {
+                        self.sync.patches.set_patch_index_from_osc(index as usize);
+                    }
+                }
+
+                self.patch_replies()
+            }
+            SYNC_ADDRESS => self.patch_replies(),
+            address => {
+                let index = if let Some(index) = self.parameter_indices.get(address) {
+                    *index
+                } else {
+                    ::log::warn!("OSC server: unknown address {}", address);
+
+                    return Vec::new();
+                };
+
+                match message.arguments.first() {
+                    Some(OscArgument::String(text)) => {
+                        self.sync.patches.set_parameter_text_from_osc(index, text);
+                    }
+                    Some(argument) => {
+                        if let Some(value) = argument.as_f64().filter(|v| !v.is_nan()) {
+                            let value = (value as f32).clamp(0.0, 1.0);
+
+                            self.sync.patches.set_parameter_from_osc(index, value);
+                        }
+                    }
+                    None => (),
+                }
+
+                self.parameter_replies(index).into()
+            }
+        }
+    }
+
+    fn parameter_replies(&self, index: usize) -> [OscMessage; 2] {
+        let address = PARAMETERS[index].osc_address();
+        let patches = &self.sync.patches;
+
+        let value = patches.get_parameter_value(index).unwrap_or(0.0);
+        let text = patches.get_parameter_value_text(index).unwrap_or_default();
+
+        [
+            OscMessage::new(address.as_str(), vec![OscArgument::Float(value)]),
+            OscMessage::new(
+                format!("{}/text", address),
+                vec![OscArgument::String(text.into())],
+            ),
+        ]
+    }
+
+    fn patch_replies(&self) -> Vec<OscMessage> {
+        let patches = &self.sync.patches;
+
+        let mut replies = vec![
+            OscMessage::new(
+                PATCH_ADDRESS,
+                vec![OscArgument::Int(patches.get_patch_index() as i32)],
+            ),
+            OscMessage::new(
+                PATCH_NAME_ADDRESS,
+                vec![OscArgument::String(patches.get_current_patch_name().into())],
+            ),
+        ];
+
+        for index in 0..PARAMETERS.len() {
+            replies.extend(self.parameter_replies(index));
+        }
+
+        replies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parameters::{OperatorParameter, Parameter};
+
+    use super::{protocol::encode_message, *};
+
+    #[test]
+    fn test_handle_parameter_messages() {
+        let sync = Arc::new(SyncState::<()>::new(None));
+        let handler = OscHandler::new(sync.clone());
+
+        let parameter = Parameter::Operator(0, OperatorParameter::Volume);
+        let index = parameter.to_index() as usize;
+
+        let replies = handler.handle_message(&OscMessage::new(
+            "/octasine/op/1/volume",
+            vec![OscArgument::Float(0.25)],
+        ));
+
+        assert_eq!(sync.patches.get_parameter_value(index), Some(0.25));
+        assert_eq!(replies[0].address, "/octasine/op/1/volume");
+        assert_eq!(replies[0].arguments, vec![OscArgument::Float(0.25)]);
+        assert_eq!(replies[1].address, "/octasine/op/1/volume/text");
+
+        // Host is notified of change
+        let changes = sync
+            .patches
+            .get_changed_parameters_from_midi_and_osc()
+            .unwrap();
+
+        assert_eq!(changes[index], Some(0.25));
+
+        // Out of range values are clamped
+        handler.handle_message(&OscMessage::new(
+            "/octasine/op/1/volume",
+            vec![OscArgument::Int(3)],
+        ));
+
+        assert_eq!(sync.patches.get_parameter_value(index), Some(1.0));
+
+        // Query
+        let replies = handler.handle_message(&OscMessage::new("/octasine/op/1/volume", vec![]));
+
+        assert_eq!(replies[0].arguments, vec![OscArgument::Float(1.0)]);
+
+        assert!(handler
+            .handle_message(&OscMessage::new("/octasine/unknown", vec![]))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_handle_patch_message() {
+        let sync = Arc::new(SyncState::<()>::new(None));
+        let handler = OscHandler::new(sync.clone());
+
+        let replies =
+            handler.handle_message(&OscMessage::new(PATCH_ADDRESS, vec![OscArgument::Int(2)]));
+
+        assert_eq!(sync.patches.get_patch_index(), 2);
+        assert!(sync.patches.has_patch_index_changed_from_audio());
+        assert_eq!(replies[0].arguments, vec![OscArgument::Int(2)]);
+        assert_eq!(replies.len(), 2 + PARAMETERS.len() * 2);
+    }
+
+    #[test]
+    fn test_server_roundtrip() {
+        let sync = Arc::new(SyncState::<()>::new(None));
+        let server = OscServer::start(sync.clone(), "127.0.0.1:0").unwrap();
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let message = OscMessage::new("/octasine/master/volume", vec![OscArgument::Float(0.75)]);
+
+        client
+            .send_to(&encode_message(&message), server.local_addr())
+            .unwrap();
+
+        let mut buffer = [0u8; MAX_REPLY_SIZE];
+        let len = client.recv(&mut buffer).unwrap();
+        let replies = decode_packet(&buffer[..len]).unwrap();
+
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[0], message);
+
+        // Patch replies are bundled into a few packets
+        client
+            .send_to(
+                &encode_message(&OscMessage::new(SYNC_ADDRESS, vec![])),
+                server.local_addr(),
+            )
+            .unwrap();
+
+        let mut num_packets = 0;
+        let mut num_replies = 0;
+
+        while num_replies < 2 + PARAMETERS.len() * 2 {
+            let len = client.recv(&mut buffer).unwrap();
+
+            num_packets += 1;
+            num_replies += decode_packet(&buffer[..len]).unwrap().len();
+        }
+
+        assert!(num_packets < 16);
+    }
+}
@@ -0,0 +1,308 @@
+//! Minimal OSC 1.0 packet encoding and decoding
+
+use anyhow::Context;
+
+const BUNDLE_TAG: &[u8] = b"#bundle\0";
+/// Special time tag value meaning "immediately"
+const TIME_TAG_IMMEDIATELY: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArgument {
+    Int(i32),
+    Float(f32),
+    String(String),
+    Long(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+impl OscArgument {
+    /// Numeric and boolean arguments as f64
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(v) => Some(f64::from(*v)),
+            Self::Float(v) => Some(f64::from(*v)),
+            Self::Long(v) => Some(*v as f64),
+            Self::Double(v) => Some(*v),
+            Self::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+            Self::String(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub arguments: Vec<OscArgument>,
+}
+
+impl OscMessage {
+    pub fn new<S: Into<String>>(address: S, arguments: Vec<OscArgument>) -> Self {
+        Self {
+            address: address.into(),
+            arguments,
+        }
+    }
+}
+
+/// Decode packet into messages. Bundles are flattened and their time tags
+/// are ignored.
+pub fn decode_packet(bytes: &[u8]) -> anyhow::Result<Vec<OscMessage>> {
+    let mut messages = Vec::new();
+
+    decode_packet_into(bytes, &mut messages)?;
+
+    Ok(messages)
+}
+
+fn decode_packet_into(bytes: &[u8], messages: &mut Vec<OscMessage>) -> anyhow::Result<()> {
+    let mut reader = Reader { bytes, offset: 0 };
+
+    if bytes.starts_with(BUNDLE_TAG) {
+        reader.offset = BUNDLE_TAG.len();
+
+        // Time tag
+        reader.take(TIME_TAG_IMMEDIATELY.len())?;
+
+        while reader.offset < bytes.len() {
+            let size = reader.read_i32()?;
+            let size = usize::try_from(size).context("negative bundle element size")?;
+
+            decode_packet_into(reader.take(size)?, messages)?;
+        }
+    } else {
+        let address = reader.read_string()?;
+
+        if !address.starts_with('/') {
+            anyhow::bail!("invalid address {}", address);
+        }
+
+        // Type tag string is optional in old implementations
+        let type_tags = if reader.offset < bytes.len() {
+            reader.read_string()?
+        } else {
+            ",".into()
+        };
+
+        let type_tags = type_tags
+            .strip_prefix(',')
+            .context("type tag string doesn't start with comma")?;
+
+        let mut arguments = Vec::with_capacity(type_tags.len());
+
+        for type_tag in type_tags.chars() {
+            let argument = match type_tag {
+                'i' => OscArgument::Int(reader.read_i32()?),
+                'f' => OscArgument::Float(f32::from_bits(reader.read_i32()? as u32)),
+                's' => OscArgument::String(reader.read_string()?),
+                'h' => OscArgument::Long(reader.read_i64()?),
+                'd' => OscArgument::Double(f64::from_bits(reader.read_i64()? as u64)),
+                'T' => OscArgument::Bool(true),
+                'F' => OscArgument::Bool(false),
+                // Types without data
+                'N' | 'I' => continue,
+                other => anyhow::bail!("unsupported type tag {}", other),
+            };
+
+            arguments.push(argument);
+        }
+
+        messages.push(OscMessage { address, arguments });
+    }
+
+    Ok(())
+}
+
+pub fn encode_message(message: &OscMessage) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    write_string(&mut bytes, &message.address);
+
+    let mut type_tags = String::from(",");
+    let mut data = Vec::new();
+
+    for argument in message.arguments.iter() {
+        match argument {
+            OscArgument::Int(v) => {
+                type_tags.push('i');
+                data.extend_from_slice(&v.to_be_bytes());
+            }
+            OscArgument::Float(v) => {
+                type_tags.push('f');
+                data.extend_from_slice(&v.to_be_bytes());
+            }
+            OscArgument::String(v) => {
+                type_tags.push('s');
+                write_string(&mut data, v);
+            }
+            OscArgument::Long(v) => {
+                type_tags.push('h');
+                data.extend_from_slice(&v.to_be_bytes());
+            }
+            OscArgument::Double(v) => {
+                type_tags.push('d');
+                data.extend_from_slice(&v.to_be_bytes());
+            }
+            OscArgument::Bool(true) => type_tags.push('T'),
+            OscArgument::Bool(false) => type_tags.push('F'),
+        }
+    }
+
+    write_string(&mut bytes, &type_tags);
+
+    bytes.extend_from_slice(&data);
+
+    bytes
+}
+
+/// Encode messages into as few bundles as possible without any bundle
+/// exceeding `max_bundle_size` bytes, unless a single message does.
+pub fn encode_bundles(messages: &[OscMessage], max_bundle_size: usize) -> Vec<Vec<u8>> {
+    let mut bundles = Vec::new();
+    let mut bundle = Vec::new();
+
+    for message in messages {
+        let encoded = encode_message(message);
+
+        if !bundle.is_empty() && bundle.len() + 4 + encoded.len() > max_bundle_size {
+            bundles.push(::std::mem::take(&mut bundle));
+        }
+
+        if bundle.is_empty() {
+            bundle.extend_from_slice(BUNDLE_TAG);
+            bundle.extend_from_slice(&TIME_TAG_IMMEDIATELY);
+        }
+
+        bundle.extend_from_slice(&(encoded.len() as i32).to_be_bytes());
+        bundle.extend_from_slice(&encoded);
+    }
+
+    if !bundle.is_empty() {
+        bundles.push(bundle);
+    }
+
+    bundles
+}
+
+/// Write null-terminated string padded to multiple of four bytes
+fn write_string(bytes: &mut Vec<u8>, s: &str) {
+    bytes.extend_from_slice(s.as_bytes());
+
+    let num_nulls = 4 - (s.len() % 4);
+
+    bytes.resize(bytes.len() + num_nulls, 0);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self.offset + len;
+        let bytes = self
+            .bytes
+            .get(self.offset..end)
+            .context("packet too short")?;
+
+        self.offset = end;
+
+        Ok(bytes)
+    }
+
+    fn read_i32(&mut self) -> anyhow::Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn read_i64(&mut self) -> anyhow::Result<i64> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn read_string(&mut self) -> anyhow::Result<String> {
+        let remaining = &self.bytes[self.offset..];
+        let len = memchr::memchr(0, remaining).context("unterminated string")?;
+        let s = ::std::str::from_utf8(&remaining[..len])?.to_owned();
+
+        // Skip string, null terminator and padding
+        self.take((len / 4 + 1) * 4)?;
+
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_message() {
+        let message = OscMessage::new(
+            "/octasine/op/1/volume",
+            vec![
+                OscArgument::Float(0.25),
+                OscArgument::Int(-3),
+                OscArgument::String("abcd".into()),
+                OscArgument::Double(1.5),
+                OscArgument::Long(1 << 40),
+                OscArgument::Bool(true),
+            ],
+        );
+
+        let bytes = encode_message(&message);
+
+        assert_eq!(bytes.len() % 4, 0);
+        assert_eq!(decode_packet(&bytes).unwrap(), vec![message]);
+    }
+
+    #[test]
+    fn test_decode_bundle() {
+        let a = OscMessage::new("/a", vec![OscArgument::Int(1)]);
+        let b = OscMessage::new("/b", vec![]);
+
+        let mut bytes = BUNDLE_TAG.to_vec();
+
+        bytes.extend_from_slice(&TIME_TAG_IMMEDIATELY);
+
+        for message in [&a, &b] {
+            let encoded = encode_message(message);
+
+            bytes.extend_from_slice(&(encoded.len() as i32).to_be_bytes());
+            bytes.extend_from_slice(&encoded);
+        }
+
+        assert_eq!(decode_packet(&bytes).unwrap(), vec![a, b]);
+    }
+
+    #[test]
+    fn test_encode_bundles() {
+        let messages: Vec<OscMessage> = (0..100)
+            .map(|i| OscMessage::new(format!("/message/{}", i), vec![OscArgument::Float(0.5)]))
+            .collect();
+
+        assert_eq!(
+            decode_packet(&encode_bundles(&messages, 65507)[0]).unwrap(),
+            messages
+        );
+
+        let bundles = encode_bundles(&messages, 256);
+
+        assert!(bundles.len() > 1);
+        assert!(bundles.iter().all(|bundle| bundle.len() <= 256));
+
+        let decoded: Vec<OscMessage> = bundles
+            .iter()
+            .flat_map(|bundle| decode_packet(bundle).unwrap())
+            .collect();
+
+        assert_eq!(decoded, messages);
+        assert!(encode_bundles(&[], 256).is_empty());
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(decode_packet(b"").is_err());
+        assert!(decode_packet(b"/abc").is_err());
+        assert!(decode_packet(b"/ab\0,f\0\0\0\0").is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use compact_str::{format_compact, CompactString};
+
+use super::{utils::parse_valid_f32, ParameterValue, SerializableRepresentation};
+
+const MIN_GATE: f32 = 0.05;
+
+/// Fraction of arpeggiator step that key is held
+#[derive(Debug, Clone, Copy)]
+pub struct ArpeggiatorGateValue(f32);
+
+impl Default for ArpeggiatorGateValue {
+    fn default() -> Self {
+        Self(0.5)
+    }
+}
+
+impl ParameterValue for ArpeggiatorGateValue {
+    type Value = f32;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        parse_valid_f32(
+            text.trim().trim_end_matches('%').trim(),
+            MIN_GATE * 100.0,
+            100.0,
+        )
+        .map(|value| Self(value / 100.0))
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(MIN_GATE + value * (1.0 - MIN_GATE))
+    }
+    fn to_patch(self) -> f32 {
+        (self.0 - MIN_GATE) / (1.0 - MIN_GATE)
+    }
+    fn get_formatted(self) -> CompactString {
+        format_compact!("{:.0}%", self.0 * 100.0)
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Float(self.0.into())
+    }
+}
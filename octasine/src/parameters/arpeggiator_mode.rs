@@ -0,0 +1,82 @@
+use compact_str::CompactString;
+
+use super::{
+    utils::{map_patch_value_to_step, map_step_to_patch_value},
+    ParameterValue, SerializableRepresentation,
+};
+
+const STEPS: &[ArpeggiatorMode] = &[
+    ArpeggiatorMode::Off,
+    ArpeggiatorMode::Up,
+    ArpeggiatorMode::Down,
+    ArpeggiatorMode::UpDown,
+    ArpeggiatorMode::Random,
+    ArpeggiatorMode::AsPlayed,
+];
+
+/// Order in which arpeggiator plays held keys
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ArpeggiatorMode {
+    #[default]
+    Off,
+    Up,
+    Down,
+    /// Up and then down, without repeating highest and lowest keys
+    UpDown,
+    Random,
+    /// In the order keys were pressed
+    AsPlayed,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArpeggiatorModeValue(ArpeggiatorMode);
+
+impl ParameterValue for ArpeggiatorModeValue {
+    type Value = ArpeggiatorMode;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        let text = text.to_lowercase();
+
+        if text.contains("off") {
+            Some(Self(ArpeggiatorMode::Off))
+        } else if text.contains("up") && text.contains("down") {
+            Some(Self(ArpeggiatorMode::UpDown))
+        } else if text.contains("up") {
+            Some(Self(ArpeggiatorMode::Up))
+        } else if text.contains("down") {
+            Some(Self(ArpeggiatorMode::Down))
+        } else if text.contains("rand") {
+            Some(Self(ArpeggiatorMode::Random))
+        } else if text.contains("play") {
+            Some(Self(ArpeggiatorMode::AsPlayed))
+        } else {
+            None
+        }
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(map_patch_value_to_step(STEPS, value))
+    }
+    fn to_patch(self) -> f32 {
+        map_step_to_patch_value(STEPS, self.0)
+    }
+    fn get_formatted(self) -> CompactString {
+        match self.0 {
+            ArpeggiatorMode::Off => "OFF".into(),
+            ArpeggiatorMode::Up => "UP".into(),
+            ArpeggiatorMode::Down => "DOWN".into(),
+            ArpeggiatorMode::UpDown => "UP/DOWN".into(),
+            ArpeggiatorMode::Random => "RANDOM".into(),
+            ArpeggiatorMode::AsPlayed => "AS PLAYED".into(),
+        }
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Other(self.get_formatted())
+    }
+}
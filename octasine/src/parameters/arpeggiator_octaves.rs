@@ -0,0 +1,47 @@
+use compact_str::{format_compact, CompactString};
+
+use super::{
+    utils::{map_patch_value_to_step, map_step_to_patch_value},
+    ParameterValue, SerializableRepresentation,
+};
+
+const STEPS: &[u8] = &[1, 2, 3, 4];
+
+/// Number of octaves arpeggiator pattern spans
+#[derive(Debug, Clone, Copy)]
+pub struct ArpeggiatorOctavesValue(u8);
+
+impl Default for ArpeggiatorOctavesValue {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+impl ParameterValue for ArpeggiatorOctavesValue {
+    type Value = u8;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        let value: u8 = text.trim().trim_end_matches("oct").trim().parse().ok()?;
+
+        Some(Self(value.clamp(STEPS[0], *STEPS.last().unwrap())))
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(map_patch_value_to_step(STEPS, value))
+    }
+    fn to_patch(self) -> f32 {
+        map_step_to_patch_value(STEPS, self.0)
+    }
+    fn get_formatted(self) -> CompactString {
+        format_compact!("{} OCT", self.0)
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Float(self.0.into())
+    }
+}
@@ -0,0 +1,83 @@
+use compact_str::CompactString;
+
+use super::{
+    utils::{map_patch_value_to_step, map_step_to_patch_value},
+    ParameterValue, SerializableRepresentation,
+};
+
+const STEPS: &[ArpeggiatorRate] = &[
+    ArpeggiatorRate::Quarter,
+    ArpeggiatorRate::Eighth,
+    ArpeggiatorRate::EighthTriplet,
+    ArpeggiatorRate::Sixteenth,
+    ArpeggiatorRate::SixteenthTriplet,
+    ArpeggiatorRate::ThirtySecond,
+];
+
+/// Arpeggiator step length, synced to tempo
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ArpeggiatorRate {
+    Quarter,
+    Eighth,
+    EighthTriplet,
+    #[default]
+    Sixteenth,
+    SixteenthTriplet,
+    ThirtySecond,
+}
+
+impl ArpeggiatorRate {
+    pub fn beats(self) -> f64 {
+        match self {
+            Self::Quarter => 1.0,
+            Self::Eighth => 1.0 / 2.0,
+            Self::EighthTriplet => 1.0 / 3.0,
+            Self::Sixteenth => 1.0 / 4.0,
+            Self::SixteenthTriplet => 1.0 / 6.0,
+            Self::ThirtySecond => 1.0 / 8.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArpeggiatorRateValue(ArpeggiatorRate);
+
+impl ParameterValue for ArpeggiatorRateValue {
+    type Value = ArpeggiatorRate;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+
+        STEPS
+            .iter()
+            .copied()
+            .find(|step| Self(*step).get_formatted().to_lowercase() == text)
+            .map(Self)
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(map_patch_value_to_step(STEPS, value))
+    }
+    fn to_patch(self) -> f32 {
+        map_step_to_patch_value(STEPS, self.0)
+    }
+    fn get_formatted(self) -> CompactString {
+        match self.0 {
+            ArpeggiatorRate::Quarter => "1/4".into(),
+            ArpeggiatorRate::Eighth => "1/8".into(),
+            ArpeggiatorRate::EighthTriplet => "1/8T".into(),
+            ArpeggiatorRate::Sixteenth => "1/16".into(),
+            ArpeggiatorRate::SixteenthTriplet => "1/16T".into(),
+            ArpeggiatorRate::ThirtySecond => "1/32".into(),
+        }
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Other(self.get_formatted())
+    }
+}
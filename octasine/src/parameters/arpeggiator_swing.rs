@@ -0,0 +1,36 @@
+use compact_str::{format_compact, CompactString};
+
+use super::{utils::parse_valid_f32, ParameterValue, SerializableRepresentation};
+
+/// Arpeggiator swing. At 1.0, every other step is three times as long as
+/// the following one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArpeggiatorSwingValue(f32);
+
+impl ParameterValue for ArpeggiatorSwingValue {
+    type Value = f32;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        parse_valid_f32(text.trim().trim_end_matches('%').trim(), 0.0, 100.0)
+            .map(|value| Self(value / 100.0))
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(value)
+    }
+    fn to_patch(self) -> f32 {
+        self.0
+    }
+    fn get_formatted(self) -> CompactString {
+        format_compact!("{:.0}%", self.0 * 100.0)
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Float(self.0.into())
+    }
+}
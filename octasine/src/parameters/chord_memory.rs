@@ -0,0 +1,194 @@
+use arrayvec::ArrayVec;
+use compact_str::{format_compact, CompactString};
+use serde::{Deserialize, Serialize};
+
+use super::{utils::parse_valid_f32, ParameterValue, SerializableRepresentation};
+
+pub const MAX_CHORD_NOTES: usize = 8;
+const MAX_CHORD_OFFSET: i8 = 48;
+
+/// When on, each key triggers chord shape stored in patch
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChordMemoryValue(bool);
+
+impl ParameterValue for ChordMemoryValue {
+    type Value = bool;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "off" => Some(Self(false)),
+            "on" => Some(Self(true)),
+            _ => None,
+        }
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(value > 0.5)
+    }
+    fn to_patch(self) -> f32 {
+        if self.0 {
+            1.0
+        } else {
+            0.0
+        }
+    }
+    fn get_formatted(self) -> CompactString {
+        format_compact!("{}", if self.0 { "ON" } else { "OFF" })
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Other(self.get_formatted())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ChordNote {
+    /// Semitones relative to pressed key
+    pub offset: i8,
+    /// Multiplied with velocity of pressed key
+    pub velocity_scale: f32,
+}
+
+/// Notes triggered by each key in chord memory mode. An empty shape plays
+/// the pressed key only.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChordShape {
+    notes: ArrayVec<ChordNote, MAX_CHORD_NOTES>,
+}
+
+impl ChordShape {
+    /// Create shape from notes. Notes are sorted by offset. Returns None if
+    /// there are too many notes, duplicate offsets or values out of range.
+    pub fn new(mut notes: Vec<ChordNote>) -> Option<Self> {
+        if notes
+            .iter()
+            .any(|n| n.offset.abs() > MAX_CHORD_OFFSET || !(0.0..=1.0).contains(&n.velocity_scale))
+        {
+            return None;
+        }
+
+        notes.sort_by_key(|n| n.offset);
+
+        if notes.windows(2).any(|w| w[0].offset == w[1].offset) {
+            return None;
+        }
+
+        Some(Self {
+            notes: ArrayVec::try_from(&notes[..]).ok()?,
+        })
+    }
+
+    /// Learn shape from held keys and their velocities. Offsets are relative
+    /// to the lowest key and velocity scales to the highest velocity.
+    /// Doesn't allocate.
+    pub fn learn<I>(keys: I) -> Option<Self>
+    where
+        I: Iterator<Item = (u8, f32)> + Clone,
+    {
+        let root = keys.clone().map(|(key, _)| key).min()?;
+        let max_velocity = keys
+            .clone()
+            .map(|(_, velocity)| velocity)
+            .fold(0.0, f32::max);
+
+        let mut notes = ArrayVec::new();
+
+        for (key, velocity) in keys {
+            let offset = i8::try_from(key - root)
+                .ok()
+                .filter(|offset| *offset <= MAX_CHORD_OFFSET)?;
+
+            let velocity_scale = if max_velocity > 0.0 {
+                velocity / max_velocity
+            } else {
+                1.0
+            };
+
+            notes
+                .try_push(ChordNote {
+                    offset,
+                    velocity_scale,
+                })
+                .ok()?;
+        }
+
+        notes.sort_by_key(|n| n.offset);
+
+        Some(Self { notes })
+    }
+
+    pub fn notes(&self) -> &[ChordNote] {
+        &self.notes
+    }
+
+    /// Parse text in format "0, 4:0.8, 7", where each entry is a semitone
+    /// offset, optionally followed by a velocity scale
+    pub fn parse(text: &str) -> Option<Self> {
+        let notes = text
+            .split(',')
+            .filter(|t| !t.trim().is_empty())
+            .map(|t| {
+                let (offset, velocity_scale) = match t.split_once(':') {
+                    Some((offset, velocity_scale)) => {
+                        (offset, parse_valid_f32(velocity_scale.trim(), 0.0, 1.0)?)
+                    }
+                    None => (t, 1.0),
+                };
+
+                Some(ChordNote {
+                    offset: offset.trim().parse().ok()?,
+                    velocity_scale,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Self::new(notes)
+    }
+}
+
+impl ::std::fmt::Display for ChordShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, note) in self.notes.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{}", note.offset)?;
+
+            if note.velocity_scale != 1.0 {
+                write!(f, ":{:.02}", note.velocity_scale)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chord_shape_parse_and_learn() {
+        let shape = ChordShape::parse("7, 0, 4:0.5").unwrap();
+
+        assert_eq!(
+            shape.notes().iter().map(|n| n.offset).collect::<Vec<_>>(),
+            [0, 4, 7]
+        );
+        assert_eq!(ChordShape::parse(&shape.to_string()), Some(shape.clone()));
+        assert_eq!(ChordShape::parse(""), Some(ChordShape::default()));
+        assert_eq!(ChordShape::parse("0, 0"), None);
+        assert_eq!(ChordShape::parse("60"), None);
+
+        let learned = ChordShape::learn([(64, 0.25), (60, 0.5), (67, 0.5)].into_iter());
+
+        assert_eq!(learned, Some(shape));
+        assert_eq!(ChordShape::learn(::std::iter::empty()), None);
+    }
+}
@@ -1,4 +1,4 @@
-use std::f32::consts::TAU;
+use std::f32::consts::{PI, TAU};
 
 use compact_str::CompactString;
 
@@ -7,7 +7,7 @@ use super::ParameterValue;
 use super::SerializableRepresentation;
 use crate::common::*;
 
-pub const LFO_SHAPE_STEPS: [LfoShape; 8] = [
+pub const LFO_SHAPE_STEPS: [LfoShape; 11] = [
     LfoShape::Triangle,
     LfoShape::ReverseTriangle,
     LfoShape::Saw,
@@ -16,8 +16,14 @@ pub const LFO_SHAPE_STEPS: [LfoShape; 8] = [
     LfoShape::ReverseSquare,
     LfoShape::Sine,
     LfoShape::ReverseSine,
+    LfoShape::SampleAndHold,
+    LfoShape::SmoothRandom,
+    LfoShape::Noise,
 ];
 
+/// Number of segments to split a cycle into when displaying random shapes
+const RANDOM_DISPLAY_SEGMENTS: f64 = 4.0;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 pub enum LfoShape {
     Saw,
@@ -29,10 +35,48 @@ pub enum LfoShape {
     ReverseSquare,
     Sine,
     ReverseSine,
+    /// New random value at the start of each cycle, held until the next one
+    SampleAndHold,
+    /// Random value smoothly interpolated towards a new one during each cycle
+    SmoothRandom,
+    /// New random value every sample
+    Noise,
+}
+
+/// Random values used by the random shapes. Kept per voice LFO.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LfoRandomValues {
+    /// Value drawn at start of previous cycle
+    pub previous: f32,
+    /// Value drawn at start of current cycle
+    pub current: f32,
+    /// Value drawn for current sample
+    pub noise: f32,
+}
+
+impl LfoRandomValues {
+    pub fn new(rng: &mut fastrand::Rng) -> Self {
+        Self {
+            previous: random_bipolar(rng),
+            current: random_bipolar(rng),
+            noise: random_bipolar(rng),
+        }
+    }
+
+    /// Call when LFO phase wraps around
+    pub fn advance_cycle(&mut self, rng: &mut fastrand::Rng) {
+        self.previous = self.current;
+        self.current = random_bipolar(rng);
+    }
+
+    /// Call every sample
+    pub fn advance_sample(&mut self, rng: &mut fastrand::Rng) {
+        self.noise = random_bipolar(rng);
+    }
 }
 
 impl LfoShape {
-    pub fn calculate(self, phase: Phase) -> f32 {
+    pub fn calculate(self, phase: Phase, random_values: LfoRandomValues) -> f32 {
         match self {
             Self::Saw => lfo_saw(phase),
             Self::ReverseSaw => -lfo_saw(phase),
@@ -42,13 +86,38 @@ impl LfoShape {
             Self::ReverseSquare => -lfo_square(phase),
             Self::Sine => lfo_sine(phase),
             Self::ReverseSine => -lfo_sine(phase),
+            Self::SampleAndHold => random_values.current,
+            Self::SmoothRandom => {
+                lfo_smooth_random(phase, random_values.previous, random_values.current)
+            }
+            Self::Noise => random_values.noise,
         }
     }
+
+    pub fn is_random(self) -> bool {
+        matches!(self, Self::SampleAndHold | Self::SmoothRandom | Self::Noise)
+    }
 }
 
 impl WaveformChoices for LfoShape {
     fn calculate_for_current(self, phase: Phase) -> f32 {
-        LfoShape::calculate(self, phase)
+        if !self.is_random() {
+            return LfoShape::calculate(self, phase, Default::default());
+        }
+
+        // Ensure same numbers are generated each time for GUI consistency
+        let segment_phase = phase.0 * RANDOM_DISPLAY_SEGMENTS;
+        let segment = segment_phase as u64;
+
+        let seeded_random = |seed: u64| random_bipolar(&mut fastrand::Rng::with_seed(seed + 2));
+
+        let random_values = LfoRandomValues {
+            previous: seeded_random(segment),
+            current: seeded_random(segment + 1),
+            noise: seeded_random(phase.0.to_bits()),
+        };
+
+        LfoShape::calculate(self, Phase(segment_phase.fract()), random_values)
     }
     fn choices() -> &'static [Self] {
         &LFO_SHAPE_STEPS
@@ -76,6 +145,9 @@ impl ParameterValue for LfoShapeValue {
             "reverse square" | "rev square" | "rev sqr" => Some(Self(LfoShape::ReverseSquare)),
             "sine" => Some(Self(LfoShape::Sine)),
             "reverse sine" | "rev sine" => Some(Self(LfoShape::ReverseSine)),
+            "sample and hold" | "sample & hold" | "s&h" => Some(Self(LfoShape::SampleAndHold)),
+            "smooth random" | "smooth" => Some(Self(LfoShape::SmoothRandom)),
+            "noise" => Some(Self(LfoShape::Noise)),
             _ => None,
         }
     }
@@ -98,6 +170,9 @@ impl ParameterValue for LfoShapeValue {
             LfoShape::ReverseSquare => "REV SQR".into(),
             LfoShape::Sine => "SINE".into(),
             LfoShape::ReverseSine => "REV SINE".into(),
+            LfoShape::SampleAndHold => "S&H".into(),
+            LfoShape::SmoothRandom => "SMOOTH".into(),
+            LfoShape::Noise => "NOISE".into(),
         }
     }
 
@@ -150,3 +225,62 @@ fn lfo_square(phase: Phase) -> f32 {
 fn lfo_sine(phase: Phase) -> f32 {
     ::sleef_trig::Sleef_sinf1_u35purec_range125(phase.0 as f32 * TAU)
 }
+
+/// LFO smooth random wave: cosine interpolation between two random values
+fn lfo_smooth_random(phase: Phase, from: f32, to: f32) -> f32 {
+    let factor = 0.5 - 0.5 * ::sleef_trig::Sleef_cosf1_u35purec_range125(phase.0 as f32 * PI);
+
+    from + factor * (to - from)
+}
+
+/// Random number in range -1.0 to 1.0
+fn random_bipolar(rng: &mut fastrand::Rng) -> f32 {
+    (rng.f32() - 0.5) * 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lfo_smooth_random_is_continuous() {
+        let mut rng = fastrand::Rng::with_seed(0);
+        let mut random_values = LfoRandomValues::new(&mut rng);
+
+        let end_of_cycle = LfoShape::SmoothRandom.calculate(Phase(0.999999), random_values);
+
+        random_values.advance_cycle(&mut rng);
+
+        let start_of_cycle = LfoShape::SmoothRandom.calculate(Phase(0.0), random_values);
+
+        assert!((end_of_cycle - start_of_cycle).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_lfo_random_values_repeatable() {
+        let mut a = LfoRandomValues::new(&mut fastrand::Rng::with_seed(1));
+        let mut b = LfoRandomValues::new(&mut fastrand::Rng::with_seed(1));
+
+        let mut rng_a = fastrand::Rng::with_seed(2);
+        let mut rng_b = fastrand::Rng::with_seed(2);
+
+        for _ in 0..16 {
+            a.advance_cycle(&mut rng_a);
+            a.advance_sample(&mut rng_a);
+            b.advance_cycle(&mut rng_b);
+            b.advance_sample(&mut rng_b);
+
+            for shape in [
+                LfoShape::SampleAndHold,
+                LfoShape::SmoothRandom,
+                LfoShape::Noise,
+            ] {
+                let value_a = shape.calculate(Phase(0.3), a);
+                let value_b = shape.calculate(Phase(0.3), b);
+
+                assert!((-1.0..=1.0).contains(&value_a));
+                assert_eq!(value_a.to_bits(), value_b.to_bits());
+            }
+        }
+    }
+}
@@ -67,6 +67,11 @@ pub fn get_lfo_target_parameters(lfo_index: usize) -> &'static [LfoTargetParamet
     &LFO_TARGETS[..end]
 }
 
+/// Parameters that can be modulated per voice, e.g., by LFOs
+pub fn is_lfo_target(parameter: Parameter) -> bool {
+    parameter != Parameter::None && LFO_TARGETS.iter().any(|t| t.parameter == parameter)
+}
+
 /// Parameter with index stored for performance reasons
 #[derive(Debug, Clone, Copy)]
 pub struct LfoTargetParameter {
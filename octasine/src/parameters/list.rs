@@ -117,6 +117,52 @@ pub const PARAMETERS: &[Parameter] = &[
     Parameter::Master(MasterParameter::GlideBpmSync),
     Parameter::Master(MasterParameter::GlideMode),
     Parameter::Master(MasterParameter::GlideRetrigger),
+    Parameter::ModMatrix(0, ModMatrixParameter::Source),
+    Parameter::ModMatrix(0, ModMatrixParameter::Target),
+    Parameter::ModMatrix(0, ModMatrixParameter::Amount),
+    Parameter::ModMatrix(1, ModMatrixParameter::Source),
+    Parameter::ModMatrix(1, ModMatrixParameter::Target),
+    Parameter::ModMatrix(1, ModMatrixParameter::Amount),
+    Parameter::ModMatrix(2, ModMatrixParameter::Source),
+    Parameter::ModMatrix(2, ModMatrixParameter::Target),
+    Parameter::ModMatrix(2, ModMatrixParameter::Amount),
+    Parameter::ModMatrix(3, ModMatrixParameter::Source),
+    Parameter::ModMatrix(3, ModMatrixParameter::Target),
+    Parameter::ModMatrix(3, ModMatrixParameter::Amount),
+    Parameter::ModMatrix(4, ModMatrixParameter::Source),
+    Parameter::ModMatrix(4, ModMatrixParameter::Target),
+    Parameter::ModMatrix(4, ModMatrixParameter::Amount),
+    Parameter::ModMatrix(5, ModMatrixParameter::Source),
+    Parameter::ModMatrix(5, ModMatrixParameter::Target),
+    Parameter::ModMatrix(5, ModMatrixParameter::Amount),
+    Parameter::ModMatrix(6, ModMatrixParameter::Source),
+    Parameter::ModMatrix(6, ModMatrixParameter::Target),
+    Parameter::ModMatrix(6, ModMatrixParameter::Amount),
+    Parameter::ModMatrix(7, ModMatrixParameter::Source),
+    Parameter::ModMatrix(7, ModMatrixParameter::Target),
+    Parameter::ModMatrix(7, ModMatrixParameter::Amount),
+    Parameter::Macro(0),
+    Parameter::Macro(1),
+    Parameter::Macro(2),
+    Parameter::Macro(3),
+    Parameter::Macro(4),
+    Parameter::Macro(5),
+    Parameter::Macro(6),
+    Parameter::Macro(7),
+    Parameter::Master(MasterParameter::VelocityCurve),
+    Parameter::Master(MasterParameter::MaxPolyphony),
+    Parameter::Master(MasterParameter::VoiceStealing),
+    Parameter::Master(MasterParameter::NotePriority),
+    Parameter::Master(MasterParameter::OverlappingVoices),
+    Parameter::Master(MasterParameter::ArpeggiatorMode),
+    Parameter::Master(MasterParameter::ArpeggiatorOctaves),
+    Parameter::Master(MasterParameter::ArpeggiatorRate),
+    Parameter::Master(MasterParameter::ArpeggiatorSwing),
+    Parameter::Master(MasterParameter::ArpeggiatorGate),
+    Parameter::Master(MasterParameter::ArpeggiatorLatch),
+    Parameter::Master(MasterParameter::ChordMemory),
+    Parameter::Master(MasterParameter::SidechainModTarget),
+    Parameter::Master(MasterParameter::SidechainModAmount),
 ];
 
 /// Parameter enum used to abstract over parameter indices
@@ -127,6 +173,8 @@ pub enum Parameter {
     Master(MasterParameter),
     Operator(u8, OperatorParameter),
     Lfo(u8, LfoParameter),
+    ModMatrix(u8, ModMatrixParameter),
+    Macro(u8),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -142,6 +190,20 @@ pub enum MasterParameter {
     GlideBpmSync,
     GlideMode,
     GlideRetrigger,
+    VelocityCurve,
+    MaxPolyphony,
+    VoiceStealing,
+    NotePriority,
+    OverlappingVoices,
+    ArpeggiatorMode,
+    ArpeggiatorOctaves,
+    ArpeggiatorRate,
+    ArpeggiatorSwing,
+    ArpeggiatorGate,
+    ArpeggiatorLatch,
+    ChordMemory,
+    SidechainModTarget,
+    SidechainModAmount,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -179,3 +241,10 @@ pub enum LfoParameter {
     /// Sync LFO phase to key presses. If turned off, start at random phase
     KeySync,
 }
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ModMatrixParameter {
+    Source,
+    Target,
+    Amount,
+}
@@ -0,0 +1,296 @@
+use anyhow::Context;
+use compact_str::{format_compact, CompactString};
+use serde::{Deserialize, Serialize};
+
+use crate::common::NUM_MACROS;
+
+use super::{
+    utils::parse_valid_f32, Parameter, ParameterKey, ParameterValue, SerializableRepresentation,
+    PARAMETERS,
+};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MacroValue(f32);
+
+impl ParameterValue for MacroValue {
+    type Value = f32;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        parse_valid_f32(text, 0.0, 1.0).map(Self)
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(value)
+    }
+    fn to_patch(self) -> f32 {
+        self.0
+    }
+    fn get_formatted(self) -> CompactString {
+        format_compact!("{:.04}", self.0)
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Float(self.0.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MacroCurve {
+    #[default]
+    Linear,
+    /// Slow start, fast end
+    Exponential,
+    /// Fast start, slow end
+    Logarithmic,
+}
+
+impl MacroCurve {
+    pub fn apply(self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Exponential => value * value,
+            Self::Logarithmic => 1.0 - (1.0 - value) * (1.0 - value),
+        }
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "lin" | "linear" => Some(Self::Linear),
+            "exp" | "exponential" => Some(Self::Exponential),
+            "log" | "logarithmic" => Some(Self::Logarithmic),
+            _ => None,
+        }
+    }
+}
+
+impl ::std::fmt::Display for MacroCurve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Linear => "lin",
+            Self::Exponential => "exp",
+            Self::Logarithmic => "log",
+        })
+    }
+}
+
+/// Parameter driven by macro. Range is in patch value space, and min may be
+/// larger than max to invert the mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MacroTarget {
+    pub key: ParameterKey,
+    pub min: f32,
+    pub max: f32,
+    pub curve: MacroCurve,
+}
+
+impl MacroTarget {
+    pub fn new(parameter: Parameter) -> Self {
+        Self {
+            key: parameter.key(),
+            min: 0.0,
+            max: 1.0,
+            curve: MacroCurve::default(),
+        }
+    }
+
+    /// Get target patch value for macro patch value
+    pub fn get_target_value(&self, macro_value: f32) -> f32 {
+        let value = self.curve.apply(macro_value.clamp(0.0, 1.0));
+
+        (self.min + value * (self.max - self.min)).clamp(0.0, 1.0)
+    }
+
+    pub fn parameter(&self) -> Option<Parameter> {
+        PARAMETERS.iter().copied().find(|p| p.key() == self.key)
+    }
+
+    /// Parse text in format "OP 1 vol = 0.0..1.0 lin". Curve is optional.
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let (name, range) = match text.split_once('=') {
+            Some(name_and_range) => name_and_range,
+            None => anyhow::bail!("expected format \"parameter = min..max curve\""),
+        };
+        let name = name.trim().to_lowercase();
+
+        let parameter = match PARAMETERS
+            .iter()
+            .copied()
+            .filter(|p| !matches!(p, Parameter::Macro(_)))
+            .find(|p| p.name().to_lowercase() == name)
+        {
+            Some(parameter) => parameter,
+            None => anyhow::bail!("unknown parameter \"{}\" (macros can't be targets)", name),
+        };
+
+        let mut range_and_curve = range.split_whitespace();
+
+        let (min, max) = match range_and_curve.next().and_then(|r| r.split_once("..")) {
+            Some(min_and_max) => min_and_max,
+            None => anyhow::bail!("expected range in format min..max, e.g., 0.0..1.0"),
+        };
+        let parse_limit = |limit: &str| {
+            parse_valid_f32(limit, 0.0, 1.0)
+                .ok_or_else(|| anyhow::anyhow!("range limit \"{}\" is not a number", limit))
+        };
+        let min = parse_limit(min)?;
+        let max = parse_limit(max)?;
+
+        let curve = match range_and_curve.next() {
+            Some(curve) => match MacroCurve::from_text(curve) {
+                Some(curve) => curve,
+                None => anyhow::bail!("unknown curve \"{}\", expected lin, exp or log", curve),
+            },
+            None => MacroCurve::default(),
+        };
+
+        if let Some(extra) = range_and_curve.next() {
+            anyhow::bail!("unexpected text \"{}\" after curve", extra);
+        }
+
+        Ok(Self {
+            key: parameter.key(),
+            min,
+            max,
+            curve,
+        })
+    }
+
+    /// Parse semicolon-separated targets. Empty text results in no targets.
+    /// Each parameter can only be targeted once.
+    pub fn parse_list(text: &str) -> anyhow::Result<Vec<Self>> {
+        let mut targets: Vec<Self> = Vec::new();
+
+        for (i, target_text) in text
+            .split(';')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .enumerate()
+        {
+            let target = Self::parse(target_text)
+                .with_context(|| format!("target {} (\"{}\")", i + 1, target_text))?;
+
+            if targets.iter().any(|t| t.key == target.key) {
+                anyhow::bail!(
+                    "target {} (\"{}\"): parameter is already targeted",
+                    i + 1,
+                    target_text
+                );
+            }
+
+            targets.push(target);
+        }
+
+        Ok(targets)
+    }
+
+    pub fn format_list(targets: &[Self]) -> String {
+        targets
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+impl ::std::fmt::Display for MacroTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self
+            .parameter()
+            .map(|p| p.name())
+            .unwrap_or_else(|| "?".into());
+
+        write!(
+            f,
+            "{} = {:.02}..{:.02} {}",
+            name, self.min, self.max, self.curve
+        )
+    }
+}
+
+/// Macro target assignments, stored in patch
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MacroMappings(pub [Vec<MacroTarget>; NUM_MACROS]);
+
+/// Macro parameter name including names of targets, e.g.,
+/// "Macro 1 (OP 1 vol, LFO 2 amount)"
+pub fn macro_name_with_targets(macro_index: u8, targets: &[MacroTarget]) -> CompactString {
+    const MAX_NUM_LISTED: usize = 3;
+
+    let name = Parameter::Macro(macro_index).name();
+
+    if targets.is_empty() {
+        return name;
+    }
+
+    let mut target_names = targets
+        .iter()
+        .take(MAX_NUM_LISTED)
+        .filter_map(|t| t.parameter())
+        .map(|p| p.name())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if targets.len() > MAX_NUM_LISTED {
+        target_names.push_str(&format!(" +{}", targets.len() - MAX_NUM_LISTED));
+    }
+
+    format_compact!("{} ({})", name, target_names)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parameters::{MasterParameter, OperatorParameter};
+
+    use super::*;
+
+    #[test]
+    fn test_macro_target_text_roundtrip() {
+        let targets = vec![
+            MacroTarget::new(Parameter::Master(MasterParameter::Volume)),
+            MacroTarget {
+                key: Parameter::Operator(1, OperatorParameter::Feedback).key(),
+                min: 0.75,
+                max: 0.25,
+                curve: MacroCurve::Exponential,
+            },
+        ];
+
+        let text = MacroTarget::format_list(&targets);
+
+        assert_eq!(MacroTarget::parse_list(&text).unwrap(), targets);
+        assert_eq!(MacroTarget::parse_list("").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_macro_target_parse_errors() {
+        let error = |text: &str| format!("{:#}", MacroTarget::parse_list(text).unwrap_err());
+
+        assert!(error("Macro 2 = 0..1").contains("unknown parameter \"macro 2\""));
+        assert!(error("Master volume").contains("expected format"));
+        assert!(error("Master volume = 0.5").contains("expected range"));
+        assert!(error("Master volume = 0..x").contains("range limit \"x\""));
+        assert!(error("Master volume = 0..1 sine").contains("unknown curve \"sine\""));
+        assert!(error("Master volume = 0..1 lin x").contains("unexpected text \"x\""));
+        assert!(error("Master volume = 0..1; master volume = 1..0")
+            .starts_with("target 2 (\"master volume = 1..0\")"));
+    }
+
+    #[test]
+    fn test_macro_target_value() {
+        let target = MacroTarget {
+            key: Parameter::Master(MasterParameter::Volume).key(),
+            min: 1.0,
+            max: 0.5,
+            curve: MacroCurve::Linear,
+        };
+
+        assert_eq!(target.get_target_value(0.0), 1.0);
+        assert_eq!(target.get_target_value(0.5), 0.75);
+        assert_eq!(target.get_target_value(1.0), 0.5);
+    }
+}
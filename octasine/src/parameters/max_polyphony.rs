@@ -0,0 +1,52 @@
+use compact_str::{format_compact, CompactString};
+
+use super::{
+    utils::{map_patch_value_to_step, map_step_to_patch_value},
+    ParameterValue, SerializableRepresentation,
+};
+
+pub const MAX_POLYPHONY_STEPS: &[usize] = &[1, 2, 4, 6, 8, 12, 16, 24, 32, 48, 64, 96, 128];
+
+const DEFAULT_MAX_POLYPHONY: usize = 128;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MaxPolyphonyValue(usize);
+
+impl Default for MaxPolyphonyValue {
+    fn default() -> Self {
+        Self(DEFAULT_MAX_POLYPHONY)
+    }
+}
+
+impl ParameterValue for MaxPolyphonyValue {
+    type Value = usize;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        let value: usize = text.trim().trim_end_matches("voices").trim().parse().ok()?;
+
+        MAX_POLYPHONY_STEPS
+            .iter()
+            .copied()
+            .min_by_key(|step| step.abs_diff(value))
+            .map(Self)
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(map_patch_value_to_step(MAX_POLYPHONY_STEPS, value))
+    }
+    fn to_patch(self) -> f32 {
+        map_step_to_patch_value(MAX_POLYPHONY_STEPS, self.0)
+    }
+    fn get_formatted(self) -> CompactString {
+        format_compact!("{}", self.0)
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Float(self.0 as f64)
+    }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+use super::ParameterKey;
+
+/// Where a learned MIDI CC mapping is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiLearnScope {
+    /// Stored in current patch
+    Patch,
+    /// Stored in settings, applies to all patches
+    Global,
+}
+
+/// Controllers 0 and 32 select bank and 120 to 127 are channel mode
+/// messages
+pub fn is_learnable_controller(controller: u8) -> bool {
+    !matches!(controller, 0 | 32 | 120..)
+}
+
+/// Hardware MIDI CC driving a parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MidiMapping {
+    pub controller: u8,
+    pub key: ParameterKey,
+}
+
+/// MIDI CC mappings. A controller can drive several parameters, but each
+/// parameter is driven by at most one controller.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MidiMappings(pub Vec<MidiMapping>);
+
+impl MidiMappings {
+    pub fn get_controller(&self, key: ParameterKey) -> Option<u8> {
+        self.0
+            .iter()
+            .find(|mapping| mapping.key == key)
+            .map(|mapping| mapping.controller)
+    }
+
+    /// Map parameter to controller, replacing any previous mapping for it
+    pub fn set(&mut self, key: ParameterKey, controller: u8) {
+        self.remove(key);
+
+        self.0.push(MidiMapping { controller, key });
+    }
+
+    /// Returns true if parameter was mapped
+    pub fn remove(&mut self, key: ParameterKey) -> bool {
+        let len = self.0.len();
+
+        self.0.retain(|mapping| mapping.key != key);
+
+        self.0.len() != len
+    }
+
+    pub fn iter_targets(&self, controller: u8) -> impl Iterator<Item = ParameterKey> + '_ {
+        self.0
+            .iter()
+            .filter(move |mapping| mapping.controller == controller)
+            .map(|mapping| mapping.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parameters::{MasterParameter, Parameter};
+
+    use super::*;
+
+    #[test]
+    fn test_midi_mappings() {
+        let volume = Parameter::Master(MasterParameter::Volume).key();
+        let frequency = Parameter::Master(MasterParameter::Frequency).key();
+
+        let mut mappings = MidiMappings::default();
+
+        mappings.set(volume, 7);
+        mappings.set(frequency, 7);
+        mappings.set(volume, 8);
+
+        assert_eq!(mappings.get_controller(volume), Some(8));
+        assert_eq!(
+            mappings.iter_targets(7).collect::<Vec<_>>(),
+            vec![frequency]
+        );
+
+        assert!(mappings.remove(frequency));
+        assert!(!mappings.remove(frequency));
+        assert_eq!(mappings.iter_targets(7).count(), 0);
+    }
+}
@@ -1,3 +1,10 @@
+pub mod arpeggiator_gate;
+pub mod arpeggiator_latch;
+pub mod arpeggiator_mode;
+pub mod arpeggiator_octaves;
+pub mod arpeggiator_rate;
+pub mod arpeggiator_swing;
+pub mod chord_memory;
 pub mod glide_active;
 pub mod glide_bpm_sync;
 pub mod glide_mode;
@@ -13,9 +20,16 @@ pub mod lfo_mode;
 pub mod lfo_shape;
 pub mod lfo_target;
 pub mod list;
+pub mod macros;
 pub mod master_frequency;
 pub mod master_pitch_bend_range;
 pub mod master_volume;
+pub mod max_polyphony;
+pub mod midi_learn;
+pub mod mod_matrix_amount;
+pub mod mod_matrix_source;
+pub mod mod_matrix_target;
+pub mod note_priority;
 pub mod operator_active;
 pub mod operator_envelope;
 pub mod operator_feedback;
@@ -28,9 +42,15 @@ pub mod operator_mod_target;
 pub mod operator_panning;
 pub mod operator_volume;
 pub mod operator_wave_type;
+pub mod overlapping_voices;
+pub mod randomize;
+pub mod sidechain_mod_amount;
+pub mod sidechain_mod_target;
 pub mod utils;
+pub mod velocity_curve;
 pub mod velocity_sensitivity;
 pub mod voice_mode;
+pub mod voice_stealing;
 
 use compact_str::{format_compact, CompactString};
 pub use lfo_active::LfoActiveValue;
@@ -43,9 +63,13 @@ pub use lfo_mode::LfoModeValue;
 pub use lfo_shape::LfoShapeValue;
 pub use lfo_target::*;
 pub use list::*;
+pub use macros::MacroValue;
 pub use master_frequency::MasterFrequencyValue;
 pub use master_pitch_bend_range::{MasterPitchBendRangeDownValue, MasterPitchBendRangeUpValue};
 pub use master_volume::MasterVolumeValue;
+pub use mod_matrix_amount::ModMatrixAmountValue;
+pub use mod_matrix_source::{ModMatrixSource, ModMatrixSourceValue};
+pub use mod_matrix_target::ModMatrixTargetValue;
 pub use operator_active::OperatorActiveValue;
 pub use operator_envelope::*;
 pub use operator_feedback::OperatorFeedbackValue;
@@ -60,7 +84,7 @@ pub use operator_volume::OperatorVolumeValue;
 pub use operator_wave_type::OperatorWaveTypeValue;
 use serde::{Deserialize, Serialize};
 
-use crate::common::{NUM_LFOS, NUM_OPERATORS};
+use crate::common::{NUM_LFOS, NUM_MOD_MATRIX_SLOTS, NUM_OPERATORS};
 
 /// Storage of audio parameter values with utilities for conversions
 /// to and from patch values.
@@ -116,6 +140,20 @@ impl Parameter {
             Self::Master(MasterParameter::GlideBpmSync) => "Glide bpm sync".into(),
             Self::Master(MasterParameter::GlideMode) => "Glide mode".into(),
             Self::Master(MasterParameter::GlideRetrigger) => "Glide retrigger".into(),
+            Self::Master(MasterParameter::VelocityCurve) => "Velocity curve".into(),
+            Self::Master(MasterParameter::MaxPolyphony) => "Max polyphony".into(),
+            Self::Master(MasterParameter::VoiceStealing) => "Voice stealing".into(),
+            Self::Master(MasterParameter::NotePriority) => "Note priority".into(),
+            Self::Master(MasterParameter::OverlappingVoices) => "Overlapping voices".into(),
+            Self::Master(MasterParameter::ArpeggiatorMode) => "Arpeggiator mode".into(),
+            Self::Master(MasterParameter::ArpeggiatorOctaves) => "Arpeggiator octaves".into(),
+            Self::Master(MasterParameter::ArpeggiatorRate) => "Arpeggiator rate".into(),
+            Self::Master(MasterParameter::ArpeggiatorSwing) => "Arpeggiator swing".into(),
+            Self::Master(MasterParameter::ArpeggiatorGate) => "Arpeggiator gate".into(),
+            Self::Master(MasterParameter::ArpeggiatorLatch) => "Arpeggiator latch".into(),
+            Self::Master(MasterParameter::ChordMemory) => "Chord memory".into(),
+            Self::Master(MasterParameter::SidechainModTarget) => "Sidechain mod target".into(),
+            Self::Master(MasterParameter::SidechainModAmount) => "Sidechain mod amount".into(),
             Self::Operator(index, p) => match p {
                 OperatorParameter::Volume => format_compact!("OP {} vol", index + 1),
                 OperatorParameter::Active => format_compact!("OP {} active", index + 1),
@@ -157,6 +195,12 @@ impl Parameter {
                 LfoParameter::Active => format_compact!("LFO {} active", index + 1),
                 LfoParameter::KeySync => format_compact!("LFO {} key sync", index + 1),
             },
+            Self::ModMatrix(index, p) => match p {
+                ModMatrixParameter::Source => format_compact!("Mod {} source", index + 1),
+                ModMatrixParameter::Target => format_compact!("Mod {} target", index + 1),
+                ModMatrixParameter::Amount => format_compact!("Mod {} amount", index + 1),
+            },
+            Self::Macro(index) => format_compact!("Macro {}", index + 1),
         }
     }
 
@@ -174,6 +218,98 @@ impl Parameter {
             Self::Master(_) => "Master".into(),
             Self::Operator(index, _) => format_compact!("Operator {}", *index),
             Self::Lfo(index, _) => format_compact!("LFO {}", *index),
+            Self::ModMatrix(index, _) => format_compact!("Mod matrix {}", *index),
+            Self::Macro(_) => "Macros".into(),
+        }
+    }
+
+    /// OSC address, e.g., "/octasine/op/1/volume". Operator, LFO, mod
+    /// matrix slot and macro numbers start at 1.
+    pub fn osc_address(&self) -> CompactString {
+        match self {
+            Self::None => "/octasine/none".into(),
+            Self::Master(p) => {
+                let name = match p {
+                    MasterParameter::Volume => "volume",
+                    MasterParameter::Frequency => "frequency",
+                    MasterParameter::PitchBendRangeUp => "pitch_bend_range_up",
+                    MasterParameter::PitchBendRangeDown => "pitch_bend_range_down",
+                    MasterParameter::VelocitySensitivityVolume => "velocity_sensitivity",
+                    MasterParameter::VoiceMode => "voice_mode",
+                    MasterParameter::GlideActive => "glide_active",
+                    MasterParameter::GlideTime => "glide_time",
+                    MasterParameter::GlideBpmSync => "glide_bpm_sync",
+                    MasterParameter::GlideMode => "glide_mode",
+                    MasterParameter::GlideRetrigger => "glide_retrigger",
+                    MasterParameter::VelocityCurve => "velocity_curve",
+                    MasterParameter::MaxPolyphony => "max_polyphony",
+                    MasterParameter::VoiceStealing => "voice_stealing",
+                    MasterParameter::NotePriority => "note_priority",
+                    MasterParameter::OverlappingVoices => "overlapping_voices",
+                    MasterParameter::ArpeggiatorMode => "arpeggiator_mode",
+                    MasterParameter::ArpeggiatorOctaves => "arpeggiator_octaves",
+                    MasterParameter::ArpeggiatorRate => "arpeggiator_rate",
+                    MasterParameter::ArpeggiatorSwing => "arpeggiator_swing",
+                    MasterParameter::ArpeggiatorGate => "arpeggiator_gate",
+                    MasterParameter::ArpeggiatorLatch => "arpeggiator_latch",
+                    MasterParameter::ChordMemory => "chord_memory",
+                    MasterParameter::SidechainModTarget => "sidechain_mod_target",
+                    MasterParameter::SidechainModAmount => "sidechain_mod_amount",
+                };
+
+                format_compact!("/octasine/master/{}", name)
+            }
+            Self::Operator(index, p) => {
+                let name = match p {
+                    OperatorParameter::Volume => "volume",
+                    OperatorParameter::Active => "active",
+                    OperatorParameter::MixOut => "mix_out",
+                    OperatorParameter::Panning => "panning",
+                    OperatorParameter::WaveType => "wave_type",
+                    OperatorParameter::ModTargets => "mod_targets",
+                    OperatorParameter::ModOut => "mod_out",
+                    OperatorParameter::Feedback => "feedback",
+                    OperatorParameter::FrequencyRatio => "frequency_ratio",
+                    OperatorParameter::FrequencyFree => "frequency_free",
+                    OperatorParameter::FrequencyFine => "frequency_fine",
+                    OperatorParameter::AttackDuration => "attack_duration",
+                    OperatorParameter::DecayDuration => "decay_duration",
+                    OperatorParameter::SustainVolume => "sustain_volume",
+                    OperatorParameter::ReleaseDuration => "release_duration",
+                    OperatorParameter::EnvelopeLockGroup => "envelope_lock_group",
+                    OperatorParameter::VelocitySensitivityModOut => "mod_out_velocity_sensitivity",
+                    OperatorParameter::VelocitySensitivityFeedback => {
+                        "feedback_velocity_sensitivity"
+                    }
+                };
+
+                format_compact!("/octasine/op/{}/{}", index + 1, name)
+            }
+            Self::Lfo(index, p) => {
+                let name = match p {
+                    LfoParameter::Target => "target",
+                    LfoParameter::BpmSync => "bpm_sync",
+                    LfoParameter::FrequencyRatio => "frequency_ratio",
+                    LfoParameter::FrequencyFree => "frequency_free",
+                    LfoParameter::Mode => "oneshot",
+                    LfoParameter::Shape => "shape",
+                    LfoParameter::Amount => "amount",
+                    LfoParameter::Active => "active",
+                    LfoParameter::KeySync => "key_sync",
+                };
+
+                format_compact!("/octasine/lfo/{}/{}", index + 1, name)
+            }
+            Self::ModMatrix(index, p) => {
+                let name = match p {
+                    ModMatrixParameter::Source => "source",
+                    ModMatrixParameter::Target => "target",
+                    ModMatrixParameter::Amount => "amount",
+                };
+
+                format_compact!("/octasine/mod_matrix/{}/{}", index + 1, name)
+            }
+            Self::Macro(index) => format_compact!("/octasine/macro/{}", index + 1),
         }
     }
 
@@ -197,6 +333,20 @@ impl Parameter {
             Self::Master(MasterParameter::GlideBpmSync) => "Glide bpm sync".into(),
             Self::Master(MasterParameter::GlideMode) => "Glide mode".into(),
             Self::Master(MasterParameter::GlideRetrigger) => "Glide retrigger".into(),
+            Self::Master(MasterParameter::VelocityCurve) => "Velocity curve".into(),
+            Self::Master(MasterParameter::MaxPolyphony) => "Max polyphony".into(),
+            Self::Master(MasterParameter::VoiceStealing) => "Voice stealing".into(),
+            Self::Master(MasterParameter::NotePriority) => "Note priority".into(),
+            Self::Master(MasterParameter::OverlappingVoices) => "Overlapping voices".into(),
+            Self::Master(MasterParameter::ArpeggiatorMode) => "Arpeggiator mode".into(),
+            Self::Master(MasterParameter::ArpeggiatorOctaves) => "Arpeggiator octaves".into(),
+            Self::Master(MasterParameter::ArpeggiatorRate) => "Arpeggiator rate".into(),
+            Self::Master(MasterParameter::ArpeggiatorSwing) => "Arpeggiator swing".into(),
+            Self::Master(MasterParameter::ArpeggiatorGate) => "Arpeggiator gate".into(),
+            Self::Master(MasterParameter::ArpeggiatorLatch) => "Arpeggiator latch".into(),
+            Self::Master(MasterParameter::ChordMemory) => "Chord memory".into(),
+            Self::Master(MasterParameter::SidechainModTarget) => "Sidechain mod target".into(),
+            Self::Master(MasterParameter::SidechainModAmount) => "Sidechain mod amount".into(),
             Self::Operator(index, p) => match p {
                 OperatorParameter::Volume => format!("OP {} vol", index + 1),
                 OperatorParameter::Active => format!("OP {} active", index + 1),
@@ -232,6 +382,12 @@ impl Parameter {
                 LfoParameter::Active => format!("LFO {} active", index + 1),
                 LfoParameter::KeySync => format!("LFO {} key sync", index + 1),
             },
+            Self::ModMatrix(index, p) => match p {
+                ModMatrixParameter::Source => format!("Mod matrix {} source", index + 1),
+                ModMatrixParameter::Target => format!("Mod matrix {} target", index + 1),
+                ModMatrixParameter::Amount => format!("Mod matrix {} amount", index + 1),
+            },
+            Self::Macro(index) => format!("Macro {}", index + 1),
         };
 
         let hash = seahash::hash(name.as_bytes());
@@ -278,6 +434,22 @@ impl LfoParameter {
     }
 }
 
+impl ModMatrixParameter {
+    pub const fn index_array(self) -> [u8; NUM_MOD_MATRIX_SLOTS] {
+        let mut arr = [0; NUM_MOD_MATRIX_SLOTS];
+
+        let mut i = 0;
+
+        while i < arr.len() {
+            arr[i] = Parameter::ModMatrix(i as u8, self).to_index();
+
+            i += 1;
+        }
+
+        arr
+    }
+}
+
 /// All metadata for a parameter
 #[derive(Debug, Clone, Copy)]
 pub struct WrappedParameter {
@@ -320,4 +492,11 @@ mod tests {
 
         assert_eq!(set.len(), PARAMETERS.len());
     }
+
+    #[test]
+    fn test_osc_address_uniqueness() {
+        let set: HashSet<_> = PARAMETERS.iter().map(|p| p.osc_address()).collect();
+
+        assert_eq!(set.len(), PARAMETERS.len());
+    }
 }
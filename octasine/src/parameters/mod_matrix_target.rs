@@ -0,0 +1,65 @@
+use compact_str::CompactString;
+
+use super::{
+    utils::*, LfoTargetParameter, Parameter, ParameterValue, SerializableRepresentation,
+    LFO_TARGETS,
+};
+
+/// Modulation matrix target. Any LFO target is a valid choice.
+#[derive(Debug, Clone, Copy)]
+pub struct ModMatrixTargetValue(pub LfoTargetParameter);
+
+impl Default for ModMatrixTargetValue {
+    fn default() -> Self {
+        Self(LfoTargetParameter::new(Parameter::None))
+    }
+}
+
+impl ParameterValue for ModMatrixTargetValue {
+    type Value = LfoTargetParameter;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+
+        LFO_TARGETS
+            .iter()
+            .find(|target| target.parameter().name().to_lowercase() == text)
+            .map(|target| Self(*target))
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(map_patch_value_to_step(LFO_TARGETS, value))
+    }
+    fn to_patch(self) -> f32 {
+        map_step_to_patch_value(LFO_TARGETS, self.0)
+    }
+    fn get_formatted(self) -> CompactString {
+        self.0.parameter().name()
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Other(self.get_formatted())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mod_matrix_target_text_roundtrip() {
+        for target in LFO_TARGETS.iter().copied() {
+            let value = ModMatrixTargetValue::new_from_audio(target);
+            let parsed = ModMatrixTargetValue::new_from_text(&value.get_formatted()).unwrap();
+
+            assert_eq!(parsed.get().parameter(), target.parameter());
+        }
+
+        assert!(ModMatrixTargetValue::new_from_text("Mod 1 amount").is_none());
+    }
+}
@@ -0,0 +1,66 @@
+use compact_str::CompactString;
+
+use super::{
+    utils::{map_patch_value_to_step, map_step_to_patch_value},
+    ParameterValue, SerializableRepresentation,
+};
+
+const STEPS: &[NotePriority] = &[
+    NotePriority::Last,
+    NotePriority::Lowest,
+    NotePriority::Highest,
+];
+
+/// Which of the held keys monophonic voice plays
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum NotePriority {
+    /// Most recently pressed key
+    #[default]
+    Last,
+    Lowest,
+    Highest,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NotePriorityValue(NotePriority);
+
+impl ParameterValue for NotePriorityValue {
+    type Value = NotePriority;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        let text = text.to_lowercase();
+
+        if text.contains("last") {
+            Some(Self(NotePriority::Last))
+        } else if text.contains("low") {
+            Some(Self(NotePriority::Lowest))
+        } else if text.contains("high") {
+            Some(Self(NotePriority::Highest))
+        } else {
+            None
+        }
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(map_patch_value_to_step(STEPS, value))
+    }
+    fn to_patch(self) -> f32 {
+        map_step_to_patch_value(STEPS, self.0)
+    }
+    fn get_formatted(self) -> CompactString {
+        match self.0 {
+            NotePriority::Last => "LAST".into(),
+            NotePriority::Lowest => "LOWEST".into(),
+            NotePriority::Highest => "HIGHEST".into(),
+        }
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Other(self.get_formatted())
+    }
+}
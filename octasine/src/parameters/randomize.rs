@@ -0,0 +1,183 @@
+use fastrand::Rng;
+
+use super::{
+    LfoParameter, OperatorAttackDurationValue, OperatorDecayDurationValue,
+    OperatorFrequencyRatioValue, OperatorMixOutValue, OperatorParameter,
+    OperatorReleaseDurationValue, OperatorVolumeValue, Parameter, ParameterValue,
+    ENVELOPE_MIN_DURATION,
+};
+
+/// Operator frequency ratios that reliably sound musical
+const MUSICAL_RATIOS: &[&str] = &[
+    "1/4", "1/2", "1", "3/2", "2", "5/2", "3", "4", "5", "6", "8",
+];
+
+enum RandomValue {
+    /// Value in a continuous range, which mutation moves towards
+    Continuous(f32),
+    /// Discrete choice, which mutation switches to with a probability
+    /// equal to the mutation amount
+    Discrete(f32),
+}
+
+/// Get random value in patch scale for parameters that make sense to
+/// randomize. Ranges are chosen to give usable results, e.g., operator 1
+/// always stays audible and attacks tend to be short.
+fn random_value(rng: &mut Rng, parameter: Parameter) -> Option<RandomValue> {
+    use RandomValue::*;
+
+    let value = match parameter {
+        Parameter::Operator(index, p) => match p {
+            OperatorParameter::Active => Discrete(if index == 0 || rng.f32() < 0.75 {
+                1.0
+            } else {
+                0.0
+            }),
+            OperatorParameter::Volume => {
+                Continuous(OperatorVolumeValue::new_from_audio(range(rng, 0.5, 1.0)).to_patch())
+            }
+            OperatorParameter::MixOut => {
+                let mix = if index == 0 {
+                    range(rng, 0.5, 1.0)
+                } else if rng.bool() {
+                    range(rng, 0.0, 1.0)
+                } else {
+                    0.0
+                };
+
+                Continuous(OperatorMixOutValue::new_from_audio(mix).to_patch())
+            }
+            OperatorParameter::Panning => Continuous(range(rng, 0.25, 0.75)),
+            OperatorParameter::WaveType | OperatorParameter::ModTargets => Discrete(rng.f32()),
+            OperatorParameter::ModOut => Continuous(range(rng, 0.0, 0.6)),
+            OperatorParameter::Feedback => Continuous(range(rng, 0.0, 0.4)),
+            OperatorParameter::FrequencyRatio => {
+                let ratio = MUSICAL_RATIOS[rng.usize(..MUSICAL_RATIOS.len())];
+
+                Discrete(OperatorFrequencyRatioValue::new_from_text(ratio)?.to_patch())
+            }
+            OperatorParameter::AttackDuration => {
+                let duration = ENVELOPE_MIN_DURATION + rng.f64().powi(2) * 0.5;
+
+                Continuous(OperatorAttackDurationValue::new_from_audio(duration).to_patch())
+            }
+            OperatorParameter::DecayDuration => {
+                let duration = 0.05 + rng.f64() * 1.95;
+
+                Continuous(OperatorDecayDurationValue::new_from_audio(duration).to_patch())
+            }
+            OperatorParameter::SustainVolume => Continuous(rng.f32()),
+            OperatorParameter::ReleaseDuration => {
+                let duration = 0.05 + rng.f64() * 1.45;
+
+                Continuous(OperatorReleaseDurationValue::new_from_audio(duration).to_patch())
+            }
+            OperatorParameter::VelocitySensitivityModOut
+            | OperatorParameter::VelocitySensitivityFeedback => Continuous(range(rng, 0.0, 0.5)),
+            // Keep tuning and envelope groups
+            OperatorParameter::FrequencyFree
+            | OperatorParameter::FrequencyFine
+            | OperatorParameter::EnvelopeLockGroup => return None,
+        },
+        Parameter::Lfo(_, p) => match p {
+            LfoParameter::Target | LfoParameter::Mode | LfoParameter::Shape => Discrete(rng.f32()),
+            LfoParameter::FrequencyRatio | LfoParameter::FrequencyFree => Continuous(rng.f32()),
+            LfoParameter::Amount => Continuous(range(rng, 0.0, 0.5)),
+            LfoParameter::Active => Discrete(if rng.bool() { 1.0 } else { 0.0 }),
+            LfoParameter::BpmSync | LfoParameter::KeySync => return None,
+        },
+        // Master parameters, modulation matrix and macros are left alone
+        Parameter::None
+        | Parameter::Master(_)
+        | Parameter::ModMatrix(_, _)
+        | Parameter::Macro(_) => return None,
+    };
+
+    Some(value)
+}
+
+/// Get random patch value, or None if parameter isn't randomized
+pub fn randomize_value(rng: &mut Rng, parameter: Parameter) -> Option<f32> {
+    match random_value(rng, parameter)? {
+        RandomValue::Continuous(value) | RandomValue::Discrete(value) => Some(value),
+    }
+}
+
+/// Move patch value towards random value by amount (0.0 to 1.0), or
+/// return None if parameter isn't randomized. Non-finite amounts leave
+/// value unchanged.
+pub fn mutate_value(rng: &mut Rng, parameter: Parameter, value: f32, amount: f32) -> Option<f32> {
+    let amount = if amount.is_finite() {
+        amount.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    match random_value(rng, parameter)? {
+        RandomValue::Continuous(target) => {
+            Some((value + (target - value) * amount).clamp(0.0, 1.0))
+        }
+        RandomValue::Discrete(target) => {
+            if rng.f32() < amount {
+                Some(target)
+            } else {
+                Some(value)
+            }
+        }
+    }
+}
+
+fn range(rng: &mut Rng, min: f32, max: f32) -> f32 {
+    min + rng.f32() * (max - min)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parameters::MasterParameter;
+
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_randomize_and_mutate() {
+        let mut rng = Rng::with_seed(123);
+
+        for ratio in MUSICAL_RATIOS {
+            assert!(OperatorFrequencyRatioValue::new_from_text(ratio).is_some());
+        }
+
+        for parameter in (0..).map_while(Parameter::from_index) {
+            for _ in 0..100 {
+                if let Some(value) = randomize_value(&mut rng, parameter) {
+                    assert!((0.0..=1.0).contains(&value), "{:?}: {}", parameter, value);
+                }
+
+                if let Some(value) = mutate_value(&mut rng, parameter, 0.5, 0.0) {
+                    assert_eq!(value, 0.5);
+                }
+
+                for amount in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+                    if let Some(value) = mutate_value(&mut rng, parameter, 0.5, amount) {
+                        assert_eq!(value, 0.5);
+                    }
+                }
+
+                if let Some(value) = mutate_value(&mut rng, parameter, 0.5, 10.0) {
+                    assert!((0.0..=1.0).contains(&value), "{:?}: {}", parameter, value);
+                }
+            }
+        }
+
+        let mix = Parameter::Operator(0, OperatorParameter::MixOut);
+
+        for _ in 0..100 {
+            let value = randomize_value(&mut rng, mix).unwrap();
+
+            assert!(OperatorMixOutValue::new_from_patch(value).get() >= 0.5);
+        }
+
+        let master_volume = Parameter::Master(MasterParameter::Volume);
+
+        assert_eq!(randomize_value(&mut rng, master_volume), None);
+    }
+}
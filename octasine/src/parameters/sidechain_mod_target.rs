@@ -0,0 +1,84 @@
+use compact_str::{format_compact, CompactString};
+
+use super::{
+    utils::{map_patch_value_to_step, map_step_to_patch_value},
+    ParameterValue, SerializableRepresentation,
+};
+
+const STEPS: &[SidechainModTarget] = &[
+    SidechainModTarget::None,
+    SidechainModTarget::Operator(0),
+    SidechainModTarget::Operator(1),
+    SidechainModTarget::Operator(2),
+    SidechainModTarget::Operator(3),
+];
+
+/// Operator that audio input from host phase modulates
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SidechainModTarget {
+    #[default]
+    None,
+    Operator(u8),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SidechainModTargetValue(SidechainModTarget);
+
+impl ParameterValue for SidechainModTargetValue {
+    type Value = SidechainModTarget;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+
+        STEPS
+            .iter()
+            .find(|target| Self(**target).get_formatted().to_lowercase() == text)
+            .map(|target| Self(*target))
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(map_patch_value_to_step(STEPS, value))
+    }
+    fn to_patch(self) -> f32 {
+        map_step_to_patch_value(STEPS, self.0)
+    }
+    fn get_formatted(self) -> CompactString {
+        match self.0 {
+            SidechainModTarget::None => "OFF".into(),
+            SidechainModTarget::Operator(index) => format_compact!("OP {}", index + 1),
+        }
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Other(self.get_formatted())
+    }
+
+    fn get_text_choices() -> Option<Vec<CompactString>> {
+        Some(
+            STEPS
+                .iter()
+                .map(|target| Self(*target).get_formatted())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidechain_mod_target_text_roundtrip() {
+        for target in STEPS.iter().copied() {
+            let value = SidechainModTargetValue::new_from_audio(target);
+            let parsed = SidechainModTargetValue::new_from_text(&value.get_formatted()).unwrap();
+
+            assert_eq!(parsed.get(), target);
+        }
+    }
+}
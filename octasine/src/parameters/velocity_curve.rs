@@ -0,0 +1,223 @@
+use arrayvec::ArrayVec;
+use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    utils::{map_patch_value_to_step, map_step_to_patch_value, parse_valid_f32},
+    ParameterValue, SerializableRepresentation,
+};
+
+pub const MAX_VELOCITY_CURVE_BREAKPOINTS: usize = 8;
+
+const STEPS: &[VelocityCurve] = &[
+    VelocityCurve::Soft,
+    VelocityCurve::Linear,
+    VelocityCurve::Hard,
+    VelocityCurve::Custom,
+];
+
+/// Mapping from MIDI key velocity to velocity used for volume, modulation
+/// output and feedback, applied before velocity sensitivity
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum VelocityCurve {
+    /// Louder output for lighter playing
+    Soft,
+    #[default]
+    Linear,
+    /// Quieter output for lighter playing
+    Hard,
+    /// Use breakpoints stored in patch
+    Custom,
+}
+
+impl VelocityCurve {
+    pub fn apply(self, velocity: f32, custom: &CustomVelocityCurve) -> f32 {
+        match self {
+            Self::Soft => velocity.sqrt(),
+            Self::Linear => velocity,
+            Self::Hard => velocity * velocity,
+            Self::Custom => custom.apply(velocity),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VelocityCurveValue(VelocityCurve);
+
+impl ParameterValue for VelocityCurveValue {
+    type Value = VelocityCurve;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        let text = text.to_lowercase();
+
+        if text.contains("soft") {
+            Some(Self(VelocityCurve::Soft))
+        } else if text.contains("lin") {
+            Some(Self(VelocityCurve::Linear))
+        } else if text.contains("hard") {
+            Some(Self(VelocityCurve::Hard))
+        } else if text.contains("custom") {
+            Some(Self(VelocityCurve::Custom))
+        } else {
+            None
+        }
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(map_patch_value_to_step(STEPS, value))
+    }
+    fn to_patch(self) -> f32 {
+        map_step_to_patch_value(STEPS, self.0)
+    }
+    fn get_formatted(self) -> CompactString {
+        match self.0 {
+            VelocityCurve::Soft => "SOFT".into(),
+            VelocityCurve::Linear => "LINEAR".into(),
+            VelocityCurve::Hard => "HARD".into(),
+            VelocityCurve::Custom => "CUSTOM".into(),
+        }
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Other(self.get_formatted())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VelocityCurveBreakpoint {
+    pub velocity: f32,
+    pub value: f32,
+}
+
+/// Piecewise linear velocity curve. Velocities outside of the breakpoints
+/// are interpolated towards (0.0, 0.0) and (1.0, 1.0).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomVelocityCurve {
+    breakpoints: ArrayVec<VelocityCurveBreakpoint, MAX_VELOCITY_CURVE_BREAKPOINTS>,
+}
+
+impl CustomVelocityCurve {
+    /// Create curve from breakpoints. Breakpoints are sorted by velocity.
+    /// Returns None if there are too many breakpoints or if any of them are
+    /// outside of the range 0.0 to 1.0.
+    pub fn new(mut breakpoints: Vec<VelocityCurveBreakpoint>) -> Option<Self> {
+        let valid_range = 0.0..=1.0;
+
+        if breakpoints
+            .iter()
+            .any(|b| !valid_range.contains(&b.velocity) || !valid_range.contains(&b.value))
+        {
+            return None;
+        }
+
+        breakpoints.sort_by(|a, b| a.velocity.total_cmp(&b.velocity));
+
+        Some(Self {
+            breakpoints: ArrayVec::try_from(&breakpoints[..]).ok()?,
+        })
+    }
+
+    pub fn breakpoints(&self) -> &[VelocityCurveBreakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn apply(&self, velocity: f32) -> f32 {
+        let mut previous = VelocityCurveBreakpoint {
+            velocity: 0.0,
+            value: 0.0,
+        };
+
+        let end = VelocityCurveBreakpoint {
+            velocity: 1.0,
+            value: 1.0,
+        };
+
+        for next in self
+            .breakpoints
+            .iter()
+            .copied()
+            .chain(::std::iter::once(end))
+        {
+            if velocity <= next.velocity {
+                let width = next.velocity - previous.velocity;
+
+                if width <= 0.0 {
+                    return next.value;
+                }
+
+                let factor = (velocity - previous.velocity) / width;
+
+                return previous.value + factor * (next.value - previous.value);
+            }
+
+            previous = next;
+        }
+
+        previous.value
+    }
+
+    /// Parse text in format "0.25:0.5, 0.75:0.9", where each entry is
+    /// velocity:value
+    pub fn parse(text: &str) -> Option<Self> {
+        let breakpoints = text
+            .split(',')
+            .filter(|t| !t.trim().is_empty())
+            .map(|t| {
+                let (velocity, value) = t.split_once(':')?;
+
+                Some(VelocityCurveBreakpoint {
+                    velocity: parse_valid_f32(velocity.trim(), 0.0, 1.0)?,
+                    value: parse_valid_f32(value.trim(), 0.0, 1.0)?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Self::new(breakpoints)
+    }
+}
+
+impl ::std::fmt::Display for CustomVelocityCurve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, b) in self.breakpoints.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{:.02}:{:.02}", b.velocity, b.value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_custom_velocity_curve() {
+        let curve = CustomVelocityCurve::parse("0.75:0.5, 0.25:0.5").unwrap();
+
+        assert_eq!(curve.apply(0.0), 0.0);
+        assert_eq!(curve.apply(0.125), 0.25);
+        assert_eq!(curve.apply(0.5), 0.5);
+        assert_eq!(curve.apply(0.875), 0.75);
+        assert_eq!(curve.apply(1.0), 1.0);
+
+        assert_eq!(
+            CustomVelocityCurve::parse(&curve.to_string()),
+            Some(curve.clone())
+        );
+        assert_eq!(
+            CustomVelocityCurve::parse(""),
+            Some(CustomVelocityCurve::default())
+        );
+        assert_eq!(CustomVelocityCurve::parse("0.5"), None);
+    }
+}
@@ -0,0 +1,72 @@
+use compact_str::CompactString;
+
+use super::{
+    utils::{map_patch_value_to_step, map_step_to_patch_value},
+    ParameterValue, SerializableRepresentation,
+};
+
+const STEPS: &[VoiceStealing] = &[
+    VoiceStealing::Oldest,
+    VoiceStealing::Quietest,
+    VoiceStealing::Lowest,
+    VoiceStealing::Highest,
+];
+
+/// Which voice to kill when polyphony limit is reached. Voices with released
+/// keys are always stolen before voices with pressed keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum VoiceStealing {
+    #[default]
+    Oldest,
+    /// Voice with lowest sum of operator envelope volumes
+    Quietest,
+    Lowest,
+    Highest,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VoiceStealingValue(VoiceStealing);
+
+impl ParameterValue for VoiceStealingValue {
+    type Value = VoiceStealing;
+
+    fn new_from_audio(value: Self::Value) -> Self {
+        Self(value)
+    }
+    fn new_from_text(text: &str) -> Option<Self> {
+        let text = text.to_lowercase();
+
+        if text.contains("old") {
+            Some(Self(VoiceStealing::Oldest))
+        } else if text.contains("quiet") {
+            Some(Self(VoiceStealing::Quietest))
+        } else if text.contains("low") {
+            Some(Self(VoiceStealing::Lowest))
+        } else if text.contains("high") {
+            Some(Self(VoiceStealing::Highest))
+        } else {
+            None
+        }
+    }
+    fn get(self) -> Self::Value {
+        self.0
+    }
+    fn new_from_patch(value: f32) -> Self {
+        Self(map_patch_value_to_step(STEPS, value))
+    }
+    fn to_patch(self) -> f32 {
+        map_step_to_patch_value(STEPS, self.0)
+    }
+    fn get_formatted(self) -> CompactString {
+        match self.0 {
+            VoiceStealing::Oldest => "OLDEST".into(),
+            VoiceStealing::Quietest => "QUIETEST".into(),
+            VoiceStealing::Lowest => "LOWEST".into(),
+            VoiceStealing::Highest => "HIGHEST".into(),
+        }
+    }
+
+    fn get_serializable(&self) -> SerializableRepresentation {
+        SerializableRepresentation::Other(self.get_formatted())
+    }
+}
@@ -15,7 +15,7 @@ use once_cell::sync::Lazy;
 pub const ID: *const c_char =
     unsafe { CStr::from_bytes_with_nul_unchecked(b"OctaSine\0").as_ptr() };
 const NAME: *const c_char = unsafe { CStr::from_bytes_with_nul_unchecked(b"OctaSine\0").as_ptr() };
-const VENDOR: *const c_char =
+pub const VENDOR: *const c_char =
     unsafe { CStr::from_bytes_with_nul_unchecked(b"Joakim Frostegard\0").as_ptr() };
 const URL: *const c_char =
     unsafe { CStr::from_bytes_with_nul_unchecked(b"https://octasine.com\0").as_ptr() };
@@ -1,30 +1,85 @@
+use std::sync::atomic::Ordering;
+
 use clap_sys::{
-    ext::audio_ports::{
-        clap_audio_port_info, clap_plugin_audio_ports, CLAP_AUDIO_PORT_IS_MAIN, CLAP_PORT_STEREO,
+    ext::{
+        audio_ports::{
+            clap_audio_port_info, clap_plugin_audio_ports, CLAP_AUDIO_PORT_IS_MAIN,
+            CLAP_AUDIO_PORT_PREFERS_64BITS, CLAP_AUDIO_PORT_SUPPORTS_64BITS, CLAP_PORT_STEREO,
+        },
+        audio_ports_config::{clap_audio_ports_config, clap_plugin_audio_ports_config},
     },
-    id::CLAP_INVALID_ID,
+    id::{clap_id, CLAP_INVALID_ID},
     plugin::clap_plugin,
 };
+use compact_str::format_compact;
 
-pub extern "C" fn count(_plugin: *const clap_plugin, is_input: bool) -> u32 {
-    if is_input {
-        0
+use crate::{common::NUM_OPERATORS, plugin::clap::plugin::OctaSine};
+
+use super::params::make_c_char_arr;
+
+/// Main stereo output only
+pub const AUDIO_PORTS_CONFIG_STEREO: clap_id = 0;
+/// Main stereo output followed by one stereo output per operator mix out
+pub const AUDIO_PORTS_CONFIG_OPERATOR_OUTPUTS: clap_id = 1;
+
+const AUDIO_PORTS_CONFIGS: [clap_id; 2] = [
+    AUDIO_PORTS_CONFIG_STEREO,
+    AUDIO_PORTS_CONFIG_OPERATOR_OUTPUTS,
+];
+
+fn num_output_ports(config_id: clap_id) -> u32 {
+    if config_id == AUDIO_PORTS_CONFIG_OPERATOR_OUTPUTS {
+        1 + NUM_OPERATORS as u32
     } else {
         1
     }
 }
+
+pub unsafe extern "C" fn count(plugin: *const clap_plugin, is_input: bool) -> u32 {
+    let plugin = &*((*plugin).plugin_data as *const OctaSine);
+
+    // One sidechain input in all configs
+    if is_input {
+        1
+    } else {
+        num_output_ports(plugin.audio_ports_config.load(Ordering::SeqCst))
+    }
+}
 pub unsafe extern "C" fn get(
-    _plugin: *const clap_plugin,
+    plugin: *const clap_plugin,
     index: u32,
     is_input: bool,
     info: *mut clap_audio_port_info,
 ) -> bool {
-    if index == 0 && !is_input {
+    let plugin = &*((*plugin).plugin_data as *const OctaSine);
+
+    let num_ports = if is_input {
+        1
+    } else {
+        num_output_ports(plugin.audio_ports_config.load(Ordering::SeqCst))
+    };
+
+    if index < num_ports {
         let info = &mut *info;
 
-        info.id = 0;
+        // Audio is generated in f64, so 64-bit buffers avoid conversion
+        let flags = CLAP_AUDIO_PORT_SUPPORTS_64BITS | CLAP_AUDIO_PORT_PREFERS_64BITS;
+
+        // Input is not main, since it is only used as modulation source.
+        // Output port 0 is the main mix, the rest are operator mix outs.
+        if is_input {
+            info.name = make_c_char_arr("Sidechain");
+            info.flags = flags;
+        } else if index == 0 {
+            info.name = make_c_char_arr("Main");
+            info.flags = CLAP_AUDIO_PORT_IS_MAIN | flags;
+        } else {
+            info.name = make_c_char_arr(&format_compact!("Operator {}", index));
+            info.flags = flags;
+        }
+
+        info.id = index;
         info.channel_count = 2;
-        info.flags = CLAP_AUDIO_PORT_IS_MAIN;
         info.port_type = CLAP_PORT_STEREO.as_ptr();
         info.in_place_pair = CLAP_INVALID_ID;
 
@@ -38,3 +93,57 @@ pub const CONFIG: clap_plugin_audio_ports = clap_plugin_audio_ports {
     count: Some(count),
     get: Some(get),
 };
+
+unsafe extern "C" fn config_count(_plugin: *const clap_plugin) -> u32 {
+    AUDIO_PORTS_CONFIGS.len() as u32
+}
+
+unsafe extern "C" fn config_get(
+    _plugin: *const clap_plugin,
+    index: u32,
+    config: *mut clap_audio_ports_config,
+) -> bool {
+    if let Some(config_id) = AUDIO_PORTS_CONFIGS.get(index as usize).copied() {
+        let name = if config_id == AUDIO_PORTS_CONFIG_OPERATOR_OUTPUTS {
+            "Stereo + operator outputs"
+        } else {
+            "Stereo"
+        };
+
+        *config = clap_audio_ports_config {
+            id: config_id,
+            name: make_c_char_arr(name),
+            input_port_count: 1,
+            output_port_count: num_output_ports(config_id),
+            has_main_input: false,
+            main_input_channel_count: 0,
+            main_input_port_type: CLAP_PORT_STEREO.as_ptr(),
+            has_main_output: true,
+            main_output_channel_count: 2,
+            main_output_port_type: CLAP_PORT_STEREO.as_ptr(),
+        };
+
+        true
+    } else {
+        false
+    }
+}
+
+/// Host only calls this when plugin is deactivated
+unsafe extern "C" fn config_select(plugin: *const clap_plugin, config_id: clap_id) -> bool {
+    let plugin = &*((*plugin).plugin_data as *const OctaSine);
+
+    if AUDIO_PORTS_CONFIGS.contains(&config_id) {
+        plugin.audio_ports_config.store(config_id, Ordering::SeqCst);
+
+        true
+    } else {
+        false
+    }
+}
+
+pub const PORTS_CONFIG: clap_plugin_audio_ports_config = clap_plugin_audio_ports_config {
+    count: Some(config_count),
+    get: Some(config_get),
+    select: Some(config_select),
+};
@@ -2,5 +2,7 @@ pub mod audio_ports;
 pub mod gui;
 pub mod note_ports;
 pub mod params;
+pub mod preset_load;
+pub mod remote_controls;
 pub mod state;
 pub mod voice_info;
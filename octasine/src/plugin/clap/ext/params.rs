@@ -2,13 +2,19 @@ use std::ffi::{c_char, c_void, CStr, CString};
 
 use clap_sys::{
     events::{clap_input_events, clap_output_events},
-    ext::params::{clap_param_info, clap_plugin_params, CLAP_PARAM_IS_AUTOMATABLE},
+    ext::params::{
+        clap_param_info, clap_plugin_params, CLAP_PARAM_IS_AUTOMATABLE, CLAP_PARAM_IS_MODULATABLE,
+        CLAP_PARAM_IS_MODULATABLE_PER_KEY, CLAP_PARAM_IS_MODULATABLE_PER_NOTE_ID,
+    },
     plugin::clap_plugin,
 };
 
-use crate::{parameters::ParameterKey, plugin::clap::plugin::OctaSine};
+use crate::{
+    parameters::{is_lfo_target, ParameterKey},
+    plugin::clap::plugin::OctaSine,
+};
 
-fn make_c_char_arr<const N: usize>(text: &str) -> [c_char; N] {
+pub fn make_c_char_arr<const N: usize>(text: &str) -> [c_char; N] {
     let text = CString::new(text).unwrap();
     let text: &[c_char] = bytemuck::cast_slice(text.as_bytes_with_nul());
 
@@ -39,11 +45,29 @@ pub unsafe extern "C" fn get_info(
         .patches
         .get_parameter_by_index(param_index as usize)
     {
+        // Macro names include their targets
+        let name = plugin
+            .sync
+            .patches
+            .get_parameter_name(param_index as usize)
+            .unwrap_or_else(|| parameter.name.clone());
+
+        // Parameters that can be modulated per voice support non-destructive
+        // modulation from host
+        let flags = if is_lfo_target(parameter.parameter.parameter()) {
+            CLAP_PARAM_IS_AUTOMATABLE
+                | CLAP_PARAM_IS_MODULATABLE
+                | CLAP_PARAM_IS_MODULATABLE_PER_NOTE_ID
+                | CLAP_PARAM_IS_MODULATABLE_PER_KEY
+        } else {
+            CLAP_PARAM_IS_AUTOMATABLE
+        };
+
         *param_info = clap_param_info {
             id: parameter.parameter.key().0,
-            flags: CLAP_PARAM_IS_AUTOMATABLE,
+            flags,
             cookie: param_index as usize as *mut c_void,
-            name: make_c_char_arr(&parameter.name),
+            name: make_c_char_arr(&name),
             module: make_c_char_arr(&parameter.clap_path),
             min_value: 0.0,
             max_value: 1.0,
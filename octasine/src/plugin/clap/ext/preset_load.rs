@@ -0,0 +1,150 @@
+use std::ffi::{c_char, CStr, CString};
+
+use clap_sys::{host::clap_host, plugin::clap_plugin};
+
+use crate::plugin::clap::{
+    plugin::OctaSine,
+    preset_discovery::{
+        CLAP_PRESET_DISCOVERY_LOCATION_FILE, CLAP_PRESET_DISCOVERY_LOCATION_PLUGIN,
+    },
+};
+
+pub use bindings::*;
+
+/// Bindings for preset load extension, which clap-sys doesn't include yet
+#[allow(non_camel_case_types)]
+mod bindings {
+    use std::ffi::{c_char, CStr};
+
+    use clap_sys::{host::clap_host, plugin::clap_plugin};
+
+    pub const CLAP_EXT_PRESET_LOAD: &CStr =
+        unsafe { CStr::from_bytes_with_nul_unchecked(b"clap.preset-load/2\0") };
+    pub const CLAP_EXT_PRESET_LOAD_COMPAT: &CStr =
+        unsafe { CStr::from_bytes_with_nul_unchecked(b"clap.preset-load.draft/2\0") };
+
+    #[repr(C)]
+    pub struct clap_plugin_preset_load {
+        pub from_location: Option<
+            unsafe extern "C" fn(
+                plugin: *const clap_plugin,
+                location_kind: u32,
+                location: *const c_char,
+                load_key: *const c_char,
+            ) -> bool,
+        >,
+    }
+
+    #[repr(C)]
+    pub struct clap_host_preset_load {
+        pub on_error: Option<
+            unsafe extern "C" fn(
+                host: *const clap_host,
+                location_kind: u32,
+                location: *const c_char,
+                load_key: *const c_char,
+                os_error: i32,
+                msg: *const c_char,
+            ),
+        >,
+        pub loaded: Option<
+            unsafe extern "C" fn(
+                host: *const clap_host,
+                location_kind: u32,
+                location: *const c_char,
+                load_key: *const c_char,
+            ),
+        >,
+    }
+}
+
+pub const CONFIG: clap_plugin_preset_load = clap_plugin_preset_load {
+    from_location: Some(from_location),
+};
+
+/// Load patch file, patch in bank file or built-in patch into current patch.
+/// Bank and built-in patches have their index as load key.
+unsafe extern "C" fn from_location(
+    plugin: *const clap_plugin,
+    location_kind: u32,
+    location: *const c_char,
+    load_key: *const c_char,
+) -> bool {
+    let plugin = &*((*plugin).plugin_data as *const OctaSine);
+
+    let parse_load_key = || -> anyhow::Result<Option<usize>> {
+        if load_key.is_null() {
+            Ok(None)
+        } else {
+            Ok(Some(CStr::from_ptr(load_key).to_str()?.parse::<usize>()?))
+        }
+    };
+
+    let result = match location_kind {
+        CLAP_PRESET_DISCOVERY_LOCATION_PLUGIN => parse_load_key().and_then(|opt_index| {
+            let index = opt_index.ok_or_else(|| anyhow::anyhow!("missing load key"))?;
+
+            plugin
+                .sync
+                .patches
+                .import_built_in_patch_into_current_patch(index)
+        }),
+        CLAP_PRESET_DISCOVERY_LOCATION_FILE if !location.is_null() => CStr::from_ptr(location)
+            .to_str()
+            .map_err(anyhow::Error::from)
+            .and_then(|path| Ok(::std::fs::read(path)?))
+            .and_then(|bytes| {
+                plugin
+                    .sync
+                    .patches
+                    .import_preset_into_current_patch(&bytes, parse_load_key()?)
+            }),
+        _ => return false,
+    };
+
+    let opt_host_ext = get_host_extension(plugin.host);
+
+    match result {
+        Ok(()) => {
+            plugin.tell_host_to_rescan_values();
+
+            if let Some(loaded) = opt_host_ext.and_then(|ext| ext.loaded) {
+                loaded(plugin.host, location_kind, location, load_key);
+            }
+
+            true
+        }
+        Err(err) => {
+            ::log::error!("preset_load from_location: {:#}", err);
+
+            if let Some(on_error) = opt_host_ext.and_then(|ext| ext.on_error) {
+                let os_error = err
+                    .downcast_ref::<::std::io::Error>()
+                    .and_then(|err| err.raw_os_error())
+                    .unwrap_or(0);
+                let message = CString::new(format!("{:#}", err)).unwrap_or_default();
+
+                on_error(
+                    plugin.host,
+                    location_kind,
+                    location,
+                    load_key,
+                    os_error,
+                    message.as_ptr(),
+                );
+            }
+
+            false
+        }
+    }
+}
+
+unsafe fn get_host_extension(host: *const clap_host) -> Option<&'static clap_host_preset_load> {
+    let get_extension = (*host).get_extension?;
+
+    [CLAP_EXT_PRESET_LOAD, CLAP_EXT_PRESET_LOAD_COMPAT]
+        .into_iter()
+        .map(|id| get_extension(host, id.as_ptr()) as *const clap_host_preset_load)
+        .find(|ext| !ext.is_null())
+        .map(|ext| &*ext)
+}
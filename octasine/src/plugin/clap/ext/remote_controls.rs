@@ -0,0 +1,166 @@
+use clap_sys::{id::CLAP_INVALID_ID, plugin::clap_plugin};
+
+use compact_str::{format_compact, CompactString};
+use once_cell::sync::Lazy;
+
+use crate::{
+    common::IndexMap,
+    parameters::{Parameter, PARAMETERS},
+};
+
+use super::params::make_c_char_arr;
+
+pub use bindings::*;
+
+/// Bindings for remote controls extension, which clap-sys doesn't include yet
+#[allow(non_camel_case_types)]
+mod bindings {
+    use std::ffi::{c_char, CStr};
+
+    use clap_sys::{id::clap_id, plugin::clap_plugin, string_sizes::CLAP_NAME_SIZE};
+
+    pub const CLAP_EXT_REMOTE_CONTROLS: &CStr =
+        unsafe { CStr::from_bytes_with_nul_unchecked(b"clap.remote-controls/2\0") };
+    pub const CLAP_EXT_REMOTE_CONTROLS_COMPAT: &CStr =
+        unsafe { CStr::from_bytes_with_nul_unchecked(b"clap.remote-controls.draft/2\0") };
+
+    pub const CLAP_REMOTE_CONTROLS_COUNT: usize = 8;
+
+    #[repr(C)]
+    pub struct clap_remote_controls_page {
+        pub section_name: [c_char; CLAP_NAME_SIZE],
+        pub page_id: clap_id,
+        pub page_name: [c_char; CLAP_NAME_SIZE],
+        pub param_ids: [clap_id; CLAP_REMOTE_CONTROLS_COUNT],
+        pub is_for_preset: bool,
+    }
+
+    #[repr(C)]
+    pub struct clap_plugin_remote_controls {
+        pub count: Option<unsafe extern "C" fn(plugin: *const clap_plugin) -> u32>,
+        pub get: Option<
+            unsafe extern "C" fn(
+                plugin: *const clap_plugin,
+                page_index: u32,
+                page: *mut clap_remote_controls_page,
+            ) -> bool,
+        >,
+    }
+}
+
+type PageParameters = [Option<Parameter>; CLAP_REMOTE_CONTROLS_COUNT];
+
+struct Page {
+    section_name: &'static str,
+    name: CompactString,
+    parameters: PageParameters,
+}
+
+/// Remote control pages, derived from parameter grouping so that all
+/// parameters are included. Each clap module path (e.g., "Master" or
+/// "Operator 0") gets as many pages of eight parameters as it needs.
+static PAGES: Lazy<Vec<Page>> = Lazy::new(|| {
+    let mut groups: IndexMap<CompactString, Vec<Parameter>> = Default::default();
+
+    for parameter in PARAMETERS.iter().copied() {
+        groups
+            .entry(parameter.clap_path())
+            .or_default()
+            .push(parameter);
+    }
+
+    let mut pages = Vec::new();
+
+    for (path, parameters) in groups {
+        let num_pages = parameters.len().div_ceil(CLAP_REMOTE_CONTROLS_COUNT);
+
+        for (page_index, chunk) in parameters.chunks(CLAP_REMOTE_CONTROLS_COUNT).enumerate() {
+            let name = if num_pages == 1 {
+                path.clone()
+            } else {
+                format_compact!("{} ({}/{})", path, page_index + 1, num_pages)
+            };
+
+            let mut page_parameters: PageParameters = Default::default();
+
+            for (slot, parameter) in page_parameters.iter_mut().zip(chunk) {
+                *slot = Some(*parameter);
+            }
+
+            pages.push(Page {
+                section_name: get_section_name(chunk[0]),
+                name,
+                parameters: page_parameters,
+            });
+        }
+    }
+
+    pages
+});
+
+fn get_section_name(parameter: Parameter) -> &'static str {
+    match parameter {
+        Parameter::None | Parameter::Master(_) => "Master",
+        Parameter::Operator(_, _) => "Operators",
+        Parameter::Lfo(_, _) => "LFOs",
+        Parameter::ModMatrix(_, _) => "Mod matrix",
+        Parameter::Macro(_) => "Macros",
+    }
+}
+
+unsafe extern "C" fn count(_plugin: *const clap_plugin) -> u32 {
+    PAGES.len() as u32
+}
+
+unsafe extern "C" fn get(
+    _plugin: *const clap_plugin,
+    page_index: u32,
+    page: *mut clap_remote_controls_page,
+) -> bool {
+    if let Some(Page {
+        section_name,
+        name,
+        parameters,
+    }) = PAGES.get(page_index as usize)
+    {
+        *page = clap_remote_controls_page {
+            section_name: make_c_char_arr(section_name),
+            page_id: page_index,
+            page_name: make_c_char_arr(name),
+            param_ids: parameters.map(|opt_parameter| {
+                opt_parameter
+                    .map(|parameter| parameter.key().0)
+                    .unwrap_or(CLAP_INVALID_ID)
+            }),
+            is_for_preset: false,
+        };
+
+        true
+    } else {
+        false
+    }
+}
+
+pub const CONFIG: clap_plugin_remote_controls = clap_plugin_remote_controls {
+    count: Some(count),
+    get: Some(get),
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_parameters_on_pages() {
+        let page_parameters = PAGES
+            .iter()
+            .flat_map(|page| page.parameters.iter().flatten().copied())
+            .collect::<Vec<_>>();
+
+        assert_eq!(page_parameters.len(), PARAMETERS.len());
+
+        for parameter in PARAMETERS {
+            assert!(page_parameters.contains(parameter), "{:?}", parameter);
+        }
+    }
+}
@@ -1,13 +1,86 @@
 use clap_sys::{
-    ext::draft::voice_info::{clap_plugin_voice_info, clap_voice_info},
+    ext::draft::voice_info::{
+        clap_host_voice_info, clap_plugin_voice_info, clap_voice_info, CLAP_EXT_VOICE_INFO,
+        CLAP_VOICE_INFO_SUPPORTS_OVERLAPPING_NOTES,
+    },
+    host::clap_host,
     plugin::clap_plugin,
 };
 
-unsafe extern "C" fn get(_plugin: *const clap_plugin, voice_info: *mut clap_voice_info) -> bool {
+use crate::{
+    parameters::{
+        max_polyphony::MaxPolyphonyValue,
+        overlapping_voices::OverlappingVoicesValue,
+        voice_mode::{VoiceMode, VoiceModeValue},
+        MasterParameter, Parameter, ParameterValue,
+    },
+    plugin::clap::plugin::OctaSine,
+    sync::SyncState,
+};
+
+const VOICE_CAPACITY: u32 = 128;
+
+/// Number of voices that current patch can use
+pub fn get_voice_count<H>(sync: &SyncState<H>) -> u32 {
+    let get_value = |parameter: Parameter| {
+        sync.patches
+            .get_parameter_value(parameter.to_index() as usize)
+            .unwrap_or_default()
+    };
+
+    let voice_mode =
+        VoiceModeValue::new_from_patch(get_value(Parameter::Master(MasterParameter::VoiceMode)));
+
+    match voice_mode.get() {
+        VoiceMode::Monophonic => 1,
+        VoiceMode::Polyphonic => {
+            let max_polyphony = MaxPolyphonyValue::new_from_patch(get_value(Parameter::Master(
+                MasterParameter::MaxPolyphony,
+            )));
+
+            max_polyphony.get() as u32
+        }
+    }
+}
+
+/// Whether patch allows several voices for the same key at once
+pub fn get_overlapping_voices<H>(sync: &SyncState<H>) -> bool {
+    let value = sync
+        .patches
+        .get_parameter_value(
+            Parameter::Master(MasterParameter::OverlappingVoices).to_index() as usize,
+        )
+        .unwrap_or_default();
+
+    OverlappingVoicesValue::new_from_patch(value).get()
+}
+
+/// Must be called from main thread
+pub unsafe fn tell_host_voice_info_changed(host: *const clap_host) {
+    let get_extension = (*host).get_extension.unwrap();
+
+    let ext = get_extension(host, CLAP_EXT_VOICE_INFO.as_ptr()) as *const clap_host_voice_info;
+
+    if !ext.is_null() {
+        if let Some(changed) = (*ext).changed {
+            changed(host);
+        }
+    }
+}
+
+unsafe extern "C" fn get(plugin: *const clap_plugin, voice_info: *mut clap_voice_info) -> bool {
+    let plugin = &*((*plugin).plugin_data as *const OctaSine);
+
+    let flags = if get_overlapping_voices(&plugin.sync) {
+        CLAP_VOICE_INFO_SUPPORTS_OVERLAPPING_NOTES
+    } else {
+        0
+    };
+
     *voice_info = clap_voice_info {
-        voice_count: 128,
-        voice_capacity: 128,
-        flags: 0,
+        voice_count: get_voice_count(&plugin.sync),
+        voice_capacity: VOICE_CAPACITY,
+        flags,
     };
 
     true
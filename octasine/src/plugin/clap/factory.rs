@@ -11,7 +11,15 @@ use clap_sys::{
 };
 use once_cell::sync::Lazy;
 
-use super::{descriptor::DESCRIPTOR, plugin::OctaSine};
+use super::{
+    descriptor::DESCRIPTOR,
+    plugin::OctaSine,
+    preset_discovery::{
+        clap_preset_discovery_factory, clap_preset_discovery_indexer,
+        clap_preset_discovery_provider, clap_preset_discovery_provider_descriptor,
+        PresetDiscoveryProvider, PROVIDER_DESCRIPTOR,
+    },
+};
 
 pub const FACTORY: clap_plugin_factory = clap_plugin_factory {
     get_plugin_count: Some(get_plugin_count),
@@ -45,3 +53,41 @@ pub unsafe extern "C" fn create_plugin(
         null()
     }
 }
+
+pub const PRESET_DISCOVERY_FACTORY: clap_preset_discovery_factory = clap_preset_discovery_factory {
+    count: Some(get_preset_discovery_provider_count),
+    get_descriptor: Some(get_preset_discovery_provider_descriptor),
+    create: Some(create_preset_discovery_provider),
+};
+
+pub unsafe extern "C" fn get_preset_discovery_provider_count(
+    _factory: *const clap_preset_discovery_factory,
+) -> u32 {
+    1
+}
+
+pub unsafe extern "C" fn get_preset_discovery_provider_descriptor(
+    _factory: *const clap_preset_discovery_factory,
+    index: u32,
+) -> *const clap_preset_discovery_provider_descriptor {
+    if index == 0 {
+        &PROVIDER_DESCRIPTOR as *const _
+    } else {
+        null()
+    }
+}
+
+pub unsafe extern "C" fn create_preset_discovery_provider(
+    _factory: *const clap_preset_discovery_factory,
+    indexer: *const clap_preset_discovery_indexer,
+    provider_id: *const c_char,
+) -> *const clap_preset_discovery_provider {
+    if !indexer.is_null()
+        && !provider_id.is_null()
+        && CStr::from_ptr(provider_id) == CStr::from_ptr(PROVIDER_DESCRIPTOR.id)
+    {
+        PresetDiscoveryProvider::create(indexer)
+    } else {
+        null()
+    }
+}
@@ -2,6 +2,7 @@ pub mod descriptor;
 pub mod ext;
 pub mod factory;
 pub mod plugin;
+pub mod preset_discovery;
 pub mod sync;
 
 use std::{
@@ -31,6 +32,10 @@ pub unsafe extern "C" fn entry_get_factory(factory_id: *const c_char) -> *const
 
     if factory_id == CLAP_PLUGIN_FACTORY_ID {
         &factory::FACTORY as *const _ as *const c_void
+    } else if (factory_id == preset_discovery::CLAP_PRESET_DISCOVERY_FACTORY_ID)
+        | (factory_id == preset_discovery::CLAP_PRESET_DISCOVERY_FACTORY_ID_COMPAT)
+    {
+        &factory::PRESET_DISCOVERY_FACTORY as *const _ as *const c_void
     } else {
         null()
     }
@@ -2,25 +2,34 @@ use std::{
     ffi::{c_char, c_void, CStr},
     mem::{size_of, MaybeUninit},
     ptr::{null, null_mut},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
 };
 
 use atomic_refcell::AtomicRefCell;
 use clap_sys::{
+    audio_buffer::clap_audio_buffer,
     events::{
         clap_event_header, clap_event_midi, clap_event_note, clap_event_note_expression,
-        clap_event_param_gesture, clap_event_param_value, clap_event_transport, clap_output_events,
-        CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_IS_LIVE, CLAP_EVENT_MIDI, CLAP_EVENT_NOTE_END,
-        CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON,
-        CLAP_EVENT_PARAM_GESTURE_BEGIN, CLAP_EVENT_PARAM_GESTURE_END, CLAP_EVENT_PARAM_VALUE,
-        CLAP_EVENT_TRANSPORT, CLAP_NOTE_EXPRESSION_PRESSURE, CLAP_TRANSPORT_HAS_TEMPO,
+        clap_event_param_gesture, clap_event_param_mod, clap_event_param_value,
+        clap_event_transport, clap_output_events, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_IS_LIVE,
+        CLAP_EVENT_MIDI, CLAP_EVENT_NOTE_END, CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF,
+        CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_GESTURE_BEGIN, CLAP_EVENT_PARAM_GESTURE_END,
+        CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE, CLAP_EVENT_TRANSPORT,
+        CLAP_NOTE_EXPRESSION_PRESSURE, CLAP_TRANSPORT_HAS_TEMPO,
     },
     ext::{
         audio_ports::CLAP_EXT_AUDIO_PORTS,
+        audio_ports_config::CLAP_EXT_AUDIO_PORTS_CONFIG,
         draft::voice_info::CLAP_EXT_VOICE_INFO,
         gui::CLAP_EXT_GUI,
         note_ports::CLAP_EXT_NOTE_PORTS,
-        params::{clap_host_params, CLAP_EXT_PARAMS, CLAP_PARAM_RESCAN_VALUES},
+        params::{
+            clap_host_params, CLAP_EXT_PARAMS, CLAP_PARAM_RESCAN_INFO, CLAP_PARAM_RESCAN_TEXT,
+            CLAP_PARAM_RESCAN_VALUES,
+        },
         state::{clap_host_state, CLAP_EXT_STATE},
     },
     host::clap_host,
@@ -33,14 +42,106 @@ use parking_lot::Mutex;
 use ringbuf::{Consumer, Producer, Rb, SharedRb};
 
 use crate::{
-    audio::{gen::process_f32_runtime_select, AudioState},
-    common::{BeatsPerMinute, EventToHost, NoteEvent, NoteEventInner, SampleRate},
-    parameters::ParameterKey,
-    sync::SyncState,
-    utils::{init_logging, update_audio_parameters},
+    audio::{
+        gen::{process_f32_runtime_select, process_f64_runtime_select, OperatorOutputs},
+        AudioState,
+    },
+    common::{BeatsPerMinute, EventToHost, NoteEvent, NoteEventInner, SampleRate, NUM_OPERATORS},
+    osc::OscServer,
+    parameters::{Parameter, ParameterKey},
+    sync::{PatchParameter, SyncState},
+    utils::{init_logging, update_audio_parameters, update_parameters_from_midi_controllers},
+};
+
+use super::{
+    descriptor::DESCRIPTOR,
+    ext::{
+        audio_ports::AUDIO_PORTS_CONFIG_STEREO,
+        gui::ParentWindow,
+        preset_load::{CLAP_EXT_PRESET_LOAD, CLAP_EXT_PRESET_LOAD_COMPAT},
+        remote_controls::{CLAP_EXT_REMOTE_CONTROLS, CLAP_EXT_REMOTE_CONTROLS_COMPAT},
+        voice_info::{get_overlapping_voices, get_voice_count, tell_host_voice_info_changed},
+    },
+    sync::ClapGuiSyncHandle,
 };
 
-use super::{descriptor::DESCRIPTOR, ext::gui::ParentWindow, sync::ClapGuiSyncHandle};
+/// Stereo input buffers used as sidechain
+type StereoInputs<'a, T> = Option<(&'a [T], &'a [T])>;
+
+/// Main stereo output buffers, and optionally operator output buffers
+struct StereoOutputs<'a, T> {
+    lefts: &'a mut [T],
+    rights: &'a mut [T],
+    opt_operators: Option<OperatorOutputs<'a, T>>,
+}
+
+/// Input and output buffers of the sample type chosen by the host
+enum AudioOutputs<'a> {
+    F32(StereoInputs<'a, f32>, StereoOutputs<'a, f32>),
+    F64(StereoInputs<'a, f64>, StereoOutputs<'a, f64>),
+}
+
+impl<'a> AudioOutputs<'a> {
+    /// Returns None if any channel pointer is null
+    unsafe fn from_channels<T>(
+        channels: *const *mut T,
+        frames_count: u32,
+    ) -> Option<(&'a mut [T], &'a mut [T])> {
+        if channels.is_null() {
+            return None;
+        }
+
+        let channels = ::std::slice::from_raw_parts(channels, 2);
+
+        if channels[0].is_null() | channels[1].is_null() {
+            return None;
+        }
+
+        Some((
+            ::std::slice::from_raw_parts_mut(channels[0], frames_count as usize),
+            ::std::slice::from_raw_parts_mut(channels[1], frames_count as usize),
+        ))
+    }
+
+    /// Main output is the first buffer. If there are more, they are operator
+    /// outputs. Returns None if buffers are invalid.
+    unsafe fn from_buffers<T>(
+        buffers: &[clap_audio_buffer],
+        frames_count: u32,
+        get_channels: fn(&clap_audio_buffer) -> *const *mut T,
+    ) -> Option<StereoOutputs<'a, T>> {
+        let (lefts, rights) = Self::from_channels(get_channels(&buffers[0]), frames_count)?;
+
+        let opt_operators = if buffers.len() == 1 + NUM_OPERATORS {
+            let operators = array_init::try_array_init(|i| {
+                Self::from_channels(get_channels(&buffers[1 + i]), frames_count).ok_or(())
+            });
+
+            Some(operators.ok()?)
+        } else {
+            None
+        };
+
+        Some(StereoOutputs {
+            lefts,
+            rights,
+            opt_operators,
+        })
+    }
+
+    /// Returns None if there is no valid stereo input of the same sample
+    /// type as the outputs
+    unsafe fn inputs_from_buffers<T>(
+        buffers: &[clap_audio_buffer],
+        frames_count: u32,
+        get_channels: fn(&clap_audio_buffer) -> *const *mut T,
+    ) -> StereoInputs<'a, T> {
+        let buffer = buffers.first().filter(|buffer| buffer.channel_count == 2)?;
+        let (lefts, rights) = Self::from_channels(get_channels(buffer), frames_count)?;
+
+        Some((lefts, rights))
+    }
+}
 
 pub type EventToHostConsumer =
     Consumer<EventToHost, Arc<SharedRb<EventToHost, Vec<MaybeUninit<EventToHost>>>>>;
@@ -55,6 +156,17 @@ pub struct OctaSine {
     pub gui_parent: Mutex<Option<ParentWindow>>,
     pub gui_window_handle: Mutex<Option<WindowHandle<crate::gui::Message>>>,
     pub clap_plugin: AtomicRefCell<clap_plugin>,
+    /// Last voice count and overlapping voices setting, used to tell host
+    /// when voice info changes
+    voice_count: AtomicU32,
+    overlapping_voices: AtomicBool,
+    voice_info_changed: AtomicBool,
+    /// Patch was changed by MIDI program change, so host should be told to
+    /// rescan parameter values from main thread
+    rescan_values_requested: AtomicBool,
+    /// Selected audio ports config id
+    pub audio_ports_config: AtomicU32,
+    _opt_osc_server: Option<OscServer>,
 }
 
 impl OctaSine {
@@ -68,10 +180,18 @@ impl OctaSine {
             host,
         };
 
+        let sync = Arc::new(SyncState::new(Some(gui_sync_handle)));
+
         let plugin = Self {
             host,
             audio: Default::default(),
-            sync: Arc::new(SyncState::new(Some(gui_sync_handle))),
+            voice_count: AtomicU32::new(get_voice_count(&sync)),
+            overlapping_voices: AtomicBool::new(get_overlapping_voices(&sync)),
+            voice_info_changed: AtomicBool::new(false),
+            rescan_values_requested: AtomicBool::new(false),
+            audio_ports_config: AtomicU32::new(AUDIO_PORTS_CONFIG_STEREO),
+            _opt_osc_server: OscServer::start_if_enabled(sync.clone()),
+            sync,
             gui_event_consumer: Mutex::new(gui_event_consumer),
             gui_parent: Default::default(),
             gui_window_handle: Default::default(),
@@ -142,27 +262,65 @@ impl OctaSine {
         let plugin = &*((*plugin).plugin_data as *const Self);
         let process = &*process;
 
-        if process.audio_outputs_count != 1 || process.audio_outputs.is_null() {
+        let num_audio_outputs = process.audio_outputs_count as usize;
+
+        // Either main output only or main output and operator outputs
+        if !((num_audio_outputs == 1) | (num_audio_outputs == 1 + NUM_OPERATORS))
+            || process.audio_outputs.is_null()
+        {
             return CLAP_PROCESS_ERROR;
         }
 
-        let audio_outputs = &*process.audio_outputs;
+        let audio_outputs = ::std::slice::from_raw_parts(process.audio_outputs, num_audio_outputs);
 
-        if (audio_outputs.channel_count != 2) | audio_outputs.data32.is_null() {
+        if audio_outputs.iter().any(|buffer| buffer.channel_count != 2) {
             return CLAP_PROCESS_ERROR;
         }
 
-        let audio_outputs =
-            ::std::slice::from_raw_parts_mut(audio_outputs.data32 as *mut *mut f32, 2);
+        // Sidechain input is optional
+        let audio_inputs = if (process.audio_inputs_count == 1) & !process.audio_inputs.is_null() {
+            ::std::slice::from_raw_parts(process.audio_inputs, 1)
+        } else {
+            &[]
+        };
 
-        if audio_outputs[0].is_null() | audio_outputs[1].is_null() {
-            return CLAP_PROCESS_ERROR;
-        }
+        // Host sets data64 instead of data32 when it has chosen 64-bit processing
+        let opt_audio_outputs = if !audio_outputs[0].data64.is_null() {
+            let get_channels: fn(&clap_audio_buffer) -> *const *mut f64 =
+                |buffer| buffer.data64 as *const _;
 
-        let lefts =
-            ::std::slice::from_raw_parts_mut(audio_outputs[0], process.frames_count as usize);
-        let rights =
-            ::std::slice::from_raw_parts_mut(audio_outputs[1], process.frames_count as usize);
+            AudioOutputs::from_buffers(audio_outputs, process.frames_count, get_channels).map(
+                |outputs| {
+                    let inputs = AudioOutputs::inputs_from_buffers(
+                        audio_inputs,
+                        process.frames_count,
+                        get_channels,
+                    );
+
+                    AudioOutputs::F64(inputs, outputs)
+                },
+            )
+        } else {
+            let get_channels: fn(&clap_audio_buffer) -> *const *mut f32 =
+                |buffer| buffer.data32 as *const _;
+
+            AudioOutputs::from_buffers(audio_outputs, process.frames_count, get_channels).map(
+                |outputs| {
+                    let inputs = AudioOutputs::inputs_from_buffers(
+                        audio_inputs,
+                        process.frames_count,
+                        get_channels,
+                    );
+
+                    AudioOutputs::F32(inputs, outputs)
+                },
+            )
+        };
+
+        let mut audio_outputs = match opt_audio_outputs {
+            Some(audio_outputs) => audio_outputs,
+            None => return CLAP_PROCESS_ERROR,
+        };
 
         let opt_in_event_data = if !process.in_events.is_null() {
             match ((*(process.in_events)).size, (*(process.in_events)).get) {
@@ -218,22 +376,58 @@ impl OctaSine {
             {
                 let mut audio = plugin.audio.lock();
 
-                let lefts = &mut lefts[process_start_index as usize..process_end_index as usize];
-                let rights = &mut rights[process_start_index as usize..process_end_index as usize];
+                let range = process_start_index as usize..process_end_index as usize;
+
+                let updater = |audio: &mut AudioState| {
+                    if let Some(process_out_events) = opt_process_out_events {
+                        plugin.send_gui_events_to_host(process_out_events, process_start_index);
+                    }
 
-                process_f32_runtime_select(
-                    &mut audio,
-                    lefts,
-                    rights,
-                    process_start_index as usize,
-                    |audio| {
-                        if let Some(process_out_events) = opt_process_out_events {
-                            plugin.send_gui_events_to_host(process_out_events, process_start_index);
-                        }
+                    update_parameters_from_midi_controllers(audio, &plugin.sync);
+                    update_audio_parameters(audio, &plugin.sync);
 
-                        update_audio_parameters(audio, &plugin.sync);
-                    },
-                );
+                    if let Some(process_out_events) = opt_process_out_events {
+                        plugin.send_midi_and_osc_changes_to_host(
+                            process_out_events,
+                            process_start_index,
+                        );
+                    }
+
+                    if plugin.sync.patches.has_patch_index_changed_from_audio() {
+                        plugin.request_rescan_values();
+                    }
+                };
+
+                match &mut audio_outputs {
+                    AudioOutputs::F32(opt_inputs, outputs) => process_f32_runtime_select(
+                        &mut audio,
+                        &mut outputs.lefts[range.clone()],
+                        &mut outputs.rights[range.clone()],
+                        opt_inputs
+                            .map(|(lefts, rights)| (&lefts[range.clone()], &rights[range.clone()])),
+                        outputs.opt_operators.as_mut().map(|operators| {
+                            operators.each_mut().map(|(lefts, rights)| {
+                                (&mut lefts[range.clone()], &mut rights[range.clone()])
+                            })
+                        }),
+                        process_start_index as usize,
+                        updater,
+                    ),
+                    AudioOutputs::F64(opt_inputs, outputs) => process_f64_runtime_select(
+                        &mut audio,
+                        &mut outputs.lefts[range.clone()],
+                        &mut outputs.rights[range.clone()],
+                        opt_inputs
+                            .map(|(lefts, rights)| (&lefts[range.clone()], &rights[range.clone()])),
+                        outputs.opt_operators.as_mut().map(|operators| {
+                            operators.each_mut().map(|(lefts, rights)| {
+                                (&mut lefts[range.clone()], &mut rights[range.clone()])
+                            })
+                        }),
+                        process_start_index as usize,
+                        updater,
+                    ),
+                }
             }
 
             if let Some(process_out_events) = opt_process_out_events {
@@ -248,6 +442,8 @@ impl OctaSine {
             process_end_index = process.frames_count;
         }
 
+        plugin.check_voice_info();
+
         // Log any unhandled events. Should never happen.
         if let Some((num_events, get_fn)) = opt_in_event_data {
             while event_index < num_events {
@@ -272,6 +468,8 @@ impl OctaSine {
 
         if id == CLAP_EXT_AUDIO_PORTS {
             &super::ext::audio_ports::CONFIG as *const _ as *const c_void
+        } else if id == CLAP_EXT_AUDIO_PORTS_CONFIG {
+            &super::ext::audio_ports::PORTS_CONFIG as *const _ as *const c_void
         } else if id == CLAP_EXT_NOTE_PORTS {
             &super::ext::note_ports::CONFIG as *const _ as *const c_void
         } else if id == CLAP_EXT_PARAMS {
@@ -282,12 +480,58 @@ impl OctaSine {
             &super::ext::voice_info::CONFIG as *const _ as *const c_void
         } else if id == CLAP_EXT_STATE {
             &super::ext::state::CONFIG as *const _ as *const c_void
+        } else if (id == CLAP_EXT_PRESET_LOAD) | (id == CLAP_EXT_PRESET_LOAD_COMPAT) {
+            &super::ext::preset_load::CONFIG as *const _ as *const c_void
+        } else if (id == CLAP_EXT_REMOTE_CONTROLS) | (id == CLAP_EXT_REMOTE_CONTROLS_COMPAT) {
+            &super::ext::remote_controls::CONFIG as *const _ as *const c_void
         } else {
             null()
         }
     }
 
-    unsafe extern "C" fn on_main_thread(_plugin: *const clap_plugin) {}
+    unsafe extern "C" fn on_main_thread(plugin: *const clap_plugin) {
+        let plugin = &*((*plugin).plugin_data as *const Self);
+
+        if plugin.voice_info_changed.swap(false, Ordering::SeqCst) {
+            tell_host_voice_info_changed(plugin.host);
+        }
+
+        if plugin.rescan_values_requested.swap(false, Ordering::SeqCst) {
+            plugin.tell_host_to_rescan_values();
+        }
+    }
+
+    /// Ask host for main thread callback, where it is told to rescan
+    /// parameter values
+    unsafe fn request_rescan_values(&self) {
+        self.rescan_values_requested.store(true, Ordering::SeqCst);
+
+        if let Some(request_callback) = (*self.host).request_callback {
+            request_callback(self.host);
+        }
+    }
+
+    /// Ask host for main thread callback if voice info changed due to
+    /// parameter changes
+    unsafe fn check_voice_info(&self) {
+        let voice_count = get_voice_count(&self.sync);
+        let overlapping_voices = get_overlapping_voices(&self.sync);
+
+        let voice_count_changed =
+            self.voice_count.swap(voice_count, Ordering::SeqCst) != voice_count;
+        let overlapping_voices_changed = self
+            .overlapping_voices
+            .swap(overlapping_voices, Ordering::SeqCst)
+            != overlapping_voices;
+
+        if voice_count_changed || overlapping_voices_changed {
+            self.voice_info_changed.store(true, Ordering::SeqCst);
+
+            if let Some(request_callback) = (*self.host).request_callback {
+                request_callback(self.host);
+            }
+        }
+    }
 
     pub unsafe fn handle_event_from_host(&self, event_header: *const clap_event_header) {
         if (*event_header).space_id != CLAP_CORE_EVENT_SPACE_ID {
@@ -316,6 +560,7 @@ impl OctaSine {
                     delta_frames: event.header.time,
                     event: NoteEventInner::ClapNoteOff {
                         key: event.key as u8,
+                        clap_note_id: event.note_id,
                     },
                 };
 
@@ -352,32 +597,54 @@ impl OctaSine {
             CLAP_EVENT_PARAM_VALUE => {
                 let event = &*(event_header as *const clap_event_param_value);
 
-                let opt_index_and_parameter = if event.cookie.is_null() {
-                    let key = ParameterKey(event.param_id);
+                if let Some((index, p)) =
+                    self.get_index_and_parameter_from_event(event.param_id, event.cookie)
+                {
+                    let value = event.value as f32;
 
-                    self.sync.patches.get_index_and_parameter_by_key(&key)
-                } else {
-                    let index = event.cookie as u64 as usize;
+                    p.set_value(value);
 
                     self.sync
                         .patches
-                        .get_parameter_by_index(index)
-                        .map(|p| (index, p))
-                };
+                        .parameter_change_info_gui
+                        .mark_as_changed(index);
 
-                if let Some((index, p)) = opt_index_and_parameter {
-                    let value = event.value as f32;
+                    let mut audio = self.audio.lock();
 
-                    p.set_value(value);
+                    // Buffer is split at event time, so this is applied at
+                    // the sample offset of the event
+                    audio.set_parameter_from_patch(p.parameter.parameter(), value);
 
+                    // Macros don't affect audio by themselves, so apply
+                    // their target values right away too
                     self.sync
                         .patches
-                        .parameter_change_info_gui
-                        .mark_as_changed(index);
+                        .set_macro_target_values(p, |_, target, target_value| {
+                            audio.set_parameter_from_patch(
+                                target.parameter.parameter(),
+                                target_value,
+                            );
+                        });
+                }
+            }
+            CLAP_EVENT_PARAM_MOD => {
+                let event = &*(event_header as *const clap_event_param_mod);
+
+                if let Some((_, p)) =
+                    self.get_index_and_parameter_from_event(event.param_id, event.cookie)
+                {
+                    // Channels are not distinguished, as for notes
+                    let event = NoteEvent {
+                        delta_frames: event.header.time,
+                        event: NoteEventInner::ClapParameterModulation {
+                            parameter: p.parameter.parameter(),
+                            clap_note_id: event.note_id,
+                            key: event.key,
+                            amount: event.amount,
+                        },
+                    };
 
-                    self.audio
-                        .lock()
-                        .set_parameter_from_patch(p.parameter.parameter(), value)
+                    self.audio.lock().enqueue_note_event(event);
                 }
             }
             CLAP_EVENT_TRANSPORT => {
@@ -389,6 +656,26 @@ impl OctaSine {
         }
     }
 
+    /// Look up parameter by cookie if set, otherwise by id
+    fn get_index_and_parameter_from_event(
+        &self,
+        param_id: u32,
+        cookie: *mut c_void,
+    ) -> Option<(usize, &PatchParameter)> {
+        if cookie.is_null() {
+            self.sync
+                .patches
+                .get_index_and_parameter_by_key(&ParameterKey(param_id))
+        } else {
+            let index = cookie as u64 as usize;
+
+            self.sync
+                .patches
+                .get_parameter_by_index(index)
+                .map(|p| (index, p))
+        }
+    }
+
     pub fn handle_transport_event_from_host(&self, event: &clap_event_transport) {
         if event.header.space_id != CLAP_CORE_EVENT_SPACE_ID {
             return;
@@ -463,6 +750,9 @@ impl OctaSine {
                     EventToHost::RescanValues => {
                         self.tell_host_to_rescan_values();
                     }
+                    EventToHost::RescanInfo => {
+                        self.tell_host_to_rescan_info();
+                    }
                     EventToHost::StateChanged => {
                         self.tell_host_state_is_dirty();
                     }
@@ -471,6 +761,42 @@ impl OctaSine {
         }
     }
 
+    /// Report parameter changes caused by mapped MIDI controllers and OSC
+    /// messages, so that they can be recorded as automation
+    pub unsafe fn send_midi_and_osc_changes_to_host(
+        &self,
+        out_events: &clap_output_events,
+        time: u32,
+    ) {
+        if let (Some(try_push_fn), Some(changes)) = (
+            out_events.try_push,
+            self.sync.patches.get_changed_parameters_from_midi_and_osc(),
+        ) {
+            for (index, opt_value) in changes.into_iter().enumerate() {
+                if let (Some(value), Some(parameter)) = (opt_value, Parameter::from_index(index)) {
+                    let event = clap_event_param_value {
+                        header: clap_event_header {
+                            size: size_of::<clap_event_param_value>() as u32,
+                            time,
+                            space_id: CLAP_CORE_EVENT_SPACE_ID,
+                            type_: CLAP_EVENT_PARAM_VALUE,
+                            flags: CLAP_EVENT_IS_LIVE,
+                        },
+                        param_id: parameter.key().0,
+                        cookie: null_mut(),
+                        note_id: -1,
+                        port_index: 0,
+                        channel: -1,
+                        key: -1,
+                        value: value as f64,
+                    };
+
+                    try_push_fn(out_events, &event as *const _ as *const _);
+                }
+            }
+        }
+    }
+
     pub fn send_note_end_events_to_host(&self, out_events: &clap_output_events) {
         if let Some(try_push_fn) = out_events.try_push {
             for note_ended in self.audio.lock().clap_ended_notes.pop_iter() {
@@ -496,7 +822,8 @@ impl OctaSine {
         }
     }
 
-    unsafe fn tell_host_to_rescan_values(&self) {
+    /// Must be called from main thread
+    pub unsafe fn tell_host_to_rescan_values(&self) {
         let host = &*(self.host);
 
         let get_extension = host.get_extension.unwrap();
@@ -511,6 +838,20 @@ impl OctaSine {
         }
     }
 
+    unsafe fn tell_host_to_rescan_info(&self) {
+        let host = &*(self.host);
+
+        let get_extension = host.get_extension.unwrap();
+
+        let ext = get_extension(self.host, CLAP_EXT_PARAMS.as_ptr()) as *const clap_host_params;
+
+        if ext.is_null() {
+            ::log::error!("host doesn't implement params extension");
+        } else {
+            (&*(ext)).rescan.unwrap()(self.host, CLAP_PARAM_RESCAN_INFO | CLAP_PARAM_RESCAN_TEXT);
+        }
+    }
+
     unsafe fn tell_host_state_is_dirty(&self) {
         let host = &*(self.host);
 
@@ -525,3 +866,56 @@ impl OctaSine {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::parameters::{macros::MacroTarget, MasterParameter};
+
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_macro_param_value_event() {
+        let plugin = OctaSine::new(null());
+
+        let target_parameter = Parameter::Master(MasterParameter::Volume);
+        let target_index = target_parameter.to_index() as usize;
+        let target = MacroTarget {
+            min: 0.25,
+            max: 0.75,
+            ..MacroTarget::new(target_parameter)
+        };
+
+        plugin.sync.patches.set_macro_targets(0, vec![target]);
+
+        let event = clap_event_param_value {
+            header: clap_event_header {
+                size: size_of::<clap_event_param_value>() as u32,
+                time: 16,
+                space_id: CLAP_CORE_EVENT_SPACE_ID,
+                type_: CLAP_EVENT_PARAM_VALUE,
+                flags: 0,
+            },
+            param_id: Parameter::Macro(0).key().0,
+            cookie: null_mut(),
+            note_id: -1,
+            port_index: -1,
+            channel: -1,
+            key: -1,
+            value: 1.0,
+        };
+
+        unsafe {
+            plugin.handle_event_from_host(&event.header);
+        }
+
+        assert_eq!(
+            plugin.sync.patches.get_parameter_value(target_index),
+            Some(0.75)
+        );
+
+        let mut audio = plugin.audio.lock();
+
+        assert!(audio.compare_parameter_patch_value(target_parameter, 0.75));
+    }
+}
@@ -0,0 +1,457 @@
+//! Preset discovery, letting hosts index and browse built-in patches as well
+//! as patch and bank files
+
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    path::{Path, PathBuf},
+    ptr::null,
+};
+
+use clap_sys::version::CLAP_VERSION;
+use compact_str::CompactString;
+
+use crate::{
+    sync::{get_built_in_patch_names, get_patch_name_from_bytes, get_patch_names_from_bank_bytes},
+    utils::get_file_storage_dir,
+};
+
+use super::descriptor::{ID as PLUGIN_ID, VENDOR};
+
+pub use bindings::*;
+
+/// Bindings for preset discovery, which clap-sys doesn't include yet
+#[allow(non_camel_case_types)]
+mod bindings {
+    use std::ffi::{c_char, c_void, CStr};
+
+    use clap_sys::version::clap_version;
+
+    pub const CLAP_PRESET_DISCOVERY_FACTORY_ID: &CStr =
+        unsafe { CStr::from_bytes_with_nul_unchecked(b"clap.preset-discovery-factory/2\0") };
+    pub const CLAP_PRESET_DISCOVERY_FACTORY_ID_COMPAT: &CStr =
+        unsafe { CStr::from_bytes_with_nul_unchecked(b"clap.preset-discovery-factory/draft-2\0") };
+
+    pub const CLAP_PRESET_DISCOVERY_LOCATION_FILE: u32 = 0;
+    pub const CLAP_PRESET_DISCOVERY_LOCATION_PLUGIN: u32 = 1;
+
+    pub const CLAP_PRESET_DISCOVERY_IS_FACTORY_CONTENT: u32 = 1 << 0;
+    pub const CLAP_PRESET_DISCOVERY_IS_USER_CONTENT: u32 = 1 << 1;
+
+    #[repr(C)]
+    pub struct clap_universal_plugin_id {
+        pub abi: *const c_char,
+        pub id: *const c_char,
+    }
+
+    #[repr(C)]
+    pub struct clap_preset_discovery_metadata_receiver {
+        pub receiver_data: *mut c_void,
+        pub on_error: Option<
+            unsafe extern "C" fn(
+                receiver: *const clap_preset_discovery_metadata_receiver,
+                os_error: i32,
+                error_message: *const c_char,
+            ),
+        >,
+        pub begin_preset: Option<
+            unsafe extern "C" fn(
+                receiver: *const clap_preset_discovery_metadata_receiver,
+                name: *const c_char,
+                load_key: *const c_char,
+            ) -> bool,
+        >,
+        pub add_plugin_id: Option<
+            unsafe extern "C" fn(
+                receiver: *const clap_preset_discovery_metadata_receiver,
+                plugin_id: *const clap_universal_plugin_id,
+            ),
+        >,
+        pub set_soundpack_id: Option<
+            unsafe extern "C" fn(
+                receiver: *const clap_preset_discovery_metadata_receiver,
+                soundpack_id: *const c_char,
+            ),
+        >,
+        pub set_flags: Option<
+            unsafe extern "C" fn(
+                receiver: *const clap_preset_discovery_metadata_receiver,
+                flags: u32,
+            ),
+        >,
+        pub add_creator: Option<
+            unsafe extern "C" fn(
+                receiver: *const clap_preset_discovery_metadata_receiver,
+                creator: *const c_char,
+            ),
+        >,
+        pub set_description: Option<
+            unsafe extern "C" fn(
+                receiver: *const clap_preset_discovery_metadata_receiver,
+                description: *const c_char,
+            ),
+        >,
+        pub set_timestamps: Option<
+            unsafe extern "C" fn(
+                receiver: *const clap_preset_discovery_metadata_receiver,
+                creation_time: u64,
+                modification_time: u64,
+            ),
+        >,
+        pub add_feature: Option<
+            unsafe extern "C" fn(
+                receiver: *const clap_preset_discovery_metadata_receiver,
+                feature: *const c_char,
+            ),
+        >,
+        pub add_extra_info: Option<
+            unsafe extern "C" fn(
+                receiver: *const clap_preset_discovery_metadata_receiver,
+                key: *const c_char,
+                value: *const c_char,
+            ),
+        >,
+    }
+
+    #[repr(C)]
+    pub struct clap_preset_discovery_filetype {
+        pub name: *const c_char,
+        pub description: *const c_char,
+        pub file_extension: *const c_char,
+    }
+
+    #[repr(C)]
+    pub struct clap_preset_discovery_location {
+        pub flags: u32,
+        pub name: *const c_char,
+        pub kind: u32,
+        pub location: *const c_char,
+    }
+
+    #[repr(C)]
+    pub struct clap_preset_discovery_provider_descriptor {
+        pub clap_version: clap_version,
+        pub id: *const c_char,
+        pub name: *const c_char,
+        pub vendor: *const c_char,
+    }
+
+    unsafe impl Sync for clap_preset_discovery_provider_descriptor {}
+
+    #[repr(C)]
+    pub struct clap_preset_discovery_provider {
+        pub desc: *const clap_preset_discovery_provider_descriptor,
+        pub provider_data: *mut c_void,
+        pub init:
+            Option<unsafe extern "C" fn(provider: *const clap_preset_discovery_provider) -> bool>,
+        pub destroy: Option<unsafe extern "C" fn(provider: *const clap_preset_discovery_provider)>,
+        pub get_metadata: Option<
+            unsafe extern "C" fn(
+                provider: *const clap_preset_discovery_provider,
+                location_kind: u32,
+                location: *const c_char,
+                metadata_receiver: *const clap_preset_discovery_metadata_receiver,
+            ) -> bool,
+        >,
+        pub get_extension: Option<
+            unsafe extern "C" fn(
+                provider: *const clap_preset_discovery_provider,
+                extension_id: *const c_char,
+            ) -> *const c_void,
+        >,
+    }
+
+    #[repr(C)]
+    pub struct clap_preset_discovery_indexer {
+        pub clap_version: clap_version,
+        pub name: *const c_char,
+        pub vendor: *const c_char,
+        pub url: *const c_char,
+        pub version: *const c_char,
+        pub indexer_data: *mut c_void,
+        pub declare_filetype: Option<
+            unsafe extern "C" fn(
+                indexer: *const clap_preset_discovery_indexer,
+                filetype: *const clap_preset_discovery_filetype,
+            ) -> bool,
+        >,
+        pub declare_location: Option<
+            unsafe extern "C" fn(
+                indexer: *const clap_preset_discovery_indexer,
+                location: *const clap_preset_discovery_location,
+            ) -> bool,
+        >,
+        pub declare_soundpack: Option<
+            unsafe extern "C" fn(
+                indexer: *const clap_preset_discovery_indexer,
+                soundpack: *const c_void,
+            ) -> bool,
+        >,
+        pub get_extension: Option<
+            unsafe extern "C" fn(
+                indexer: *const clap_preset_discovery_indexer,
+                extension_id: *const c_char,
+            ) -> *const c_void,
+        >,
+    }
+
+    #[repr(C)]
+    pub struct clap_preset_discovery_factory {
+        pub count:
+            Option<unsafe extern "C" fn(factory: *const clap_preset_discovery_factory) -> u32>,
+        pub get_descriptor: Option<
+            unsafe extern "C" fn(
+                factory: *const clap_preset_discovery_factory,
+                index: u32,
+            ) -> *const clap_preset_discovery_provider_descriptor,
+        >,
+        pub create: Option<
+            unsafe extern "C" fn(
+                factory: *const clap_preset_discovery_factory,
+                indexer: *const clap_preset_discovery_indexer,
+                provider_id: *const c_char,
+            ) -> *const clap_preset_discovery_provider,
+        >,
+    }
+}
+
+const PROVIDER_ID: *const c_char =
+    unsafe { CStr::from_bytes_with_nul_unchecked(b"OctaSine.patches\0").as_ptr() };
+const PROVIDER_NAME: *const c_char =
+    unsafe { CStr::from_bytes_with_nul_unchecked(b"OctaSine patches\0").as_ptr() };
+const CLAP_ABI: *const c_char = unsafe { CStr::from_bytes_with_nul_unchecked(b"clap\0").as_ptr() };
+
+pub static PROVIDER_DESCRIPTOR: clap_preset_discovery_provider_descriptor =
+    clap_preset_discovery_provider_descriptor {
+        clap_version: CLAP_VERSION,
+        id: PROVIDER_ID,
+        name: PROVIDER_NAME,
+        vendor: VENDOR,
+    };
+
+/// Directory indexed for user patch and bank files
+pub fn get_user_patch_dir() -> anyhow::Result<PathBuf> {
+    get_file_storage_dir().map(|path| path.join("Patches"))
+}
+
+/// Provider indexing built-in patches and fxp and fxb files in user patch
+/// directory
+#[repr(C)]
+pub struct PresetDiscoveryProvider {
+    // Must be first field for pointer casts to work
+    clap_provider: clap_preset_discovery_provider,
+    indexer: *const clap_preset_discovery_indexer,
+}
+
+impl PresetDiscoveryProvider {
+    pub fn create(
+        indexer: *const clap_preset_discovery_indexer,
+    ) -> *const clap_preset_discovery_provider {
+        let provider = Box::new(Self {
+            clap_provider: clap_preset_discovery_provider {
+                desc: &PROVIDER_DESCRIPTOR,
+                provider_data: ::std::ptr::null_mut(),
+                init: Some(Self::init),
+                destroy: Some(Self::destroy),
+                get_metadata: Some(Self::get_metadata),
+                get_extension: Some(Self::get_extension),
+            },
+            indexer,
+        });
+
+        Box::into_raw(provider) as *const clap_preset_discovery_provider
+    }
+
+    unsafe extern "C" fn init(provider: *const clap_preset_discovery_provider) -> bool {
+        let provider = &*(provider as *const Self);
+        let indexer = &*provider.indexer;
+
+        let (declare_filetype, declare_location) =
+            match (indexer.declare_filetype, indexer.declare_location) {
+                (Some(declare_filetype), Some(declare_location)) => {
+                    (declare_filetype, declare_location)
+                }
+                _ => return false,
+            };
+
+        for (name, file_extension) in [
+            (
+                CStr::from_bytes_with_nul_unchecked(b"OctaSine patch\0"),
+                CStr::from_bytes_with_nul_unchecked(b"fxp\0"),
+            ),
+            (
+                CStr::from_bytes_with_nul_unchecked(b"OctaSine patch bank\0"),
+                CStr::from_bytes_with_nul_unchecked(b"fxb\0"),
+            ),
+        ] {
+            let filetype = clap_preset_discovery_filetype {
+                name: name.as_ptr(),
+                description: null(),
+                file_extension: file_extension.as_ptr(),
+            };
+
+            if !declare_filetype(provider.indexer, &filetype) {
+                return false;
+            }
+        }
+
+        let path = match get_user_patch_dir() {
+            Ok(path) => path,
+            Err(err) => {
+                ::log::error!("Couldn't get user patch directory: {:#}", err);
+
+                return false;
+            }
+        };
+
+        // Make sure directory exists so that users have somewhere to put
+        // patches. Ignore creation errors.
+        let _ = ::std::fs::create_dir_all(&path);
+
+        let path = match CString::new(path.to_string_lossy().as_bytes()) {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+
+        let locations = [
+            clap_preset_discovery_location {
+                flags: CLAP_PRESET_DISCOVERY_IS_FACTORY_CONTENT,
+                name: CStr::from_bytes_with_nul_unchecked(b"OctaSine built-in patches\0").as_ptr(),
+                kind: CLAP_PRESET_DISCOVERY_LOCATION_PLUGIN,
+                location: null(),
+            },
+            clap_preset_discovery_location {
+                flags: CLAP_PRESET_DISCOVERY_IS_USER_CONTENT,
+                name: CStr::from_bytes_with_nul_unchecked(b"OctaSine user patches\0").as_ptr(),
+                kind: CLAP_PRESET_DISCOVERY_LOCATION_FILE,
+                location: path.as_ptr(),
+            },
+        ];
+
+        locations
+            .iter()
+            .all(|location| declare_location(provider.indexer, location))
+    }
+
+    unsafe extern "C" fn destroy(provider: *const clap_preset_discovery_provider) {
+        drop(Box::from_raw(provider as *mut Self));
+    }
+
+    unsafe extern "C" fn get_metadata(
+        _provider: *const clap_preset_discovery_provider,
+        location_kind: u32,
+        location: *const c_char,
+        metadata_receiver: *const clap_preset_discovery_metadata_receiver,
+    ) -> bool {
+        if metadata_receiver.is_null() {
+            return false;
+        }
+
+        let receiver = &*metadata_receiver;
+
+        let report_error = |os_error: i32, message: String| {
+            if let Some(on_error) = receiver.on_error {
+                let message = CString::new(message).unwrap_or_default();
+
+                on_error(metadata_receiver, os_error, message.as_ptr());
+            }
+        };
+
+        // Presets as names and optional bank indices
+        let (presets, flags) = match location_kind {
+            // Built-in patches, with bank indices as load keys
+            CLAP_PRESET_DISCOVERY_LOCATION_PLUGIN => {
+                let presets = get_built_in_patch_names()
+                    .into_iter()
+                    .enumerate()
+                    // Skip unused patch slots
+                    .filter(|(_, name)| !matches!(name.as_str(), "" | "-"))
+                    .map(|(index, name)| (CompactString::from(name), Some(index)))
+                    .collect::<Vec<_>>();
+
+                (presets, CLAP_PRESET_DISCOVERY_IS_FACTORY_CONTENT)
+            }
+            CLAP_PRESET_DISCOVERY_LOCATION_FILE if !location.is_null() => {
+                let path = match CStr::from_ptr(location).to_str() {
+                    Ok(path) => Path::new(path),
+                    Err(_) => return false,
+                };
+
+                let bytes = match ::std::fs::read(path) {
+                    Ok(bytes) => bytes,
+                    Err(err) => {
+                        report_error(err.raw_os_error().unwrap_or(0), format!("{:#}", err));
+
+                        return false;
+                    }
+                };
+
+                let presets = match path.extension().and_then(|s| s.to_str()) {
+                    Some("fxb") => get_patch_names_from_bank_bytes(&bytes).map(|names| {
+                        names
+                            .into_iter()
+                            .enumerate()
+                            // Skip unused patch slots
+                            .filter(|(_, name)| !matches!(name.as_str(), "" | "-"))
+                            .map(|(index, name)| (name, Some(index)))
+                            .collect::<Vec<_>>()
+                    }),
+                    Some("fxp") => get_patch_name_from_bytes(&bytes).map(|name| vec![(name, None)]),
+                    _ => return false,
+                };
+
+                match presets {
+                    Ok(presets) => (presets, CLAP_PRESET_DISCOVERY_IS_USER_CONTENT),
+                    Err(err) => {
+                        report_error(0, format!("{:#}", err));
+
+                        return false;
+                    }
+                }
+            }
+            _ => return false,
+        };
+
+        let (begin_preset, add_plugin_id, set_flags) = match (
+            receiver.begin_preset,
+            receiver.add_plugin_id,
+            receiver.set_flags,
+        ) {
+            (Some(begin_preset), Some(add_plugin_id), Some(set_flags)) => {
+                (begin_preset, add_plugin_id, set_flags)
+            }
+            _ => return false,
+        };
+
+        let plugin_id = clap_universal_plugin_id {
+            abi: CLAP_ABI,
+            id: PLUGIN_ID,
+        };
+
+        for (name, opt_bank_index) in presets {
+            let name = CString::new(name.as_str()).unwrap_or_default();
+            let opt_load_key =
+                opt_bank_index.map(|index| CString::new(index.to_string()).unwrap_or_default());
+
+            let load_key = opt_load_key
+                .as_ref()
+                .map(|load_key| load_key.as_ptr())
+                .unwrap_or(null());
+
+            if !begin_preset(metadata_receiver, name.as_ptr(), load_key) {
+                break;
+            }
+
+            add_plugin_id(metadata_receiver, &plugin_id);
+            set_flags(metadata_receiver, flags);
+        }
+
+        true
+    }
+
+    unsafe extern "C" fn get_extension(
+        _provider: *const clap_preset_discovery_provider,
+        _extension_id: *const c_char,
+    ) -> *const c_void {
+        null()
+    }
+}
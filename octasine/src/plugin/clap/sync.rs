@@ -6,9 +6,15 @@ use parking_lot::Mutex;
 
 use crate::{
     common::EventToHost,
-    parameters::WrappedParameter,
+    parameters::{
+        chord_memory::ChordShape,
+        macros::MacroTarget,
+        midi_learn::{MidiLearnScope, MidiMappings},
+        velocity_curve::CustomVelocityCurve,
+        WrappedParameter,
+    },
     settings::Settings,
-    sync::{change_info::MAX_NUM_PARAMETERS, GuiSyncHandle, SyncState},
+    sync::{change_info::MAX_NUM_PARAMETERS, snapshots::SnapshotSlot, GuiSyncHandle, SyncState},
 };
 
 use super::plugin::EventToHostProducer;
@@ -23,7 +29,7 @@ unsafe impl Send for ClapGuiSyncHandle {}
 unsafe impl Sync for ClapGuiSyncHandle {}
 
 impl ClapGuiSyncHandle {
-    fn send_event(&self, event: EventToHost) {
+    pub fn send_event(&self, event: EventToHost) {
         if let Err(_) = self.producer.lock().push(event) {
             ::log::error!("ClapGuiSyncHandle can't send event due to full buffer");
         }
@@ -171,6 +177,15 @@ impl GuiSyncHandle for Arc<SyncState<ClapGuiSyncHandle>> {
             host.send_event(EventToHost::RescanValues);
         }
     }
+    fn import_bank(&self, bytes: &[u8]) {
+        if let Err(err) = self.patches.import_bank_from_bytes(bytes) {
+            ::log::error!("failed importing patch bank: {:#}", err);
+        }
+
+        if let Some(host) = &self.host {
+            host.send_event(EventToHost::RescanValues);
+        }
+    }
     fn clear_patch(&self) {
         self.patches.clear_current_patch();
 
@@ -185,4 +200,110 @@ impl GuiSyncHandle for Arc<SyncState<ClapGuiSyncHandle>> {
             host.send_event(EventToHost::RescanValues);
         }
     }
+    fn get_macro_targets(&self, macro_index: usize) -> Vec<MacroTarget> {
+        self.patches.get_macro_targets(macro_index)
+    }
+    fn set_macro_targets(&self, macro_index: usize, targets: Vec<MacroTarget>) {
+        self.patches.set_macro_targets(macro_index, targets);
+
+        if let Some(host) = &self.host {
+            host.send_events([
+                EventToHost::RescanInfo,
+                EventToHost::RescanValues,
+                EventToHost::StateChanged,
+            ]);
+        }
+    }
+    fn get_custom_velocity_curve(&self) -> CustomVelocityCurve {
+        self.patches.get_custom_velocity_curve()
+    }
+    fn set_custom_velocity_curve(&self, curve: CustomVelocityCurve) {
+        self.patches.set_custom_velocity_curve(curve);
+
+        if let Some(host) = &self.host {
+            host.send_event(EventToHost::StateChanged);
+        }
+    }
+    fn get_chord_shape(&self) -> ChordShape {
+        self.patches.get_chord_shape()
+    }
+    fn set_chord_shape(&self, shape: ChordShape) {
+        self.patches.set_chord_shape(shape);
+
+        if let Some(host) = &self.host {
+            host.send_event(EventToHost::StateChanged);
+        }
+    }
+    fn request_chord_learn(&self) {
+        self.patches.request_chord_learn();
+
+        if let Some(host) = &self.host {
+            host.send_event(EventToHost::StateChanged);
+        }
+    }
+    fn get_midi_mapping(&self, parameter: WrappedParameter) -> Option<(u8, MidiLearnScope)> {
+        self.patches.get_midi_mapping(parameter.index() as usize)
+    }
+    fn request_midi_learn(&self, parameter: WrappedParameter, scope: MidiLearnScope) {
+        self.patches
+            .request_midi_learn(parameter.index() as usize, scope);
+    }
+    fn clear_midi_mapping(&self, parameter: WrappedParameter) {
+        self.patches.clear_midi_mapping(parameter.index() as usize);
+
+        if let Some(host) = &self.host {
+            host.send_event(EventToHost::StateChanged);
+        }
+    }
+    fn apply_midi_learn_result(&self) {
+        if self.patches.apply_midi_learn_result() {
+            if let Some(host) = &self.host {
+                host.send_event(EventToHost::StateChanged);
+            }
+        }
+    }
+    fn get_global_midi_mappings(&self) -> MidiMappings {
+        self.patches.get_global_midi_mappings()
+    }
+    fn have_global_midi_mappings_changed(&self) -> bool {
+        self.patches.have_global_midi_mappings_changed()
+    }
+    fn get_active_snapshot(&self) -> SnapshotSlot {
+        self.patches.get_active_snapshot()
+    }
+    fn switch_snapshot(&self) {
+        self.patches.switch_snapshot();
+
+        if let Some(host) = &self.host {
+            host.send_events([EventToHost::RescanValues, EventToHost::StateChanged]);
+        }
+    }
+    fn copy_snapshot_a_to_b(&self) {
+        self.patches.copy_snapshot_a_to_b();
+
+        if let Some(host) = &self.host {
+            host.send_events([EventToHost::RescanValues, EventToHost::StateChanged]);
+        }
+    }
+    fn is_parameter_locked(&self, parameter: WrappedParameter) -> bool {
+        self.patches.is_parameter_locked(parameter.index() as usize)
+    }
+    fn set_parameter_locked(&self, parameter: WrappedParameter, locked: bool) {
+        self.patches
+            .set_parameter_locked(parameter.index() as usize, locked);
+    }
+    fn randomize_patch(&self) {
+        self.patches.randomize_current_patch();
+
+        if let Some(host) = &self.host {
+            host.send_events([EventToHost::RescanValues, EventToHost::StateChanged]);
+        }
+    }
+    fn mutate_patch(&self, amount: f32) {
+        self.patches.mutate_current_patch(amount);
+
+        if let Some(host) = &self.host {
+            host.send_events([EventToHost::RescanValues, EventToHost::StateChanged]);
+        }
+    }
 }
@@ -0,0 +1,215 @@
+//! Minimal LV2 C API bindings for the parts of the spec used by OctaSine
+
+#![allow(
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals,
+    dead_code
+)]
+
+use std::ffi::{c_char, c_int, c_void, CStr};
+
+pub const LV2_URID__map: &CStr = c"http://lv2plug.in/ns/ext/urid#map";
+pub const LV2_MIDI__MidiEvent: &CStr = c"http://lv2plug.in/ns/ext/midi#MidiEvent";
+pub const LV2_ATOM__Chunk: &CStr = c"http://lv2plug.in/ns/ext/atom#Chunk";
+pub const LV2_STATE__interface: &CStr = c"http://lv2plug.in/ns/ext/state#interface";
+pub const LV2_UI__parent: &CStr = c"http://lv2plug.in/ns/extensions/ui#parent";
+pub const LV2_UI__idleInterface: &CStr = c"http://lv2plug.in/ns/extensions/ui#idleInterface";
+pub const LV2_INSTANCE_ACCESS_URI: &CStr = c"http://lv2plug.in/ns/ext/instance-access";
+
+pub type LV2_Handle = *mut c_void;
+pub type LV2_URID = u32;
+
+#[repr(C)]
+pub struct LV2_Feature {
+    pub URI: *const c_char,
+    pub data: *mut c_void,
+}
+
+#[repr(C)]
+pub struct LV2_Descriptor {
+    pub URI: *const c_char,
+    pub instantiate: Option<
+        unsafe extern "C" fn(
+            descriptor: *const LV2_Descriptor,
+            sample_rate: f64,
+            bundle_path: *const c_char,
+            features: *const *const LV2_Feature,
+        ) -> LV2_Handle,
+    >,
+    pub connect_port:
+        Option<unsafe extern "C" fn(instance: LV2_Handle, port: u32, data_location: *mut c_void)>,
+    pub activate: Option<unsafe extern "C" fn(instance: LV2_Handle)>,
+    pub run: Option<unsafe extern "C" fn(instance: LV2_Handle, sample_count: u32)>,
+    pub deactivate: Option<unsafe extern "C" fn(instance: LV2_Handle)>,
+    pub cleanup: Option<unsafe extern "C" fn(instance: LV2_Handle)>,
+    pub extension_data: Option<unsafe extern "C" fn(uri: *const c_char) -> *const c_void>,
+}
+
+// SAFETY: only contains pointers to static data
+unsafe impl Sync for LV2_Descriptor {}
+
+#[repr(C)]
+pub struct LV2_URID_Map {
+    pub handle: *mut c_void,
+    pub map: Option<unsafe extern "C" fn(handle: *mut c_void, uri: *const c_char) -> LV2_URID>,
+}
+
+#[repr(C)]
+pub struct LV2_Atom {
+    pub size: u32,
+    pub type_: u32,
+}
+
+#[repr(C)]
+pub struct LV2_Atom_Sequence_Body {
+    pub unit: u32,
+    pub pad: u32,
+}
+
+#[repr(C)]
+pub struct LV2_Atom_Sequence {
+    pub atom: LV2_Atom,
+    pub body: LV2_Atom_Sequence_Body,
+}
+
+#[repr(C)]
+pub struct LV2_Atom_Event {
+    /// Time in frames (sequences with beat time units are not supported)
+    pub time_frames: i64,
+    pub body: LV2_Atom,
+}
+
+pub type LV2_State_Status = u32;
+
+pub const LV2_STATE_SUCCESS: LV2_State_Status = 0;
+pub const LV2_STATE_ERR_UNKNOWN: LV2_State_Status = 1;
+pub const LV2_STATE_ERR_BAD_TYPE: LV2_State_Status = 2;
+pub const LV2_STATE_ERR_NO_PROPERTY: LV2_State_Status = 5;
+
+pub type LV2_State_Flags = u32;
+
+pub const LV2_STATE_IS_POD: LV2_State_Flags = 1;
+pub const LV2_STATE_IS_PORTABLE: LV2_State_Flags = 2;
+
+pub type LV2_State_Handle = *mut c_void;
+
+pub type LV2_State_Store_Function = Option<
+    unsafe extern "C" fn(
+        handle: LV2_State_Handle,
+        key: LV2_URID,
+        value: *const c_void,
+        size: usize,
+        type_: LV2_URID,
+        flags: LV2_State_Flags,
+    ) -> LV2_State_Status,
+>;
+
+pub type LV2_State_Retrieve_Function = Option<
+    unsafe extern "C" fn(
+        handle: LV2_State_Handle,
+        key: LV2_URID,
+        size: *mut usize,
+        type_: *mut LV2_URID,
+        flags: *mut LV2_State_Flags,
+    ) -> *const c_void,
+>;
+
+#[repr(C)]
+pub struct LV2_State_Interface {
+    pub save: Option<
+        unsafe extern "C" fn(
+            instance: LV2_Handle,
+            store: LV2_State_Store_Function,
+            handle: LV2_State_Handle,
+            flags: LV2_State_Flags,
+            features: *const *const LV2_Feature,
+        ) -> LV2_State_Status,
+    >,
+    pub restore: Option<
+        unsafe extern "C" fn(
+            instance: LV2_Handle,
+            retrieve: LV2_State_Retrieve_Function,
+            handle: LV2_State_Handle,
+            flags: LV2_State_Flags,
+            features: *const *const LV2_Feature,
+        ) -> LV2_State_Status,
+    >,
+}
+
+pub type LV2UI_Handle = *mut c_void;
+pub type LV2UI_Controller = *mut c_void;
+pub type LV2UI_Widget = *mut c_void;
+
+pub type LV2UI_Write_Function = Option<
+    unsafe extern "C" fn(
+        controller: LV2UI_Controller,
+        port_index: u32,
+        buffer_size: u32,
+        port_protocol: u32,
+        buffer: *const c_void,
+    ),
+>;
+
+#[repr(C)]
+pub struct LV2UI_Descriptor {
+    pub URI: *const c_char,
+    pub instantiate: Option<
+        unsafe extern "C" fn(
+            descriptor: *const LV2UI_Descriptor,
+            plugin_uri: *const c_char,
+            bundle_path: *const c_char,
+            write_function: LV2UI_Write_Function,
+            controller: LV2UI_Controller,
+            widget: *mut LV2UI_Widget,
+            features: *const *const LV2_Feature,
+        ) -> LV2UI_Handle,
+    >,
+    pub cleanup: Option<unsafe extern "C" fn(ui: LV2UI_Handle)>,
+    pub port_event: Option<
+        unsafe extern "C" fn(
+            ui: LV2UI_Handle,
+            port_index: u32,
+            buffer_size: u32,
+            format: u32,
+            buffer: *const c_void,
+        ),
+    >,
+    pub extension_data: Option<unsafe extern "C" fn(uri: *const c_char) -> *const c_void>,
+}
+
+// SAFETY: only contains pointers to static data
+unsafe impl Sync for LV2UI_Descriptor {}
+
+#[repr(C)]
+pub struct LV2UI_Idle_Interface {
+    pub idle: Option<unsafe extern "C" fn(ui: LV2UI_Handle) -> c_int>,
+}
+
+/// Return data of feature with given URI, if host provided it
+///
+/// # Safety
+///
+/// `features` must be null or a null-terminated array of valid features
+pub unsafe fn get_feature_data(
+    features: *const *const LV2_Feature,
+    uri: &CStr,
+) -> Option<*mut c_void> {
+    if features.is_null() {
+        return None;
+    }
+
+    let mut feature_ptr = features;
+
+    while !(*feature_ptr).is_null() {
+        let feature = &**feature_ptr;
+
+        if !feature.URI.is_null() && CStr::from_ptr(feature.URI) == uri {
+            return Some(feature.data);
+        }
+
+        feature_ptr = feature_ptr.add(1);
+    }
+
+    None
+}
@@ -0,0 +1,333 @@
+//! LV2 plugin
+//!
+//! Port layout is MIDI atom input, stereo audio output and then one control
+//! port per parameter in [PARAMETERS] order. Control port values are in
+//! range 0.0 to 1.0, like VST2 and clap parameter values.
+
+pub mod bindings;
+mod state;
+pub mod ttl;
+#[cfg(all(feature = "gui", target_os = "linux"))]
+pub mod ui;
+
+use std::{
+    ffi::{c_char, c_void, CStr},
+    mem::size_of,
+    ptr::{null, null_mut},
+    sync::Arc,
+};
+
+use crate::{
+    audio::{gen::process_f32_runtime_select, AudioState},
+    common::{NoteEvent, NoteEventInner, SampleRate},
+    osc::OscServer,
+    parameters::PARAMETERS,
+    sync::SyncState,
+    utils::{init_logging, update_audio_parameters, update_parameters_from_midi_controllers},
+};
+
+use bindings::*;
+
+pub const PLUGIN_URI: &CStr = c"https://octasine.com/lv2";
+/// State key for patch bank in v2 serde format
+pub const STATE_BANK_URI: &CStr = c"https://octasine.com/lv2#bank";
+
+pub const PORT_INDEX_MIDI_IN: u32 = 0;
+pub const PORT_INDEX_AUDIO_OUT_LEFT: u32 = 1;
+pub const PORT_INDEX_AUDIO_OUT_RIGHT: u32 = 2;
+pub const PORT_INDEX_FIRST_PARAMETER: u32 = 3;
+
+pub static DESCRIPTOR: LV2_Descriptor = LV2_Descriptor {
+    URI: PLUGIN_URI.as_ptr(),
+    instantiate: Some(instantiate),
+    connect_port: Some(connect_port),
+    activate: None,
+    run: Some(run),
+    deactivate: None,
+    cleanup: Some(cleanup),
+    extension_data: Some(extension_data),
+};
+
+struct Urids {
+    midi_event: LV2_URID,
+    atom_chunk: LV2_URID,
+    state_bank: LV2_URID,
+}
+
+impl Urids {
+    unsafe fn new(map: &LV2_URID_Map) -> Option<Self> {
+        let map_fn = map.map?;
+
+        Some(Self {
+            midi_event: map_fn(map.handle, LV2_MIDI__MidiEvent.as_ptr()),
+            atom_chunk: map_fn(map.handle, LV2_ATOM__Chunk.as_ptr()),
+            state_bank: map_fn(map.handle, STATE_BANK_URI.as_ptr()),
+        })
+    }
+}
+
+struct Ports {
+    midi_in: *const LV2_Atom_Sequence,
+    audio_out_left: *mut f32,
+    audio_out_right: *mut f32,
+    parameters: Vec<*const f32>,
+}
+
+pub struct OctaSine {
+    pub audio: Box<AudioState>,
+    pub sync: Arc<SyncState<()>>,
+    urids: Urids,
+    ports: Ports,
+    /// Last seen parameter control port values, used to only pass on values
+    /// that host actually changed
+    parameter_port_values: Vec<f32>,
+    /// Don't apply port values on next run, e.g. because state was just
+    /// restored and host might not have updated ports accordingly
+    ignore_parameter_ports: bool,
+    _opt_osc_server: Option<OscServer>,
+}
+
+impl OctaSine {
+    fn update_parameters_from_ports(&mut self) {
+        let ports = self.ports.parameters.iter();
+        let last_values = self.parameter_port_values.iter_mut();
+
+        for (index, (port, last_value)) in ports.zip(last_values).enumerate() {
+            if port.is_null() {
+                continue;
+            }
+
+            let value = unsafe { **port };
+
+            if value != *last_value {
+                *last_value = value;
+
+                if !self.ignore_parameter_ports {
+                    self.sync.patches.set_parameter_from_host(index, value);
+                }
+            }
+        }
+
+        self.ignore_parameter_ports = false;
+    }
+}
+
+unsafe extern "C" fn instantiate(
+    _descriptor: *const LV2_Descriptor,
+    sample_rate: f64,
+    _bundle_path: *const c_char,
+    features: *const *const LV2_Feature,
+) -> LV2_Handle {
+    // If initialization of logging fails, we can't do much about it, but
+    // we shouldn't panic
+    let _ = init_logging("lv2");
+
+    let opt_urids = get_feature_data(features, LV2_URID__map)
+        .filter(|data| !data.is_null())
+        .and_then(|data| Urids::new(&*(data as *const LV2_URID_Map)));
+
+    let urids = if let Some(urids) = opt_urids {
+        urids
+    } else {
+        ::log::error!("LV2 host doesn't provide required feature urid:map");
+
+        return null_mut();
+    };
+
+    let mut audio: Box<AudioState> = Default::default();
+
+    audio.set_sample_rate(SampleRate(sample_rate));
+
+    let sync = Arc::new(SyncState::new(None));
+    let _opt_osc_server = OscServer::start_if_enabled(sync.clone());
+
+    let parameter_port_values = (0..PARAMETERS.len())
+        .map(|index| sync.patches.get_parameter_value(index).unwrap_or(0.0))
+        .collect();
+
+    let plugin = OctaSine {
+        audio,
+        sync,
+        urids,
+        ports: Ports {
+            midi_in: null(),
+            audio_out_left: null_mut(),
+            audio_out_right: null_mut(),
+            parameters: vec![null(); PARAMETERS.len()],
+        },
+        parameter_port_values,
+        ignore_parameter_ports: true,
+        _opt_osc_server,
+    };
+
+    Box::into_raw(Box::new(plugin)) as LV2_Handle
+}
+
+unsafe extern "C" fn connect_port(instance: LV2_Handle, port: u32, data_location: *mut c_void) {
+    let plugin = &mut *(instance as *mut OctaSine);
+
+    match port {
+        PORT_INDEX_MIDI_IN => {
+            plugin.ports.midi_in = data_location as *const LV2_Atom_Sequence;
+        }
+        PORT_INDEX_AUDIO_OUT_LEFT => {
+            plugin.ports.audio_out_left = data_location as *mut f32;
+        }
+        PORT_INDEX_AUDIO_OUT_RIGHT => {
+            plugin.ports.audio_out_right = data_location as *mut f32;
+        }
+        port => {
+            let index = (port - PORT_INDEX_FIRST_PARAMETER) as usize;
+
+            if let Some(parameter_port) = plugin.ports.parameters.get_mut(index) {
+                *parameter_port = data_location as *const f32;
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn run(instance: LV2_Handle, sample_count: u32) {
+    let plugin = &mut *(instance as *mut OctaSine);
+
+    plugin.update_parameters_from_ports();
+
+    if !plugin.ports.midi_in.is_null() {
+        plugin.audio.enqueue_note_events(iter_midi_events(
+            plugin.ports.midi_in,
+            plugin.urids.midi_event,
+        ));
+    }
+
+    if plugin.ports.audio_out_left.is_null() | plugin.ports.audio_out_right.is_null() {
+        return;
+    }
+
+    let lefts = std::slice::from_raw_parts_mut(plugin.ports.audio_out_left, sample_count as usize);
+    let rights =
+        std::slice::from_raw_parts_mut(plugin.ports.audio_out_right, sample_count as usize);
+
+    process_f32_runtime_select(
+        &mut plugin.audio,
+        lefts,
+        rights,
+        None,
+        None,
+        0,
+        |audio_state| {
+            update_parameters_from_midi_controllers(audio_state, &plugin.sync);
+            update_audio_parameters(audio_state, &plugin.sync);
+        },
+    );
+}
+
+unsafe extern "C" fn cleanup(instance: LV2_Handle) {
+    drop(Box::from_raw(instance as *mut OctaSine));
+}
+
+unsafe extern "C" fn extension_data(uri: *const c_char) -> *const c_void {
+    if CStr::from_ptr(uri) == LV2_STATE__interface {
+        &state::STATE_INTERFACE as *const _ as *const c_void
+    } else {
+        null()
+    }
+}
+
+/// Iterate over MIDI events in atom sequence. Events are already sorted by
+/// time according to spec.
+///
+/// # Safety
+///
+/// `sequence` must point to a valid atom sequence that outlives the iterator
+unsafe fn iter_midi_events(
+    sequence: *const LV2_Atom_Sequence,
+    midi_event_urid: LV2_URID,
+) -> impl Iterator<Item = NoteEvent> {
+    let body_size =
+        ((*sequence).atom.size as usize).saturating_sub(size_of::<LV2_Atom_Sequence_Body>());
+    let events_start = (sequence as *const u8).add(size_of::<LV2_Atom_Sequence>());
+
+    let mut offset = 0;
+
+    ::std::iter::from_fn(move || loop {
+        if offset + size_of::<LV2_Atom_Event>() > body_size {
+            return None;
+        }
+
+        let event = &*(events_start.add(offset) as *const LV2_Atom_Event);
+        let data = events_start.add(offset + size_of::<LV2_Atom_Event>());
+        let data_size = event.body.size as usize;
+
+        // Events are padded to 64-bit boundaries
+        offset += (size_of::<LV2_Atom_Event>() + data_size + 7) & !7;
+
+        if (event.body.type_ == midi_event_urid) & (1..=3).contains(&data_size) {
+            let mut midi_data = [0; 3];
+
+            midi_data[..data_size].copy_from_slice(std::slice::from_raw_parts(data, data_size));
+
+            return Some(NoteEvent {
+                delta_frames: event.time_frames.try_into().unwrap_or(0),
+                event: NoteEventInner::Midi { data: midi_data },
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iter_midi_events() {
+        const MIDI_URID: LV2_URID = 7;
+        const OTHER_URID: LV2_URID = 8;
+
+        fn push_event(words: &mut Vec<u64>, frame: i64, type_: u32, data: &[u8]) {
+            words.push(frame as u64);
+            words.push(u64::from(data.len() as u32) | (u64::from(type_) << 32));
+
+            for chunk in data.chunks(8) {
+                let mut bytes = [0u8; 8];
+
+                bytes[..chunk.len()].copy_from_slice(chunk);
+
+                words.push(u64::from_ne_bytes(bytes));
+            }
+        }
+
+        let mut words: Vec<u64> = Vec::new();
+
+        // Sequence header followed by three events: note on, an event of
+        // other type and (two byte) program change
+        words.push(0);
+        words.push(0);
+
+        push_event(&mut words, 3, MIDI_URID, &[0x90, 60, 100]);
+        push_event(&mut words, 5, OTHER_URID, &[1; 12]);
+        push_event(&mut words, 9, MIDI_URID, &[0xC0, 2]);
+
+        let body_size = ((words.len() - 1) * 8) as u32;
+
+        words[0] = u64::from(body_size);
+
+        let sequence = words.as_ptr() as *const LV2_Atom_Sequence;
+
+        let events: Vec<NoteEvent> = unsafe { iter_midi_events(sequence, MIDI_URID).collect() };
+
+        assert_eq!(events.len(), 2);
+
+        assert_eq!(events[0].delta_frames, 3);
+        assert!(matches!(
+            events[0].event,
+            NoteEventInner::Midi {
+                data: [0x90, 60, 100]
+            }
+        ));
+
+        assert_eq!(events[1].delta_frames, 9);
+        assert!(matches!(
+            events[1].event,
+            NoteEventInner::Midi { data: [0xC0, 2, 0] }
+        ));
+    }
+}
@@ -0,0 +1,85 @@
+use std::ffi::c_void;
+
+use super::{bindings::*, OctaSine};
+
+pub static STATE_INTERFACE: LV2_State_Interface = LV2_State_Interface {
+    save: Some(save),
+    restore: Some(restore),
+};
+
+unsafe extern "C" fn save(
+    instance: LV2_Handle,
+    store: LV2_State_Store_Function,
+    handle: LV2_State_Handle,
+    _flags: LV2_State_Flags,
+    _features: *const *const LV2_Feature,
+) -> LV2_State_Status {
+    let plugin = &*(instance as *const OctaSine);
+
+    let store = if let Some(store) = store {
+        store
+    } else {
+        return LV2_STATE_ERR_UNKNOWN;
+    };
+
+    let bytes = plugin.sync.patches.export_plain_bytes();
+
+    store(
+        handle,
+        plugin.urids.state_bank,
+        bytes.as_ptr() as *const c_void,
+        bytes.len(),
+        plugin.urids.atom_chunk,
+        LV2_STATE_IS_POD | LV2_STATE_IS_PORTABLE,
+    )
+}
+
+unsafe extern "C" fn restore(
+    instance: LV2_Handle,
+    retrieve: LV2_State_Retrieve_Function,
+    handle: LV2_State_Handle,
+    _flags: LV2_State_Flags,
+    _features: *const *const LV2_Feature,
+) -> LV2_State_Status {
+    let plugin = &mut *(instance as *mut OctaSine);
+
+    let retrieve = if let Some(retrieve) = retrieve {
+        retrieve
+    } else {
+        return LV2_STATE_ERR_UNKNOWN;
+    };
+
+    let mut size = 0;
+    let mut type_ = 0;
+    let mut flags = 0;
+
+    let data = retrieve(
+        handle,
+        plugin.urids.state_bank,
+        &mut size,
+        &mut type_,
+        &mut flags,
+    );
+
+    if data.is_null() {
+        return LV2_STATE_ERR_NO_PROPERTY;
+    }
+    if type_ != plugin.urids.atom_chunk {
+        return LV2_STATE_ERR_BAD_TYPE;
+    }
+
+    let bytes = std::slice::from_raw_parts(data as *const u8, size);
+
+    match plugin.sync.patches.import_bank_from_bytes(bytes) {
+        Ok(()) => {
+            plugin.ignore_parameter_ports = true;
+
+            LV2_STATE_SUCCESS
+        }
+        Err(err) => {
+            ::log::error!("restore OctaSine LV2 state: {:#}", err);
+
+            LV2_STATE_ERR_UNKNOWN
+        }
+    }
+}
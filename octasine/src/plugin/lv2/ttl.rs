@@ -0,0 +1,217 @@
+//! Generate LV2 bundle turtle files from [PARAMETERS]
+
+use std::{fmt::Write, path::Path};
+
+use crate::{parameters::PARAMETERS, sync::PatchBank};
+
+use super::{
+    PLUGIN_URI, PORT_INDEX_AUDIO_OUT_LEFT, PORT_INDEX_AUDIO_OUT_RIGHT, PORT_INDEX_FIRST_PARAMETER,
+    PORT_INDEX_MIDI_IN,
+};
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.ttl";
+pub const PLUGIN_FILE_NAME: &str = "octasine.ttl";
+
+const PREFIXES: &str = "\
+@prefix atom: <http://lv2plug.in/ns/ext/atom#> .
+@prefix doap: <http://usefulinc.com/ns/doap#> .
+@prefix foaf: <http://xmlns.com/foaf/0.1/> .
+@prefix lv2: <http://lv2plug.in/ns/lv2core#> .
+@prefix midi: <http://lv2plug.in/ns/ext/midi#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix state: <http://lv2plug.in/ns/ext/state#> .
+@prefix ui: <http://lv2plug.in/ns/extensions/ui#> .
+@prefix urid: <http://lv2plug.in/ns/ext/urid#> .
+";
+
+/// Write manifest and plugin description to bundle directory. The plugin
+/// library needs to be copied there separately.
+pub fn write_bundle_files(bundle_dir: &Path) -> anyhow::Result<()> {
+    ::std::fs::create_dir_all(bundle_dir)?;
+    ::std::fs::write(bundle_dir.join(MANIFEST_FILE_NAME), manifest_ttl())?;
+    ::std::fs::write(bundle_dir.join(PLUGIN_FILE_NAME), plugin_ttl())?;
+
+    Ok(())
+}
+
+pub fn binary_file_name() -> String {
+    format!(
+        "{}octasine{}",
+        ::std::env::consts::DLL_PREFIX,
+        ::std::env::consts::DLL_SUFFIX
+    )
+}
+
+pub fn manifest_ttl() -> String {
+    let uri = plugin_uri();
+    let binary = binary_file_name();
+
+    let mut ttl = format!(
+        "{PREFIXES}
+<{uri}>
+    a lv2:Plugin, lv2:InstrumentPlugin ;
+    lv2:binary <{binary}> ;
+    rdfs:seeAlso <{PLUGIN_FILE_NAME}> .
+"
+    );
+
+    if cfg!(all(feature = "gui", target_os = "linux")) {
+        let _ = write!(
+            ttl,
+            "
+<{uri}#ui>
+    a ui:X11UI ;
+    ui:binary <{binary}> ;
+    rdfs:seeAlso <{PLUGIN_FILE_NAME}> .
+"
+        );
+    }
+
+    ttl
+}
+
+pub fn plugin_ttl() -> String {
+    let uri = plugin_uri();
+
+    let mut ttl = format!(
+        "{PREFIXES}
+<{uri}>
+    a lv2:Plugin, lv2:InstrumentPlugin ;
+    doap:name \"OctaSine\" ;
+    doap:license <https://spdx.org/licenses/AGPL-3.0> ;
+    doap:maintainer [ foaf:name \"Joakim Frostegard\" ] ;
+    lv2:requiredFeature urid:map ;
+    lv2:optionalFeature lv2:hardRTCapable ;
+    lv2:extensionData state:interface ;
+"
+    );
+
+    if cfg!(all(feature = "gui", target_os = "linux")) {
+        let _ = writeln!(ttl, "    ui:ui <{uri}#ui> ;");
+    }
+
+    let _ = write!(
+        ttl,
+        "    lv2:port [
+        a lv2:InputPort, atom:AtomPort ;
+        atom:bufferType atom:Sequence ;
+        atom:supports midi:MidiEvent ;
+        lv2:designation lv2:control ;
+        lv2:index {PORT_INDEX_MIDI_IN} ;
+        lv2:symbol \"midi_in\" ;
+        lv2:name \"MIDI in\" ;
+    ] , [
+        a lv2:OutputPort, lv2:AudioPort ;
+        lv2:index {PORT_INDEX_AUDIO_OUT_LEFT} ;
+        lv2:symbol \"out_left\" ;
+        lv2:name \"Left\" ;
+    ] , [
+        a lv2:OutputPort, lv2:AudioPort ;
+        lv2:index {PORT_INDEX_AUDIO_OUT_RIGHT} ;
+        lv2:symbol \"out_right\" ;
+        lv2:name \"Right\" ;
+    ]"
+    );
+
+    let patch_bank = PatchBank::default();
+
+    for (index, parameter) in PARAMETERS.iter().enumerate() {
+        let default = patch_bank.get_parameter_value(index).unwrap_or(0.0);
+
+        let _ = write!(
+            ttl,
+            " , [
+        a lv2:InputPort, lv2:ControlPort ;
+        lv2:index {} ;
+        lv2:symbol \"{}\" ;
+        lv2:name \"{}\" ;
+        lv2:default {:?} ;
+        lv2:minimum 0.0 ;
+        lv2:maximum 1.0 ;
+    ]",
+            PORT_INDEX_FIRST_PARAMETER as usize + index,
+            port_symbol(&parameter.name()),
+            parameter.name().replace('\\', "\\\\").replace('"', "\\\""),
+            default,
+        );
+    }
+
+    ttl.push_str(" .\n");
+
+    if cfg!(all(feature = "gui", target_os = "linux")) {
+        let _ = write!(
+            ttl,
+            "
+<{uri}#ui>
+    a ui:X11UI ;
+    lv2:requiredFeature ui:idleInterface, ui:parent, <http://lv2plug.in/ns/ext/instance-access> ;
+    lv2:extensionData ui:idleInterface .
+"
+        );
+    }
+
+    ttl
+}
+
+fn plugin_uri() -> &'static str {
+    PLUGIN_URI.to_str().expect("plugin URI is valid UTF-8")
+}
+
+/// Convert parameter name to valid LV2 port symbol (C identifier)
+fn port_symbol(name: &str) -> String {
+    let mut symbol = String::with_capacity(name.len());
+
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            symbol.push(c.to_ascii_lowercase());
+        } else if !symbol.is_empty() && !symbol.ends_with('_') {
+            symbol.push('_');
+        }
+    }
+
+    let symbol = symbol.trim_end_matches('_');
+
+    if symbol.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("p_{}", symbol)
+    } else {
+        symbol.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_port_symbols() {
+        assert_eq!(port_symbol("Pitch bend range (up)"), "pitch_bend_range_up");
+        assert_eq!(port_symbol("OP 1 vol"), "op_1_vol");
+
+        let symbols: HashSet<String> = PARAMETERS.iter().map(|p| port_symbol(&p.name())).collect();
+
+        assert_eq!(symbols.len(), PARAMETERS.len(), "port symbols not unique");
+
+        for symbol in symbols {
+            assert!(!symbol.is_empty());
+            assert!(!symbol.starts_with(|c: char| c.is_ascii_digit()));
+            assert!(symbol
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        }
+    }
+
+    #[test]
+    fn test_plugin_ttl_ports() {
+        let ttl = plugin_ttl();
+
+        let num_control_ports = ttl.matches("lv2:ControlPort").count();
+
+        assert_eq!(num_control_ports, PARAMETERS.len());
+        assert!(ttl.contains(&format!(
+            "lv2:index {} ;",
+            PORT_INDEX_FIRST_PARAMETER as usize + PARAMETERS.len() - 1
+        )));
+    }
+}
@@ -0,0 +1,335 @@
+//! LV2 X11 UI embedding the iced GUI
+//!
+//! Requires instance access, since GUI works directly on plugin sync state.
+//! Parameter changes made in GUI are passed on to host through control port
+//! writes in the idle callback, which host calls from its UI thread.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    path::PathBuf,
+    ptr::{null, null_mut},
+    sync::Arc,
+};
+
+use compact_str::CompactString;
+use iced_baseview::window::WindowHandle;
+use parking_lot::Mutex;
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+
+use crate::{
+    gui::{get_iced_baseview_settings, Message, OctaSineIcedApplication},
+    parameters::{
+        chord_memory::ChordShape,
+        macros::MacroTarget,
+        midi_learn::{MidiLearnScope, MidiMappings},
+        velocity_curve::CustomVelocityCurve,
+        WrappedParameter,
+    },
+    plugin::common::PLUGIN_SEMVER_NAME,
+    settings::Settings,
+    sync::{change_info::MAX_NUM_PARAMETERS, snapshots::SnapshotSlot, GuiSyncHandle, SyncState},
+};
+
+use super::{bindings::*, OctaSine, PORT_INDEX_FIRST_PARAMETER};
+
+pub const UI_URI: &CStr = c"https://octasine.com/lv2#ui";
+
+pub static UI_DESCRIPTOR: LV2UI_Descriptor = LV2UI_Descriptor {
+    URI: UI_URI.as_ptr(),
+    instantiate: Some(instantiate),
+    cleanup: Some(cleanup),
+    port_event: None,
+    extension_data: Some(extension_data),
+};
+
+static IDLE_INTERFACE: LV2UI_Idle_Interface = LV2UI_Idle_Interface { idle: Some(idle) };
+
+#[derive(Clone)]
+pub struct Lv2GuiSyncHandle {
+    sync: Arc<SyncState<()>>,
+    /// Parameter values to write to control ports in next idle callback
+    pending_port_writes: Arc<Mutex<Vec<(WrappedParameter, f32)>>>,
+}
+
+impl Lv2GuiSyncHandle {
+    fn queue_port_write(&self, parameter: WrappedParameter, value: f32) {
+        self.pending_port_writes.lock().push((parameter, value));
+    }
+}
+
+impl GuiSyncHandle for Lv2GuiSyncHandle {
+    fn begin_edit(&self, _parameter: WrappedParameter) {}
+    fn end_edit(&self, _parameter: WrappedParameter) {}
+    fn set_parameter(&self, parameter: WrappedParameter, value: f32) {
+        self.queue_port_write(parameter, value);
+
+        self.sync
+            .patches
+            .set_parameter_from_gui(parameter.index() as usize, value);
+    }
+    fn set_parameter_immediate(&self, parameter: WrappedParameter, value: f32) {
+        self.set_parameter(parameter, value);
+    }
+    fn parse_parameter_from_text(&self, parameter: WrappedParameter, text: &str) -> Option<f32> {
+        let parser = self
+            .sync
+            .patches
+            .get_current_patch()
+            .parameters
+            .get(&parameter.key())?
+            .value_from_text;
+
+        parser(text)
+    }
+    fn get_parameter_text_choices(
+        &self,
+        parameter: WrappedParameter,
+    ) -> Option<Vec<CompactString>> {
+        self.sync
+            .patches
+            .get_current_patch()
+            .parameters
+            .get(&parameter.key())
+            .and_then(|p| p.text_choices.clone())
+    }
+    fn set_parameter_audio_only(&self, parameter: WrappedParameter, value: f32) {
+        self.sync
+            .patches
+            .set_parameter_from_gui(parameter.index() as usize, value);
+    }
+    fn get_parameter(&self, parameter: WrappedParameter) -> f32 {
+        self.sync
+            .patches
+            .get_parameter_value(parameter.index() as usize)
+            .unwrap() // FIXME: unwrap
+    }
+    fn format_parameter_value(&self, parameter: WrappedParameter, value: f32) -> CompactString {
+        self.sync
+            .patches
+            .format_parameter_value(parameter.index() as usize, value)
+            .unwrap() // FIXME: unwrap
+    }
+    fn get_patches(&self) -> (usize, Vec<CompactString>) {
+        let index = self.sync.patches.get_patch_index();
+        let names = self.sync.patches.get_patch_names();
+
+        (index, names)
+    }
+    fn set_patch_index(&self, index: usize) {
+        self.sync.patches.set_patch_index(index);
+    }
+    fn get_current_patch_name(&self) -> CompactString {
+        self.sync.patches.get_current_patch_name()
+    }
+    fn set_current_patch_name(&self, name: &str) {
+        self.sync.patches.set_patch_name(name);
+    }
+    fn get_changed_parameters(&self) -> Option<[Option<f32>; MAX_NUM_PARAMETERS]> {
+        self.sync.patches.get_changed_parameters_from_gui()
+    }
+    fn have_patches_changed(&self) -> bool {
+        self.sync.patches.have_patches_changed()
+    }
+    fn get_gui_settings(&self) -> crate::gui::GuiSettings {
+        Settings::load_or_default().gui
+    }
+    fn export_patch(&self) -> (CompactString, Vec<u8>) {
+        let name = self.sync.patches.get_current_patch().get_fxp_filename();
+        let data = self.sync.patches.get_current_patch().export_fxp_bytes();
+
+        (name, data)
+    }
+    fn export_bank(&self) -> Vec<u8> {
+        self.sync.patches.export_fxb_bytes()
+    }
+    fn import_bank_or_patches_from_paths(&self, paths: &[PathBuf]) {
+        self.sync.patches.import_bank_or_patches_from_paths(paths);
+    }
+    fn import_bank(&self, bytes: &[u8]) {
+        if let Err(err) = self.sync.patches.import_bank_from_bytes(bytes) {
+            ::log::error!("failed importing patch bank: {:#}", err);
+        }
+    }
+    fn clear_patch(&self) {
+        self.sync.patches.clear_current_patch();
+    }
+    fn clear_bank(&self) {
+        self.sync.patches.clear_bank();
+    }
+    fn get_macro_targets(&self, macro_index: usize) -> Vec<MacroTarget> {
+        self.sync.patches.get_macro_targets(macro_index)
+    }
+    fn set_macro_targets(&self, macro_index: usize, targets: Vec<MacroTarget>) {
+        self.sync.patches.set_macro_targets(macro_index, targets);
+    }
+    fn get_custom_velocity_curve(&self) -> CustomVelocityCurve {
+        self.sync.patches.get_custom_velocity_curve()
+    }
+    fn set_custom_velocity_curve(&self, curve: CustomVelocityCurve) {
+        self.sync.patches.set_custom_velocity_curve(curve);
+    }
+    fn get_chord_shape(&self) -> ChordShape {
+        self.sync.patches.get_chord_shape()
+    }
+    fn set_chord_shape(&self, shape: ChordShape) {
+        self.sync.patches.set_chord_shape(shape);
+    }
+    fn request_chord_learn(&self) {
+        self.sync.patches.request_chord_learn();
+    }
+    fn get_midi_mapping(&self, parameter: WrappedParameter) -> Option<(u8, MidiLearnScope)> {
+        self.sync
+            .patches
+            .get_midi_mapping(parameter.index() as usize)
+    }
+    fn request_midi_learn(&self, parameter: WrappedParameter, scope: MidiLearnScope) {
+        self.sync
+            .patches
+            .request_midi_learn(parameter.index() as usize, scope);
+    }
+    fn clear_midi_mapping(&self, parameter: WrappedParameter) {
+        self.sync
+            .patches
+            .clear_midi_mapping(parameter.index() as usize);
+    }
+    fn apply_midi_learn_result(&self) {
+        self.sync.patches.apply_midi_learn_result();
+    }
+    fn get_global_midi_mappings(&self) -> MidiMappings {
+        self.sync.patches.get_global_midi_mappings()
+    }
+    fn have_global_midi_mappings_changed(&self) -> bool {
+        self.sync.patches.have_global_midi_mappings_changed()
+    }
+    fn get_active_snapshot(&self) -> SnapshotSlot {
+        self.sync.patches.get_active_snapshot()
+    }
+    fn switch_snapshot(&self) {
+        self.sync.patches.switch_snapshot();
+    }
+    fn copy_snapshot_a_to_b(&self) {
+        self.sync.patches.copy_snapshot_a_to_b();
+    }
+    fn is_parameter_locked(&self, parameter: WrappedParameter) -> bool {
+        self.sync
+            .patches
+            .is_parameter_locked(parameter.index() as usize)
+    }
+    fn set_parameter_locked(&self, parameter: WrappedParameter, locked: bool) {
+        self.sync
+            .patches
+            .set_parameter_locked(parameter.index() as usize, locked);
+    }
+    fn randomize_patch(&self) {
+        self.sync.patches.randomize_current_patch();
+    }
+    fn mutate_patch(&self, amount: f32) {
+        self.sync.patches.mutate_current_patch(amount);
+    }
+}
+
+struct OctaSineUi {
+    sync_handle: Lv2GuiSyncHandle,
+    write_function: LV2UI_Write_Function,
+    controller: LV2UI_Controller,
+    window_handle: WindowHandle<Message>,
+}
+
+struct ParentWindow(*mut c_void);
+
+unsafe impl HasRawWindowHandle for ParentWindow {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        let mut handle = raw_window_handle::XcbWindowHandle::empty();
+
+        handle.window = self.0 as u32;
+
+        RawWindowHandle::Xcb(handle)
+    }
+}
+
+unsafe extern "C" fn instantiate(
+    _descriptor: *const LV2UI_Descriptor,
+    _plugin_uri: *const c_char,
+    _bundle_path: *const c_char,
+    write_function: LV2UI_Write_Function,
+    controller: LV2UI_Controller,
+    widget: *mut LV2UI_Widget,
+    features: *const *const LV2_Feature,
+) -> LV2UI_Handle {
+    let opt_plugin = get_feature_data(features, LV2_INSTANCE_ACCESS_URI)
+        .filter(|data| !data.is_null())
+        .map(|data| &*(data as *const OctaSine));
+    let opt_parent = get_feature_data(features, LV2_UI__parent);
+
+    let (plugin, parent) = if let (Some(plugin), Some(parent)) = (opt_plugin, opt_parent) {
+        (plugin, parent)
+    } else {
+        ::log::error!(
+            "LV2 host doesn't provide required UI features instance-access and ui:parent"
+        );
+
+        return null_mut();
+    };
+
+    let sync_handle = Lv2GuiSyncHandle {
+        sync: plugin.sync.clone(),
+        pending_port_writes: Default::default(),
+    };
+
+    let window_handle =
+        iced_baseview::open_parented::<OctaSineIcedApplication<Lv2GuiSyncHandle>, ParentWindow>(
+            &ParentWindow(parent),
+            get_iced_baseview_settings(sync_handle.clone(), PLUGIN_SEMVER_NAME.to_string()),
+        );
+
+    // Window is embedded through ui:parent, so no widget is passed to host
+    if !widget.is_null() {
+        *widget = null_mut();
+    }
+
+    let ui = OctaSineUi {
+        sync_handle,
+        write_function,
+        controller,
+        window_handle,
+    };
+
+    Box::into_raw(Box::new(ui)) as LV2UI_Handle
+}
+
+unsafe extern "C" fn cleanup(ui: LV2UI_Handle) {
+    let mut ui = Box::from_raw(ui as *mut OctaSineUi);
+
+    ui.window_handle.close_window();
+}
+
+unsafe extern "C" fn idle(ui: LV2UI_Handle) -> c_int {
+    let ui = &*(ui as *const OctaSineUi);
+
+    let port_writes = ::std::mem::take(&mut *ui.sync_handle.pending_port_writes.lock());
+
+    if let Some(write_function) = ui.write_function {
+        for (parameter, value) in port_writes {
+            let port_index = PORT_INDEX_FIRST_PARAMETER + u32::from(parameter.index());
+
+            // Protocol 0 means plain float control port value
+            write_function(
+                ui.controller,
+                port_index,
+                ::std::mem::size_of::<f32>() as u32,
+                0,
+                &value as *const f32 as *const c_void,
+            );
+        }
+    }
+
+    0
+}
+
+unsafe extern "C" fn extension_data(uri: *const c_char) -> *const c_void {
+    if CStr::from_ptr(uri) == LV2_UI__idleInterface {
+        &IDLE_INTERFACE as *const _ as *const c_void
+    } else {
+        null()
+    }
+}
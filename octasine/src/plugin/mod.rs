@@ -1,5 +1,9 @@
 #[cfg(feature = "clap")]
 pub mod clap;
 pub mod common;
+#[cfg(feature = "lv2")]
+pub mod lv2;
 #[cfg(feature = "vst2")]
 pub mod vst2;
+#[cfg(feature = "vst3")]
+pub mod vst3;
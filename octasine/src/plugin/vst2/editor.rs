@@ -3,11 +3,12 @@ use std::sync::Arc;
 use iced_baseview::{open_blocking, open_parented, window::WindowHandle};
 use parking_lot::Mutex;
 use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+use vst::{host::Host, plugin::HostCallback};
 
 use crate::{
     gui::{get_iced_baseview_settings, Message, GUI_HEIGHT, GUI_WIDTH},
     plugin::vst2::PLUGIN_SEMVER_NAME,
-    sync::GuiSyncHandle,
+    sync::{GuiSyncHandle, SyncState},
 };
 
 use crate::gui::OctaSineIcedApplication;
@@ -33,7 +34,7 @@ impl<H: GuiSyncHandle> Editor<H> {
     }
 }
 
-impl<H: GuiSyncHandle> vst::editor::Editor for Editor<H> {
+impl vst::editor::Editor for Editor<Arc<SyncState<HostCallback>>> {
     fn size(&self) -> (i32, i32) {
         (GUI_WIDTH as i32, GUI_HEIGHT as i32)
     }
@@ -47,10 +48,11 @@ impl<H: GuiSyncHandle> vst::editor::Editor for Editor<H> {
             return false;
         }
 
-        let window_handle = open_parented::<OctaSineIcedApplication<H>, ParentWindow>(
-            &ParentWindow(parent),
-            get_iced_baseview_settings(self.sync_state.clone(), PLUGIN_SEMVER_NAME.to_string()),
-        );
+        let window_handle =
+            open_parented::<OctaSineIcedApplication<Arc<SyncState<HostCallback>>>, ParentWindow>(
+                &ParentWindow(parent),
+                get_iced_baseview_settings(self.sync_state.clone(), PLUGIN_SEMVER_NAME.to_string()),
+            );
 
         self.window_handle = Some(WindowHandleWrapper::new(window_handle));
 
@@ -66,6 +68,17 @@ impl<H: GuiSyncHandle> vst::editor::Editor for Editor<H> {
     fn is_open(&mut self) -> bool {
         self.window_handle.is_some()
     }
+
+    /// Called by host from UI thread. Host is notified of patch changes
+    /// caused by MIDI program change messages here rather than from audio
+    /// thread, so this only happens while editor is open.
+    fn idle(&mut self) {
+        if self.sync_state.patches.has_patch_index_changed_from_audio() {
+            if let Some(host) = self.sync_state.host {
+                host.update_display();
+            }
+        }
+    }
 }
 
 struct WindowHandleWrapper(Arc<Mutex<WindowHandle<Message>>>);
@@ -12,6 +12,7 @@ use vst::plugin::{CanDo, Category, HostCallback, Info, Plugin, PluginParameters}
 
 use crate::audio::gen::process_f32_runtime_select;
 use crate::audio::AudioState;
+use crate::osc::OscServer;
 use crate::sync::SyncState;
 use crate::utils::{init_logging, update_audio_parameters};
 use crate::{common::*, crate_version};
@@ -23,6 +24,7 @@ pub struct OctaSine {
     pub sync: Arc<SyncState<vst::plugin::HostCallback>>,
     #[cfg(feature = "gui")]
     editor: Option<editor::Editor<Arc<SyncState<vst::plugin::HostCallback>>>>,
+    _opt_osc_server: Option<OscServer>,
 }
 
 impl Default for OctaSine {
@@ -44,6 +46,7 @@ impl OctaSine {
 
         Self {
             audio: Default::default(),
+            _opt_osc_server: OscServer::start_if_enabled(sync.clone()),
             sync,
             #[cfg(feature = "gui")]
             editor: Some(editor),
@@ -68,11 +71,19 @@ impl OctaSine {
 #[allow(deprecated)]
 impl Plugin for OctaSine {
     fn process(&mut self, buffer: &mut vst::buffer::AudioBuffer<f32>) {
-        let (l, r) = &mut buffer.split().1.split_at_mut(1);
+        let (inputs, outputs) = buffer.split();
+        let (l, r) = &mut outputs.split_at_mut(1);
 
         let lefts = l.get_mut(0);
         let rights = r.get_mut(0);
 
+        // Stereo input is used as sidechain modulation source
+        let opt_inputs = if inputs.len() == 2 {
+            Some((inputs.get(0), inputs.get(1)))
+        } else {
+            None
+        };
+
         // VST2 spec does not guarantee that events are sent in order
         self.audio.sort_note_events();
 
@@ -80,9 +91,31 @@ impl Plugin for OctaSine {
             self.audio.set_bpm(bpm);
         }
 
-        process_f32_runtime_select(&mut self.audio, lefts, rights, 0, |audio_state| {
-            update_audio_parameters(audio_state, &self.sync);
-        });
+        process_f32_runtime_select(
+            &mut self.audio,
+            lefts,
+            rights,
+            opt_inputs,
+            None,
+            0,
+            |audio_state| {
+                update_audio_parameters(audio_state, &self.sync);
+
+                // Let host record parameter changes caused by mapped MIDI
+                // controllers and OSC messages. VST2 hosts expect automate
+                // calls from process.
+                if let (Some(host), Some(changes)) = (
+                    self.sync.host,
+                    self.sync.patches.get_changed_parameters_from_midi_and_osc(),
+                ) {
+                    for (index, opt_value) in changes.into_iter().enumerate() {
+                        if let Some(value) = opt_value {
+                            host.automate(index as i32, value);
+                        }
+                    }
+                }
+            },
+        );
     }
 
     fn new(host: HostCallback) -> Self {
@@ -96,7 +129,7 @@ impl Plugin for OctaSine {
             version: crate_version_to_vst2_format(crate_version!()),
             unique_id: PLUGIN_UNIQUE_VST2_ID,
             category: Category::Synth,
-            inputs: 0,
+            inputs: 2,
             outputs: 2,
             presets: self.sync.patches.num_patches() as i32,
             parameters: self.sync.patches.num_parameters() as i32,
@@ -108,19 +141,37 @@ impl Plugin for OctaSine {
     }
 
     fn process_events(&mut self, events: &Events) {
-        self.audio
-            .enqueue_note_events(events.events().filter_map(|event| {
-                if let Event::Midi(event) = event {
-                    let event = NoteEvent {
-                        delta_frames: event.delta_frames.try_into().unwrap_or(0),
-                        event: NoteEventInner::Midi { data: event.data },
-                    };
-
-                    Some(event)
-                } else {
-                    None
+        for event in events.events() {
+            if let Event::Midi(event) = event {
+                let delta_frames = event.delta_frames.try_into().unwrap_or(0);
+
+                // VST2 parameter changes carry no sample offsets, but MIDI
+                // events do, so apply changes to parameters mapped to MIDI
+                // controllers at the offset of the message
+                let [status, controller, value] = event.data;
+
+                if status >> 4 == 0b_1011 {
+                    let audio = &mut self.audio;
+
+                    self.sync.patches.set_parameters_from_midi_controller_with(
+                        controller,
+                        value,
+                        |_, parameter, value| {
+                            audio.enqueue_parameter_event(ParameterEvent {
+                                delta_frames,
+                                parameter: parameter.parameter.parameter(),
+                                value,
+                            });
+                        },
+                    );
                 }
-            }))
+
+                self.audio.enqueue_note_event(NoteEvent {
+                    delta_frames,
+                    event: NoteEventInner::Midi { data: event.data },
+                });
+            }
+        }
     }
 
     fn set_sample_rate(&mut self, rate: f32) {
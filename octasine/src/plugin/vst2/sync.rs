@@ -8,7 +8,16 @@ use vst::host::Host;
 
 use crate::{parameters::WrappedParameter, sync::SyncState};
 #[cfg(feature = "gui")]
-use crate::{settings::Settings, sync::change_info::MAX_NUM_PARAMETERS};
+use crate::{
+    parameters::{
+        chord_memory::ChordShape,
+        macros::MacroTarget,
+        midi_learn::{MidiLearnScope, MidiMappings},
+        velocity_curve::CustomVelocityCurve,
+    },
+    settings::Settings,
+    sync::{change_info::MAX_NUM_PARAMETERS, snapshots::SnapshotSlot},
+};
 
 impl vst::plugin::PluginParameters for SyncState<vst::plugin::HostCallback> {
     /// Get parameter label for parameter at `index` (e.g. "db", "sec", "ms", "%").
@@ -40,6 +49,11 @@ impl vst::plugin::PluginParameters for SyncState<vst::plugin::HostCallback> {
     }
 
     /// Set the value of parameter at `index`. `value` is between 0.0 and 1.0.
+    ///
+    /// VST2 hosts don't pass sample offsets with parameter changes, so
+    /// changes are applied at the start of the next processed buffer. MIDI
+    /// events do carry `delta_frames`, so changes to parameters mapped to
+    /// MIDI controllers are applied at their offsets (see `process_events`).
     fn set_parameter(&self, index: i32, value: f32) {
         self.patches.set_parameter_from_host(index as usize, value);
     }
@@ -227,10 +241,101 @@ impl crate::sync::GuiSyncHandle for Arc<SyncState<vst::plugin::HostCallback>> {
             host.update_display();
         }
     }
+    fn import_bank(&self, bytes: &[u8]) {
+        if let Err(err) = self.patches.import_bank_from_bytes(bytes) {
+            ::log::error!("failed importing patch bank: {:#}", err);
+        }
+
+        if let Some(host) = self.host {
+            host.update_display();
+        }
+    }
     fn clear_patch(&self) {
         self.patches.clear_current_patch();
     }
     fn clear_bank(&self) {
         self.patches.clear_bank();
     }
+    fn get_macro_targets(&self, macro_index: usize) -> Vec<MacroTarget> {
+        self.patches.get_macro_targets(macro_index)
+    }
+    fn set_macro_targets(&self, macro_index: usize, targets: Vec<MacroTarget>) {
+        self.patches.set_macro_targets(macro_index, targets);
+
+        if let Some(host) = self.host {
+            host.update_display();
+        }
+    }
+    fn get_custom_velocity_curve(&self) -> CustomVelocityCurve {
+        self.patches.get_custom_velocity_curve()
+    }
+    fn set_custom_velocity_curve(&self, curve: CustomVelocityCurve) {
+        self.patches.set_custom_velocity_curve(curve);
+    }
+    fn get_chord_shape(&self) -> ChordShape {
+        self.patches.get_chord_shape()
+    }
+    fn set_chord_shape(&self, shape: ChordShape) {
+        self.patches.set_chord_shape(shape);
+    }
+    fn request_chord_learn(&self) {
+        self.patches.request_chord_learn();
+    }
+    fn get_midi_mapping(&self, parameter: WrappedParameter) -> Option<(u8, MidiLearnScope)> {
+        self.patches.get_midi_mapping(parameter.index() as usize)
+    }
+    fn request_midi_learn(&self, parameter: WrappedParameter, scope: MidiLearnScope) {
+        self.patches
+            .request_midi_learn(parameter.index() as usize, scope);
+    }
+    fn clear_midi_mapping(&self, parameter: WrappedParameter) {
+        self.patches.clear_midi_mapping(parameter.index() as usize);
+    }
+    fn apply_midi_learn_result(&self) {
+        self.patches.apply_midi_learn_result();
+    }
+    fn get_global_midi_mappings(&self) -> MidiMappings {
+        self.patches.get_global_midi_mappings()
+    }
+    fn have_global_midi_mappings_changed(&self) -> bool {
+        self.patches.have_global_midi_mappings_changed()
+    }
+    fn get_active_snapshot(&self) -> SnapshotSlot {
+        self.patches.get_active_snapshot()
+    }
+    fn switch_snapshot(&self) {
+        self.patches.switch_snapshot();
+
+        if let Some(host) = self.host {
+            host.update_display();
+        }
+    }
+    fn copy_snapshot_a_to_b(&self) {
+        self.patches.copy_snapshot_a_to_b();
+
+        if let Some(host) = self.host {
+            host.update_display();
+        }
+    }
+    fn is_parameter_locked(&self, parameter: WrappedParameter) -> bool {
+        self.patches.is_parameter_locked(parameter.index() as usize)
+    }
+    fn set_parameter_locked(&self, parameter: WrappedParameter, locked: bool) {
+        self.patches
+            .set_parameter_locked(parameter.index() as usize, locked);
+    }
+    fn randomize_patch(&self) {
+        self.patches.randomize_current_patch();
+
+        if let Some(host) = self.host {
+            host.update_display();
+        }
+    }
+    fn mutate_patch(&self, amount: f32) {
+        self.patches.mutate_current_patch(amount);
+
+        if let Some(host) = self.host {
+            host.update_display();
+        }
+    }
 }
@@ -0,0 +1,660 @@
+//! Minimal VST3 C++ ABI bindings for the parts of the SDK used by OctaSine
+//!
+//! Interfaces are laid out as COM-style objects: a pointer to a table of
+//! function pointers in declaration order, with inherited methods first.
+
+#![allow(
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals,
+    dead_code
+)]
+
+use std::ffi::{c_char, c_void};
+
+pub type tresult = i32;
+pub type TBool = u8;
+pub type TUID = [u8; 16];
+pub type FIDString = *const c_char;
+pub type TChar = u16;
+pub type String128 = [TChar; 128];
+pub type ParamID = u32;
+pub type ParamValue = f64;
+pub type SpeakerArrangement = u64;
+
+#[cfg(target_os = "windows")]
+mod results {
+    use super::tresult;
+
+    pub const kNoInterface: tresult = 0x80004002_u32 as tresult;
+    pub const kResultOk: tresult = 0;
+    pub const kResultTrue: tresult = kResultOk;
+    pub const kResultFalse: tresult = 1;
+    pub const kInvalidArgument: tresult = 0x80070057_u32 as tresult;
+    pub const kNotImplemented: tresult = 0x80004001_u32 as tresult;
+    pub const kInternalError: tresult = 0x80004005_u32 as tresult;
+}
+
+#[cfg(not(target_os = "windows"))]
+mod results {
+    use super::tresult;
+
+    pub const kNoInterface: tresult = -1;
+    pub const kResultOk: tresult = 0;
+    pub const kResultTrue: tresult = kResultOk;
+    pub const kResultFalse: tresult = 1;
+    pub const kInvalidArgument: tresult = 2;
+    pub const kNotImplemented: tresult = 3;
+    pub const kInternalError: tresult = 4;
+}
+
+pub use results::*;
+
+/// Build interface or class id. Byte order matches SDK `INLINE_UID`, which
+/// is COM compatible on Windows.
+pub const fn uid(l1: u32, l2: u32, l3: u32, l4: u32) -> TUID {
+    let [a1, a2, a3, a4] = l1.to_be_bytes();
+    let [b1, b2, b3, b4] = l2.to_be_bytes();
+    let [c1, c2, c3, c4] = l3.to_be_bytes();
+    let [d1, d2, d3, d4] = l4.to_be_bytes();
+
+    if cfg!(target_os = "windows") {
+        [
+            a4, a3, a2, a1, b2, b1, b4, b3, c1, c2, c3, c4, d1, d2, d3, d4,
+        ]
+    } else {
+        [
+            a1, a2, a3, a4, b1, b2, b3, b4, c1, c2, c3, c4, d1, d2, d3, d4,
+        ]
+    }
+}
+
+pub const FUnknown_iid: TUID = uid(0x00000000, 0x00000000, 0xC0000000, 0x00000046);
+pub const IPluginBase_iid: TUID = uid(0x22888DDB, 0x156E45AE, 0x8358B348, 0x08190625);
+pub const IPluginFactory_iid: TUID = uid(0x7A4D811C, 0x52114A1F, 0xAED9D2EE, 0x0B43BF9F);
+pub const IPluginFactory2_iid: TUID = uid(0x0007B650, 0xF24B4C0B, 0xA464EDB9, 0xF00B2ABB);
+pub const IComponent_iid: TUID = uid(0xE831FF31, 0xF2D54301, 0x928EBBEE, 0x25697802);
+pub const IAudioProcessor_iid: TUID = uid(0x42043F99, 0xB7DA453C, 0xA569E79D, 0x9AAEC33D);
+pub const IEditController_iid: TUID = uid(0xDCD7BBE3, 0x7742448D, 0xA874AACC, 0x979C759E);
+pub const IMidiMapping_iid: TUID = uid(0xDF0FF9F7, 0x49B74669, 0xB63AB732, 0x7ADBF5E5);
+pub const IComponentHandler2_iid: TUID = uid(0xF040B4B3, 0xA36045EC, 0xABCDC045, 0xB4D5A2CC);
+pub const IPlugView_iid: TUID = uid(0x5BC32507, 0xD06049EA, 0xA6151B52, 0x2B755B29);
+
+pub const kVstAudioEffectClass: &[u8] = b"Audio Module Class";
+pub const kManyInstances: i32 = 0x7FFFFFFF;
+pub const kDistributable: u32 = 1 << 0;
+pub const kUnicode: i32 = 1 << 4;
+
+pub const kAudio: i32 = 0;
+pub const kEvent: i32 = 1;
+pub const kInput: i32 = 0;
+pub const kOutput: i32 = 1;
+pub const kMain: i32 = 0;
+pub const kAux: i32 = 1;
+pub const kDefaultActive: u32 = 1 << 0;
+
+pub const kSample32: i32 = 0;
+pub const kSample64: i32 = 1;
+
+pub const kStereo: SpeakerArrangement = 0b11;
+
+pub const kTempoValid: u32 = 1 << 10;
+
+pub const kNoteOnEvent: u16 = 0;
+pub const kNoteOffEvent: u16 = 1;
+pub const kPolyPressureEvent: u16 = 3;
+
+pub const kCanAutomate: i32 = 1 << 0;
+pub const kIsReadOnly: i32 = 1 << 1;
+pub const kIsList: i32 = 1 << 3;
+pub const kIsHidden: i32 = 1 << 4;
+
+pub const kRootUnitId: i32 = 0;
+
+pub const kParamValuesChanged: i32 = 1 << 2;
+pub const kParamTitlesChanged: i32 = 1 << 4;
+
+pub const kAfterTouch: i16 = 128;
+pub const kPitchBend: i16 = 129;
+pub const kCountCtrlNumber: i16 = 130;
+
+pub const kIBSeekSet: i32 = 0;
+
+pub const kPlatformTypeHWND: &[u8] = b"HWND";
+pub const kPlatformTypeNSView: &[u8] = b"NSView";
+pub const kPlatformTypeX11EmbedWindowID: &[u8] = b"X11EmbedWindowID";
+
+pub const kEditor: &[u8] = b"editor";
+
+#[repr(C)]
+pub struct FUnknownVtbl {
+    pub queryInterface: unsafe extern "system" fn(
+        this: *mut c_void,
+        iid: *const TUID,
+        obj: *mut *mut c_void,
+    ) -> tresult,
+    pub addRef: unsafe extern "system" fn(this: *mut c_void) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut c_void) -> u32,
+}
+
+/// Any interface pointer: pointer to object starting with vtable pointer
+#[repr(C)]
+pub struct FUnknown {
+    pub vtbl: *const FUnknownVtbl,
+}
+
+#[repr(C)]
+pub struct PFactoryInfo {
+    pub vendor: [c_char; 64],
+    pub url: [c_char; 256],
+    pub email: [c_char; 128],
+    pub flags: i32,
+}
+
+#[repr(C)]
+pub struct PClassInfo {
+    pub cid: TUID,
+    pub cardinality: i32,
+    pub category: [c_char; 32],
+    pub name: [c_char; 64],
+}
+
+#[repr(C)]
+pub struct PClassInfo2 {
+    pub cid: TUID,
+    pub cardinality: i32,
+    pub category: [c_char; 32],
+    pub name: [c_char; 64],
+    pub classFlags: u32,
+    pub subCategories: [c_char; 128],
+    pub vendor: [c_char; 64],
+    pub version: [c_char; 64],
+    pub sdkVersion: [c_char; 64],
+}
+
+#[repr(C)]
+pub struct IPluginFactory2Vtbl {
+    pub unknown: FUnknownVtbl,
+    pub getFactoryInfo:
+        unsafe extern "system" fn(this: *mut c_void, info: *mut PFactoryInfo) -> tresult,
+    pub countClasses: unsafe extern "system" fn(this: *mut c_void) -> i32,
+    pub getClassInfo:
+        unsafe extern "system" fn(this: *mut c_void, index: i32, info: *mut PClassInfo) -> tresult,
+    pub createInstance: unsafe extern "system" fn(
+        this: *mut c_void,
+        cid: FIDString,
+        iid: FIDString,
+        obj: *mut *mut c_void,
+    ) -> tresult,
+    pub getClassInfo2:
+        unsafe extern "system" fn(this: *mut c_void, index: i32, info: *mut PClassInfo2) -> tresult,
+}
+
+#[repr(C)]
+pub struct BusInfo {
+    pub mediaType: i32,
+    pub direction: i32,
+    pub channelCount: i32,
+    pub name: String128,
+    pub busType: i32,
+    pub flags: u32,
+}
+
+#[repr(C)]
+pub struct RoutingInfo {
+    pub mediaType: i32,
+    pub busIndex: i32,
+    pub channel: i32,
+}
+
+#[repr(C)]
+pub struct IComponentVtbl {
+    pub unknown: FUnknownVtbl,
+    // IPluginBase
+    pub initialize: unsafe extern "system" fn(this: *mut c_void, context: *mut FUnknown) -> tresult,
+    pub terminate: unsafe extern "system" fn(this: *mut c_void) -> tresult,
+    // IComponent
+    pub getControllerClassId:
+        unsafe extern "system" fn(this: *mut c_void, class_id: *mut TUID) -> tresult,
+    pub setIoMode: unsafe extern "system" fn(this: *mut c_void, mode: i32) -> tresult,
+    pub getBusCount: unsafe extern "system" fn(this: *mut c_void, media_type: i32, dir: i32) -> i32,
+    pub getBusInfo: unsafe extern "system" fn(
+        this: *mut c_void,
+        media_type: i32,
+        dir: i32,
+        index: i32,
+        bus: *mut BusInfo,
+    ) -> tresult,
+    pub getRoutingInfo: unsafe extern "system" fn(
+        this: *mut c_void,
+        in_info: *mut RoutingInfo,
+        out_info: *mut RoutingInfo,
+    ) -> tresult,
+    pub activateBus: unsafe extern "system" fn(
+        this: *mut c_void,
+        media_type: i32,
+        dir: i32,
+        index: i32,
+        state: TBool,
+    ) -> tresult,
+    pub setActive: unsafe extern "system" fn(this: *mut c_void, state: TBool) -> tresult,
+    pub setState: unsafe extern "system" fn(this: *mut c_void, state: *mut IBStream) -> tresult,
+    pub getState: unsafe extern "system" fn(this: *mut c_void, state: *mut IBStream) -> tresult,
+}
+
+#[repr(C)]
+pub struct ProcessSetup {
+    pub processMode: i32,
+    pub symbolicSampleSize: i32,
+    pub maxSamplesPerBlock: i32,
+    pub sampleRate: f64,
+}
+
+#[repr(C)]
+pub struct AudioBusBuffers {
+    pub numChannels: i32,
+    pub silenceFlags: u64,
+    /// `Sample32**` or `Sample64**` depending on symbolic sample size
+    pub channelBuffers: *mut *mut c_void,
+}
+
+#[repr(C)]
+pub struct Chord {
+    pub keyNote: u8,
+    pub rootNote: u8,
+    pub chordMask: i16,
+}
+
+#[repr(C)]
+pub struct FrameRate {
+    pub framesPerSecond: u32,
+    pub flags: u32,
+}
+
+#[repr(C)]
+pub struct ProcessContext {
+    pub state: u32,
+    pub sampleRate: f64,
+    pub projectTimeSamples: i64,
+    pub systemTime: i64,
+    pub continousTimeSamples: i64,
+    pub projectTimeMusic: f64,
+    pub barPositionMusic: f64,
+    pub cycleStartMusic: f64,
+    pub cycleEndMusic: f64,
+    pub tempo: f64,
+    pub timeSigNumerator: i32,
+    pub timeSigDenominator: i32,
+    pub chord: Chord,
+    pub smpteOffsetSubframes: i32,
+    pub frameRate: FrameRate,
+    pub samplesToNextClock: i32,
+}
+
+#[repr(C)]
+pub struct ProcessData {
+    pub processMode: i32,
+    pub symbolicSampleSize: i32,
+    pub numSamples: i32,
+    pub numInputs: i32,
+    pub numOutputs: i32,
+    pub inputs: *mut AudioBusBuffers,
+    pub outputs: *mut AudioBusBuffers,
+    pub inputParameterChanges: *mut IParameterChanges,
+    pub outputParameterChanges: *mut IParameterChanges,
+    pub inputEvents: *mut IEventList,
+    pub outputEvents: *mut IEventList,
+    pub processContext: *mut ProcessContext,
+}
+
+#[repr(C)]
+pub struct IAudioProcessorVtbl {
+    pub unknown: FUnknownVtbl,
+    pub setBusArrangements: unsafe extern "system" fn(
+        this: *mut c_void,
+        inputs: *mut SpeakerArrangement,
+        num_ins: i32,
+        outputs: *mut SpeakerArrangement,
+        num_outs: i32,
+    ) -> tresult,
+    pub getBusArrangement: unsafe extern "system" fn(
+        this: *mut c_void,
+        dir: i32,
+        index: i32,
+        arr: *mut SpeakerArrangement,
+    ) -> tresult,
+    pub canProcessSampleSize:
+        unsafe extern "system" fn(this: *mut c_void, symbolic_sample_size: i32) -> tresult,
+    pub getLatencySamples: unsafe extern "system" fn(this: *mut c_void) -> u32,
+    pub setupProcessing:
+        unsafe extern "system" fn(this: *mut c_void, setup: *mut ProcessSetup) -> tresult,
+    pub setProcessing: unsafe extern "system" fn(this: *mut c_void, state: TBool) -> tresult,
+    pub process: unsafe extern "system" fn(this: *mut c_void, data: *mut ProcessData) -> tresult,
+    pub getTailSamples: unsafe extern "system" fn(this: *mut c_void) -> u32,
+}
+
+#[repr(C)]
+pub struct ParameterInfo {
+    pub id: ParamID,
+    pub title: String128,
+    pub shortTitle: String128,
+    pub units: String128,
+    pub stepCount: i32,
+    pub defaultNormalizedValue: ParamValue,
+    pub unitId: i32,
+    pub flags: i32,
+}
+
+#[repr(C)]
+pub struct IEditControllerVtbl {
+    pub unknown: FUnknownVtbl,
+    // IPluginBase
+    pub initialize: unsafe extern "system" fn(this: *mut c_void, context: *mut FUnknown) -> tresult,
+    pub terminate: unsafe extern "system" fn(this: *mut c_void) -> tresult,
+    // IEditController
+    pub setComponentState:
+        unsafe extern "system" fn(this: *mut c_void, state: *mut IBStream) -> tresult,
+    pub setState: unsafe extern "system" fn(this: *mut c_void, state: *mut IBStream) -> tresult,
+    pub getState: unsafe extern "system" fn(this: *mut c_void, state: *mut IBStream) -> tresult,
+    pub getParameterCount: unsafe extern "system" fn(this: *mut c_void) -> i32,
+    pub getParameterInfo: unsafe extern "system" fn(
+        this: *mut c_void,
+        param_index: i32,
+        info: *mut ParameterInfo,
+    ) -> tresult,
+    pub getParamStringByValue: unsafe extern "system" fn(
+        this: *mut c_void,
+        id: ParamID,
+        value_normalized: ParamValue,
+        string: *mut TChar,
+    ) -> tresult,
+    pub getParamValueByString: unsafe extern "system" fn(
+        this: *mut c_void,
+        id: ParamID,
+        string: *const TChar,
+        value_normalized: *mut ParamValue,
+    ) -> tresult,
+    pub normalizedParamToPlain: unsafe extern "system" fn(
+        this: *mut c_void,
+        id: ParamID,
+        value_normalized: ParamValue,
+    ) -> ParamValue,
+    pub plainParamToNormalized: unsafe extern "system" fn(
+        this: *mut c_void,
+        id: ParamID,
+        plain_value: ParamValue,
+    ) -> ParamValue,
+    pub getParamNormalized: unsafe extern "system" fn(this: *mut c_void, id: ParamID) -> ParamValue,
+    pub setParamNormalized:
+        unsafe extern "system" fn(this: *mut c_void, id: ParamID, value: ParamValue) -> tresult,
+    pub setComponentHandler:
+        unsafe extern "system" fn(this: *mut c_void, handler: *mut IComponentHandler) -> tresult,
+    pub createView: unsafe extern "system" fn(this: *mut c_void, name: FIDString) -> *mut c_void,
+}
+
+#[repr(C)]
+pub struct IMidiMappingVtbl {
+    pub unknown: FUnknownVtbl,
+    pub getMidiControllerAssignment: unsafe extern "system" fn(
+        this: *mut c_void,
+        bus_index: i32,
+        channel: i16,
+        midi_controller_number: i16,
+        id: *mut ParamID,
+    ) -> tresult,
+}
+
+#[repr(C)]
+pub struct ViewRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+#[repr(C)]
+pub struct IPlugViewVtbl {
+    pub unknown: FUnknownVtbl,
+    pub isPlatformTypeSupported:
+        unsafe extern "system" fn(this: *mut c_void, type_: FIDString) -> tresult,
+    pub attached: unsafe extern "system" fn(
+        this: *mut c_void,
+        parent: *mut c_void,
+        type_: FIDString,
+    ) -> tresult,
+    pub removed: unsafe extern "system" fn(this: *mut c_void) -> tresult,
+    pub onWheel: unsafe extern "system" fn(this: *mut c_void, distance: f32) -> tresult,
+    pub onKeyDown: unsafe extern "system" fn(
+        this: *mut c_void,
+        key: u16,
+        key_code: i16,
+        modifiers: i16,
+    ) -> tresult,
+    pub onKeyUp: unsafe extern "system" fn(
+        this: *mut c_void,
+        key: u16,
+        key_code: i16,
+        modifiers: i16,
+    ) -> tresult,
+    pub getSize: unsafe extern "system" fn(this: *mut c_void, size: *mut ViewRect) -> tresult,
+    pub onSize: unsafe extern "system" fn(this: *mut c_void, new_size: *mut ViewRect) -> tresult,
+    pub onFocus: unsafe extern "system" fn(this: *mut c_void, state: TBool) -> tresult,
+    pub setFrame: unsafe extern "system" fn(this: *mut c_void, frame: *mut c_void) -> tresult,
+    pub canResize: unsafe extern "system" fn(this: *mut c_void) -> tresult,
+    pub checkSizeConstraint:
+        unsafe extern "system" fn(this: *mut c_void, rect: *mut ViewRect) -> tresult,
+}
+
+// Host interfaces
+
+#[repr(C)]
+pub struct IBStreamVtbl {
+    pub unknown: FUnknownVtbl,
+    pub read: unsafe extern "system" fn(
+        this: *mut IBStream,
+        buffer: *mut c_void,
+        num_bytes: i32,
+        num_bytes_read: *mut i32,
+    ) -> tresult,
+    pub write: unsafe extern "system" fn(
+        this: *mut IBStream,
+        buffer: *mut c_void,
+        num_bytes: i32,
+        num_bytes_written: *mut i32,
+    ) -> tresult,
+    pub seek: unsafe extern "system" fn(
+        this: *mut IBStream,
+        pos: i64,
+        mode: i32,
+        result: *mut i64,
+    ) -> tresult,
+    pub tell: unsafe extern "system" fn(this: *mut IBStream, pos: *mut i64) -> tresult,
+}
+
+#[repr(C)]
+pub struct IBStream {
+    pub vtbl: *const IBStreamVtbl,
+}
+
+#[repr(C)]
+pub struct IParamValueQueueVtbl {
+    pub unknown: FUnknownVtbl,
+    pub getParameterId: unsafe extern "system" fn(this: *mut IParamValueQueue) -> ParamID,
+    pub getPointCount: unsafe extern "system" fn(this: *mut IParamValueQueue) -> i32,
+    pub getPoint: unsafe extern "system" fn(
+        this: *mut IParamValueQueue,
+        index: i32,
+        sample_offset: *mut i32,
+        value: *mut ParamValue,
+    ) -> tresult,
+    pub addPoint: unsafe extern "system" fn(
+        this: *mut IParamValueQueue,
+        sample_offset: i32,
+        value: ParamValue,
+        index: *mut i32,
+    ) -> tresult,
+}
+
+#[repr(C)]
+pub struct IParamValueQueue {
+    pub vtbl: *const IParamValueQueueVtbl,
+}
+
+#[repr(C)]
+pub struct IParameterChangesVtbl {
+    pub unknown: FUnknownVtbl,
+    pub getParameterCount: unsafe extern "system" fn(this: *mut IParameterChanges) -> i32,
+    pub getParameterData: unsafe extern "system" fn(
+        this: *mut IParameterChanges,
+        index: i32,
+    ) -> *mut IParamValueQueue,
+    pub addParameterData: unsafe extern "system" fn(
+        this: *mut IParameterChanges,
+        id: *const ParamID,
+        index: *mut i32,
+    ) -> *mut IParamValueQueue,
+}
+
+#[repr(C)]
+pub struct IParameterChanges {
+    pub vtbl: *const IParameterChangesVtbl,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct NoteOnEvent {
+    pub channel: i16,
+    pub pitch: i16,
+    pub tuning: f32,
+    pub velocity: f32,
+    pub length: i32,
+    pub pressure: f32,
+    pub noteId: i32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct NoteOffEvent {
+    pub channel: i16,
+    pub pitch: i16,
+    pub velocity: f32,
+    pub noteId: i32,
+    pub tuning: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PolyPressureEvent {
+    pub channel: i16,
+    pub pitch: i16,
+    pub pressure: f32,
+    pub noteId: i32,
+}
+
+/// Event payload. Padding member makes the union as large as the largest
+/// SDK event type, which isn't otherwise used.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union EventData {
+    pub noteOn: NoteOnEvent,
+    pub noteOff: NoteOffEvent,
+    pub polyPressure: PolyPressureEvent,
+    pub _padding: [u64; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Event {
+    pub busIndex: i32,
+    pub sampleOffset: i32,
+    pub ppqPosition: f64,
+    pub flags: u16,
+    pub type_: u16,
+    pub data: EventData,
+}
+
+#[repr(C)]
+pub struct IEventListVtbl {
+    pub unknown: FUnknownVtbl,
+    pub getEventCount: unsafe extern "system" fn(this: *mut IEventList) -> i32,
+    pub getEvent:
+        unsafe extern "system" fn(this: *mut IEventList, index: i32, event: *mut Event) -> tresult,
+    pub addEvent: unsafe extern "system" fn(this: *mut IEventList, event: *mut Event) -> tresult,
+}
+
+#[repr(C)]
+pub struct IEventList {
+    pub vtbl: *const IEventListVtbl,
+}
+
+#[repr(C)]
+pub struct IComponentHandlerVtbl {
+    pub unknown: FUnknownVtbl,
+    pub beginEdit: unsafe extern "system" fn(this: *mut IComponentHandler, id: ParamID) -> tresult,
+    pub performEdit: unsafe extern "system" fn(
+        this: *mut IComponentHandler,
+        id: ParamID,
+        value_normalized: ParamValue,
+    ) -> tresult,
+    pub endEdit: unsafe extern "system" fn(this: *mut IComponentHandler, id: ParamID) -> tresult,
+    pub restartComponent:
+        unsafe extern "system" fn(this: *mut IComponentHandler, flags: i32) -> tresult,
+}
+
+#[repr(C)]
+pub struct IComponentHandler {
+    pub vtbl: *const IComponentHandlerVtbl,
+}
+
+#[repr(C)]
+pub struct IComponentHandler2Vtbl {
+    pub unknown: FUnknownVtbl,
+    pub setDirty: unsafe extern "system" fn(this: *mut IComponentHandler2, state: TBool) -> tresult,
+    pub requestOpenEditor:
+        unsafe extern "system" fn(this: *mut IComponentHandler2, name: FIDString) -> tresult,
+    pub startGroupEdit: unsafe extern "system" fn(this: *mut IComponentHandler2) -> tresult,
+    pub finishGroupEdit: unsafe extern "system" fn(this: *mut IComponentHandler2) -> tresult,
+}
+
+#[repr(C)]
+pub struct IComponentHandler2 {
+    pub vtbl: *const IComponentHandler2Vtbl,
+}
+
+/// Copy string into fixed size C string array, truncating if necessary
+pub fn copy_to_c_char_arr<const N: usize>(arr: &mut [c_char; N], s: &[u8]) {
+    let len = s.len().min(N - 1);
+
+    for (c, b) in arr.iter_mut().zip(s[..len].iter()) {
+        *c = *b as c_char;
+    }
+
+    arr[len] = 0;
+}
+
+/// Copy string into fixed size UTF-16 string array, truncating if necessary
+pub fn copy_to_string128(arr: &mut String128, s: &str) {
+    let mut len = 0;
+
+    for (c, u) in arr[..127].iter_mut().zip(s.encode_utf16()) {
+        *c = u;
+        len += 1;
+    }
+
+    arr[len] = 0;
+}
+
+/// Read null-terminated UTF-16 string
+///
+/// # Safety
+///
+/// `s` must point to a null-terminated UTF-16 string
+pub unsafe fn string_from_tchar_ptr(s: *const TChar) -> String {
+    let mut len = 0;
+
+    while *s.add(len) != 0 {
+        len += 1;
+    }
+
+    String::from_utf16_lossy(std::slice::from_raw_parts(s, len))
+}
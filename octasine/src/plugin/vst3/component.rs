@@ -0,0 +1,680 @@
+//! IComponent and IAudioProcessor implementation
+
+use std::ffi::c_void;
+
+use crate::{
+    audio::{
+        gen::{process_f32_runtime_select, process_f64_runtime_select},
+        AudioState,
+    },
+    common::{BeatsPerMinute, NoteEvent, NoteEventInner, ParameterEvent, SampleRate},
+    utils::update_audio_parameters,
+};
+
+use super::{
+    bindings::*, midi_parameter_controller, midi_parameter_message, parameter_id,
+    plugin_from_interface, unknown_vtbl, OctaSine, AUDIO_PROCESSOR_OFFSET, COMPONENT_OFFSET,
+};
+
+pub static COMPONENT_VTBL: IComponentVtbl = IComponentVtbl {
+    unknown: unknown_vtbl::<COMPONENT_OFFSET>(),
+    initialize,
+    terminate,
+    getControllerClassId: get_controller_class_id,
+    setIoMode: set_io_mode,
+    getBusCount: get_bus_count,
+    getBusInfo: get_bus_info,
+    getRoutingInfo: get_routing_info,
+    activateBus: activate_bus,
+    setActive: set_active,
+    setState: set_state,
+    getState: get_state,
+};
+
+pub static AUDIO_PROCESSOR_VTBL: IAudioProcessorVtbl = IAudioProcessorVtbl {
+    unknown: unknown_vtbl::<AUDIO_PROCESSOR_OFFSET>(),
+    setBusArrangements: set_bus_arrangements,
+    getBusArrangement: get_bus_arrangement,
+    canProcessSampleSize: can_process_sample_size,
+    getLatencySamples: get_latency_samples,
+    setupProcessing: setup_processing,
+    setProcessing: set_processing,
+    process,
+    getTailSamples: get_tail_samples,
+};
+
+pub unsafe extern "system" fn initialize(_this: *mut c_void, _context: *mut FUnknown) -> tresult {
+    kResultOk
+}
+
+pub unsafe extern "system" fn terminate(_this: *mut c_void) -> tresult {
+    kResultOk
+}
+
+/// Edit controller is implemented by the component itself
+unsafe extern "system" fn get_controller_class_id(
+    _this: *mut c_void,
+    _class_id: *mut TUID,
+) -> tresult {
+    kNotImplemented
+}
+
+unsafe extern "system" fn set_io_mode(_this: *mut c_void, _mode: i32) -> tresult {
+    kNotImplemented
+}
+
+unsafe extern "system" fn get_bus_count(_this: *mut c_void, media_type: i32, dir: i32) -> i32 {
+    match (media_type, dir) {
+        (kAudio, kOutput) | (kAudio, kInput) | (kEvent, kInput) => 1,
+        _ => 0,
+    }
+}
+
+unsafe extern "system" fn get_bus_info(
+    _this: *mut c_void,
+    media_type: i32,
+    dir: i32,
+    index: i32,
+    bus: *mut BusInfo,
+) -> tresult {
+    if (index != 0) | bus.is_null() {
+        return kInvalidArgument;
+    }
+
+    // Stereo input is used as sidechain modulation source
+    let (channel_count, name, bus_type, flags) = match (media_type, dir) {
+        (kAudio, kOutput) => (2, "Output", kMain, kDefaultActive),
+        (kAudio, kInput) => (2, "Sidechain", kAux, 0),
+        (kEvent, kInput) => (16, "Note input", kMain, kDefaultActive),
+        _ => return kInvalidArgument,
+    };
+
+    let bus = &mut *bus;
+
+    bus.mediaType = media_type;
+    bus.direction = dir;
+    bus.channelCount = channel_count;
+    copy_to_string128(&mut bus.name, name);
+    bus.busType = bus_type;
+    bus.flags = flags;
+
+    kResultOk
+}
+
+unsafe extern "system" fn get_routing_info(
+    _this: *mut c_void,
+    _in_info: *mut RoutingInfo,
+    _out_info: *mut RoutingInfo,
+) -> tresult {
+    kNotImplemented
+}
+
+unsafe extern "system" fn activate_bus(
+    _this: *mut c_void,
+    _media_type: i32,
+    _dir: i32,
+    _index: i32,
+    _state: TBool,
+) -> tresult {
+    kResultTrue
+}
+
+unsafe extern "system" fn set_active(_this: *mut c_void, _state: TBool) -> tresult {
+    kResultOk
+}
+
+/// Restore patch bank stored as v2 serde bytes
+unsafe extern "system" fn set_state(this: *mut c_void, state: *mut IBStream) -> tresult {
+    let plugin = plugin_from_interface::<COMPONENT_OFFSET>(this);
+
+    if state.is_null() {
+        return kInvalidArgument;
+    }
+
+    let bytes = read_stream(state);
+
+    match plugin.sync.patches.import_bank_from_bytes(&bytes) {
+        Ok(()) => kResultOk,
+        Err(err) => {
+            ::log::error!("restore OctaSine VST3 state: {:#}", err);
+
+            kResultFalse
+        }
+    }
+}
+
+/// Store patch bank as v2 serde bytes
+unsafe extern "system" fn get_state(this: *mut c_void, state: *mut IBStream) -> tresult {
+    let plugin = plugin_from_interface::<COMPONENT_OFFSET>(this);
+
+    if state.is_null() {
+        return kInvalidArgument;
+    }
+
+    if write_stream(state, &plugin.sync.patches.export_plain_bytes()) {
+        kResultOk
+    } else {
+        kResultFalse
+    }
+}
+
+unsafe fn read_stream(stream: *mut IBStream) -> Vec<u8> {
+    let read = (*(*stream).vtbl).read;
+
+    let mut bytes = Vec::new();
+    let mut buffer = [0u8; 4096];
+
+    loop {
+        let mut num_bytes_read = 0;
+
+        let result = read(
+            stream,
+            buffer.as_mut_ptr() as *mut c_void,
+            buffer.len() as i32,
+            &mut num_bytes_read,
+        );
+
+        if (result != kResultOk) | (num_bytes_read <= 0) {
+            break;
+        }
+
+        bytes.extend_from_slice(&buffer[..num_bytes_read as usize]);
+    }
+
+    bytes
+}
+
+unsafe fn write_stream(stream: *mut IBStream, mut bytes: &[u8]) -> bool {
+    let write = (*(*stream).vtbl).write;
+
+    while !bytes.is_empty() {
+        let mut num_bytes_written = 0;
+
+        let result = write(
+            stream,
+            bytes.as_ptr() as *mut c_void,
+            bytes.len().min(i32::MAX as usize) as i32,
+            &mut num_bytes_written,
+        );
+
+        if (result != kResultOk) | (num_bytes_written <= 0) {
+            return false;
+        }
+
+        bytes = &bytes[num_bytes_written as usize..];
+    }
+
+    true
+}
+
+unsafe extern "system" fn set_bus_arrangements(
+    _this: *mut c_void,
+    inputs: *mut SpeakerArrangement,
+    num_ins: i32,
+    outputs: *mut SpeakerArrangement,
+    num_outs: i32,
+) -> tresult {
+    let inputs_ok = (num_ins == 0) | ((num_ins == 1) && *inputs == kStereo);
+    let outputs_ok = (num_outs == 1) && *outputs == kStereo;
+
+    if inputs_ok & outputs_ok {
+        kResultTrue
+    } else {
+        kResultFalse
+    }
+}
+
+unsafe extern "system" fn get_bus_arrangement(
+    _this: *mut c_void,
+    dir: i32,
+    index: i32,
+    arr: *mut SpeakerArrangement,
+) -> tresult {
+    if ((dir == kInput) | (dir == kOutput)) & (index == 0) & !arr.is_null() {
+        *arr = kStereo;
+
+        kResultOk
+    } else {
+        kInvalidArgument
+    }
+}
+
+unsafe extern "system" fn can_process_sample_size(
+    _this: *mut c_void,
+    symbolic_sample_size: i32,
+) -> tresult {
+    match symbolic_sample_size {
+        kSample32 | kSample64 => kResultTrue,
+        _ => kResultFalse,
+    }
+}
+
+unsafe extern "system" fn get_latency_samples(_this: *mut c_void) -> u32 {
+    0
+}
+
+unsafe extern "system" fn setup_processing(this: *mut c_void, setup: *mut ProcessSetup) -> tresult {
+    let plugin = plugin_from_interface::<AUDIO_PROCESSOR_OFFSET>(this);
+
+    if setup.is_null() {
+        return kInvalidArgument;
+    }
+
+    plugin
+        .audio
+        .lock()
+        .set_sample_rate(SampleRate((*setup).sampleRate));
+
+    kResultOk
+}
+
+unsafe extern "system" fn set_processing(_this: *mut c_void, _state: TBool) -> tresult {
+    kResultOk
+}
+
+unsafe extern "system" fn process(this: *mut c_void, data: *mut ProcessData) -> tresult {
+    let plugin = plugin_from_interface::<AUDIO_PROCESSOR_OFFSET>(this);
+
+    if data.is_null() {
+        return kInvalidArgument;
+    }
+
+    let data = &*data;
+
+    let mut audio = plugin.audio.lock();
+
+    if !data.processContext.is_null() {
+        let context = &*data.processContext;
+
+        if context.state & kTempoValid != 0 {
+            audio.set_bpm(BeatsPerMinute(context.tempo));
+        }
+    }
+
+    if !data.inputParameterChanges.is_null() {
+        plugin.handle_parameter_changes(&mut audio, data.inputParameterChanges);
+    }
+    if !data.inputEvents.is_null() {
+        handle_events(&mut audio, data.inputEvents);
+    }
+
+    // Parameter changes and MIDI messages from hidden parameters are only
+    // sorted by time within each parameter
+    audio.sort_note_events();
+
+    let num_samples = data.numSamples.max(0) as usize;
+
+    // Hosts may call process without audio buffers to pass on parameter
+    // changes. They will then be applied in next call.
+    if (num_samples == 0) | (data.numOutputs < 1) | data.outputs.is_null() {
+        return kResultOk;
+    }
+
+    let outputs = &*data.outputs;
+
+    if (outputs.numChannels != 2) | outputs.channelBuffers.is_null() {
+        return kInvalidArgument;
+    }
+
+    let opt_input_bus = if (data.numInputs >= 1) & !data.inputs.is_null() {
+        Some(&*data.inputs).filter(|bus| (bus.numChannels == 2) & !bus.channelBuffers.is_null())
+    } else {
+        None
+    };
+
+    let updater = |audio_state: &mut AudioState| {
+        update_audio_parameters(audio_state, &plugin.sync);
+
+        // Let host record parameter changes caused by mapped MIDI
+        // controllers and OSC messages
+        if !data.outputParameterChanges.is_null() {
+            plugin.send_midi_and_osc_changes_to_host(data.outputParameterChanges);
+        }
+    };
+
+    match data.symbolicSampleSize {
+        kSample32 => {
+            let (lefts, rights) = get_channels::<f32>(outputs, num_samples);
+            let opt_inputs = opt_input_bus.map(|bus| {
+                let (lefts, rights) = get_channels::<f32>(bus, num_samples);
+
+                (&*lefts, &*rights)
+            });
+
+            process_f32_runtime_select(&mut audio, lefts, rights, opt_inputs, None, 0, updater);
+        }
+        kSample64 => {
+            let (lefts, rights) = get_channels::<f64>(outputs, num_samples);
+            let opt_inputs = opt_input_bus.map(|bus| {
+                let (lefts, rights) = get_channels::<f64>(bus, num_samples);
+
+                (&*lefts, &*rights)
+            });
+
+            process_f64_runtime_select(&mut audio, lefts, rights, opt_inputs, None, 0, updater);
+        }
+        _ => return kInvalidArgument,
+    }
+
+    kResultOk
+}
+
+unsafe extern "system" fn get_tail_samples(_this: *mut c_void) -> u32 {
+    0
+}
+
+/// # Safety
+///
+/// `bus` must have two channel buffers of sample type `T` with at least
+/// `num_samples` samples each
+unsafe fn get_channels<'a, T>(
+    bus: &AudioBusBuffers,
+    num_samples: usize,
+) -> (&'a mut [T], &'a mut [T]) {
+    let channels = bus.channelBuffers as *const *mut T;
+
+    (
+        std::slice::from_raw_parts_mut(*channels, num_samples),
+        std::slice::from_raw_parts_mut(*channels.add(1), num_samples),
+    )
+}
+
+unsafe fn handle_events(audio: &mut AudioState, events: *mut IEventList) {
+    let vtbl = &*(*events).vtbl;
+
+    for index in 0..(vtbl.getEventCount)(events) {
+        let mut event: Event = std::mem::zeroed();
+
+        if (vtbl.getEvent)(events, index, &mut event) != kResultOk {
+            continue;
+        }
+
+        let delta_frames = event.sampleOffset.max(0) as u32;
+
+        // Clap note events carry the same data as VST3 ones
+        let opt_event = match event.type_ {
+            kNoteOnEvent => {
+                let event = event.data.noteOn;
+
+                Some(NoteEventInner::ClapNoteOn {
+                    key: event.pitch.clamp(0, 127) as u8,
+                    velocity: f64::from(event.velocity),
+                    clap_note_id: event.noteId,
+                })
+            }
+            kNoteOffEvent => {
+                let event = event.data.noteOff;
+
+                Some(NoteEventInner::ClapNoteOff {
+                    key: event.pitch.clamp(0, 127) as u8,
+                    clap_note_id: event.noteId,
+                })
+            }
+            kPolyPressureEvent => {
+                let event = event.data.polyPressure;
+
+                Some(NoteEventInner::ClapNotePressure {
+                    key: event.pitch.clamp(0, 127) as u8,
+                    pressure: f64::from(event.pressure),
+                })
+            }
+            _ => None,
+        };
+
+        if let Some(event) = opt_event {
+            audio.enqueue_note_event(NoteEvent {
+                delta_frames,
+                event,
+            });
+        }
+    }
+}
+
+impl OctaSine {
+    /// Apply parameter changes at their sample offsets. Changes to hidden
+    /// MIDI parameters are passed on as MIDI messages.
+    unsafe fn handle_parameter_changes(
+        &self,
+        audio: &mut AudioState,
+        changes: *mut IParameterChanges,
+    ) {
+        let changes_vtbl = &*(*changes).vtbl;
+
+        for queue_index in 0..(changes_vtbl.getParameterCount)(changes) {
+            let queue = (changes_vtbl.getParameterData)(changes, queue_index);
+
+            if queue.is_null() {
+                continue;
+            }
+
+            let queue_vtbl = &*(*queue).vtbl;
+            let id = (queue_vtbl.getParameterId)(queue);
+
+            let points = (0..(queue_vtbl.getPointCount)(queue)).filter_map(|point_index| {
+                let mut sample_offset = 0;
+                let mut value = 0.0;
+
+                if (queue_vtbl.getPoint)(queue, point_index, &mut sample_offset, &mut value)
+                    == kResultOk
+                {
+                    Some((sample_offset.max(0) as u32, value))
+                } else {
+                    None
+                }
+            });
+
+            if let Some(controller) = midi_parameter_controller(id) {
+                for (delta_frames, value) in points {
+                    let data = midi_parameter_message(controller, value);
+
+                    if let [0xB0, controller, value] = data {
+                        self.sync.patches.set_parameters_from_midi_controller_with(
+                            controller,
+                            value,
+                            |_, parameter, value| {
+                                audio.enqueue_parameter_event(ParameterEvent {
+                                    delta_frames,
+                                    parameter: parameter.parameter.parameter(),
+                                    value,
+                                });
+                            },
+                        );
+                    }
+
+                    audio.enqueue_note_event(NoteEvent {
+                        delta_frames,
+                        event: NoteEventInner::Midi { data },
+                    });
+                }
+            } else if let Some(index) = self.get_parameter_index(id) {
+                let parameter = match self.sync.patches.get_parameter_by_index(index) {
+                    Some(parameter) => parameter,
+                    None => continue,
+                };
+
+                for (delta_frames, value) in points {
+                    let value = value as f32;
+
+                    parameter.set_value(value);
+
+                    self.sync
+                        .patches
+                        .parameter_change_info_gui
+                        .mark_as_changed(index);
+
+                    audio.enqueue_parameter_event(ParameterEvent {
+                        delta_frames,
+                        parameter: parameter.parameter.parameter(),
+                        value,
+                    });
+
+                    // Macros don't affect audio by themselves, so apply
+                    // their target values at the same sample offset
+                    self.sync.patches.set_macro_target_values(
+                        parameter,
+                        |_, target, target_value| {
+                            audio.enqueue_parameter_event(ParameterEvent {
+                                delta_frames,
+                                parameter: target.parameter.parameter(),
+                                value: target_value,
+                            });
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    unsafe fn send_midi_and_osc_changes_to_host(&self, changes: *mut IParameterChanges) {
+        let changed = match self.sync.patches.get_changed_parameters_from_midi_and_osc() {
+            Some(changed) => changed,
+            None => return,
+        };
+
+        let changes_vtbl = &*(*changes).vtbl;
+
+        for (index, opt_value) in changed.into_iter().enumerate() {
+            let (value, parameter) =
+                match (opt_value, self.sync.patches.get_parameter_by_index(index)) {
+                    (Some(value), Some(parameter)) => (value, parameter),
+                    _ => continue,
+                };
+
+            let id = parameter_id(parameter.parameter.key());
+            let mut queue_index = 0;
+
+            let queue = (changes_vtbl.addParameterData)(changes, &id, &mut queue_index);
+
+            if !queue.is_null() {
+                let mut point_index = 0;
+
+                ((*(*queue).vtbl).addPoint)(queue, 0, f64::from(value), &mut point_index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr::null_mut;
+
+    use super::*;
+
+    #[repr(C)]
+    struct EventList {
+        vtbl: *const IEventListVtbl,
+        events: Vec<Event>,
+    }
+
+    unsafe extern "system" fn query_interface(
+        _this: *mut c_void,
+        _iid: *const TUID,
+        _obj: *mut *mut c_void,
+    ) -> tresult {
+        kNoInterface
+    }
+
+    unsafe extern "system" fn add_ref_or_release(_this: *mut c_void) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn get_event_count(this: *mut IEventList) -> i32 {
+        (*(this as *const EventList)).events.len() as i32
+    }
+
+    unsafe extern "system" fn get_event(
+        this: *mut IEventList,
+        index: i32,
+        event: *mut Event,
+    ) -> tresult {
+        let event_list = &*(this as *const EventList);
+
+        *event = event_list.events[index as usize];
+
+        kResultOk
+    }
+
+    unsafe extern "system" fn add_event(_this: *mut IEventList, _event: *mut Event) -> tresult {
+        kNotImplemented
+    }
+
+    static EVENT_LIST_VTBL: IEventListVtbl = IEventListVtbl {
+        unknown: FUnknownVtbl {
+            queryInterface: query_interface,
+            addRef: add_ref_or_release,
+            release: add_ref_or_release,
+        },
+        getEventCount: get_event_count,
+        getEvent: get_event,
+        addEvent: add_event,
+    };
+
+    #[test]
+    fn test_note_event_applied_at_sample_offset() {
+        unsafe {
+            let mut processor: *mut c_void = null_mut();
+
+            assert_eq!(
+                OctaSine::create_instance(&IAudioProcessor_iid, &mut processor),
+                kResultOk
+            );
+
+            let vtbl = &**(processor as *const *const IAudioProcessorVtbl);
+
+            let mut events = EventList {
+                vtbl: &EVENT_LIST_VTBL,
+                events: vec![Event {
+                    busIndex: 0,
+                    sampleOffset: 16,
+                    ppqPosition: 0.0,
+                    flags: 0,
+                    type_: kNoteOnEvent,
+                    data: EventData {
+                        noteOn: NoteOnEvent {
+                            channel: 0,
+                            pitch: 60,
+                            tuning: 0.0,
+                            velocity: 1.0,
+                            length: 0,
+                            pressure: 0.0,
+                            noteId: -1,
+                        },
+                    },
+                }],
+            };
+
+            let mut lefts = [0.0f32; 64];
+            let mut rights = [0.0f32; 64];
+            let mut channels = [
+                lefts.as_mut_ptr() as *mut c_void,
+                rights.as_mut_ptr() as *mut c_void,
+            ];
+            let mut outputs = AudioBusBuffers {
+                numChannels: 2,
+                silenceFlags: 0,
+                channelBuffers: channels.as_mut_ptr(),
+            };
+            let mut data = ProcessData {
+                processMode: 0,
+                symbolicSampleSize: kSample32,
+                numSamples: 64,
+                numInputs: 0,
+                numOutputs: 1,
+                inputs: null_mut(),
+                outputs: &mut outputs,
+                inputParameterChanges: null_mut(),
+                outputParameterChanges: null_mut(),
+                inputEvents: &mut events as *mut EventList as *mut IEventList,
+                outputEvents: null_mut(),
+                processContext: null_mut(),
+            };
+
+            assert_eq!((vtbl.process)(processor, &mut data), kResultOk);
+
+            assert!(lefts[..16].iter().all(|sample| *sample == 0.0));
+            assert!(lefts[16..].iter().any(|sample| *sample != 0.0));
+
+            assert_eq!((vtbl.unknown.release)(processor), 0);
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! IEditController and IMidiMapping implementation
+
+use std::ffi::c_void;
+
+use crate::parameters::PARAMETERS;
+
+use super::{
+    bindings::*, component, midi_parameter_controller, parameter_id, plugin_from_interface,
+    unknown_vtbl, EDIT_CONTROLLER_OFFSET, FIRST_MIDI_PARAMETER_ID, MIDI_MAPPING_OFFSET,
+    NUM_MIDI_PARAMETERS,
+};
+
+pub static EDIT_CONTROLLER_VTBL: IEditControllerVtbl = IEditControllerVtbl {
+    unknown: unknown_vtbl::<EDIT_CONTROLLER_OFFSET>(),
+    initialize: component::initialize,
+    terminate: component::terminate,
+    setComponentState: set_component_state,
+    setState: set_state,
+    getState: get_state,
+    getParameterCount: get_parameter_count,
+    getParameterInfo: get_parameter_info,
+    getParamStringByValue: get_param_string_by_value,
+    getParamValueByString: get_param_value_by_string,
+    normalizedParamToPlain: normalized_param_to_plain,
+    plainParamToNormalized: plain_param_to_normalized,
+    getParamNormalized: get_param_normalized,
+    setParamNormalized: set_param_normalized,
+    setComponentHandler: set_component_handler,
+    createView: create_view,
+};
+
+pub static MIDI_MAPPING_VTBL: IMidiMappingVtbl = IMidiMappingVtbl {
+    unknown: unknown_vtbl::<MIDI_MAPPING_OFFSET>(),
+    getMidiControllerAssignment: get_midi_controller_assignment,
+};
+
+/// Component and controller share state, so there is nothing to sync
+unsafe extern "system" fn set_component_state(
+    _this: *mut c_void,
+    _state: *mut IBStream,
+) -> tresult {
+    kResultOk
+}
+
+/// All state is stored by the component
+unsafe extern "system" fn set_state(_this: *mut c_void, _state: *mut IBStream) -> tresult {
+    kResultOk
+}
+
+/// All state is stored by the component
+unsafe extern "system" fn get_state(_this: *mut c_void, _state: *mut IBStream) -> tresult {
+    kResultOk
+}
+
+unsafe extern "system" fn get_parameter_count(_this: *mut c_void) -> i32 {
+    (PARAMETERS.len() + NUM_MIDI_PARAMETERS) as i32
+}
+
+unsafe extern "system" fn get_parameter_info(
+    this: *mut c_void,
+    param_index: i32,
+    info: *mut ParameterInfo,
+) -> tresult {
+    let plugin = plugin_from_interface::<EDIT_CONTROLLER_OFFSET>(this);
+
+    if (param_index < 0) | info.is_null() {
+        return kInvalidArgument;
+    }
+
+    let index = param_index as usize;
+    let info = &mut *info;
+
+    if let Some(parameter) = plugin.sync.patches.get_parameter_by_index(index) {
+        // Macro names include their targets
+        let name = plugin
+            .sync
+            .patches
+            .get_parameter_name(index)
+            .unwrap_or_else(|| parameter.name.clone());
+
+        info.id = parameter_id(parameter.parameter.key());
+        copy_to_string128(&mut info.title, &name);
+        copy_to_string128(&mut info.shortTitle, &name);
+        copy_to_string128(&mut info.units, "");
+        info.stepCount = 0;
+        info.defaultNormalizedValue = f64::from(parameter.default_value);
+        info.unitId = kRootUnitId;
+        info.flags = kCanAutomate;
+
+        kResultOk
+    } else if let Some(controller) = index
+        .checked_sub(PARAMETERS.len())
+        .filter(|i| *i < NUM_MIDI_PARAMETERS)
+    {
+        let controller = controller as i16;
+
+        let (name, default) = match controller {
+            kAfterTouch => ("MIDI channel pressure".to_string(), 0.0),
+            kPitchBend => ("MIDI pitch bend".to_string(), 0.5),
+            controller => (format!("MIDI CC {}", controller), 0.0),
+        };
+
+        info.id = FIRST_MIDI_PARAMETER_ID + controller as u32;
+        copy_to_string128(&mut info.title, &name);
+        copy_to_string128(&mut info.shortTitle, &name);
+        copy_to_string128(&mut info.units, "");
+        info.stepCount = 0;
+        info.defaultNormalizedValue = default;
+        info.unitId = kRootUnitId;
+        info.flags = kIsHidden;
+
+        kResultOk
+    } else {
+        kInvalidArgument
+    }
+}
+
+unsafe extern "system" fn get_param_string_by_value(
+    this: *mut c_void,
+    id: ParamID,
+    value_normalized: ParamValue,
+    string: *mut TChar,
+) -> tresult {
+    let plugin = plugin_from_interface::<EDIT_CONTROLLER_OFFSET>(this);
+
+    if string.is_null() {
+        return kInvalidArgument;
+    }
+
+    let text = if let Some(parameter) = plugin
+        .get_parameter_index(id)
+        .and_then(|index| plugin.sync.patches.get_parameter_by_index(index))
+    {
+        String::from((parameter.format)(value_normalized as f32))
+    } else if midi_parameter_controller(id).is_some() {
+        format!("{:.2}", value_normalized)
+    } else {
+        return kInvalidArgument;
+    };
+
+    copy_to_string128(&mut *(string as *mut String128), &text);
+
+    kResultOk
+}
+
+unsafe extern "system" fn get_param_value_by_string(
+    this: *mut c_void,
+    id: ParamID,
+    string: *const TChar,
+    value_normalized: *mut ParamValue,
+) -> tresult {
+    let plugin = plugin_from_interface::<EDIT_CONTROLLER_OFFSET>(this);
+
+    if string.is_null() | value_normalized.is_null() {
+        return kInvalidArgument;
+    }
+
+    let parameter = match plugin
+        .get_parameter_index(id)
+        .and_then(|index| plugin.sync.patches.get_parameter_by_index(index))
+    {
+        Some(parameter) => parameter,
+        None => return kInvalidArgument,
+    };
+
+    match (parameter.value_from_text)(&string_from_tchar_ptr(string)) {
+        Some(value) => {
+            *value_normalized = f64::from(value);
+
+            kResultOk
+        }
+        None => kResultFalse,
+    }
+}
+
+/// Parameters have no plain representation separate from normalized one
+unsafe extern "system" fn normalized_param_to_plain(
+    _this: *mut c_void,
+    _id: ParamID,
+    value_normalized: ParamValue,
+) -> ParamValue {
+    value_normalized
+}
+
+/// Parameters have no plain representation separate from normalized one
+unsafe extern "system" fn plain_param_to_normalized(
+    _this: *mut c_void,
+    _id: ParamID,
+    plain_value: ParamValue,
+) -> ParamValue {
+    plain_value
+}
+
+unsafe extern "system" fn get_param_normalized(this: *mut c_void, id: ParamID) -> ParamValue {
+    let plugin = plugin_from_interface::<EDIT_CONTROLLER_OFFSET>(this);
+
+    match plugin
+        .get_parameter_index(id)
+        .and_then(|index| plugin.sync.patches.get_parameter_value(index))
+    {
+        Some(value) => f64::from(value),
+        None => 0.0,
+    }
+}
+
+/// Update value shown in GUI. Audio is updated through process calls.
+unsafe extern "system" fn set_param_normalized(
+    this: *mut c_void,
+    id: ParamID,
+    value: ParamValue,
+) -> tresult {
+    let plugin = plugin_from_interface::<EDIT_CONTROLLER_OFFSET>(this);
+
+    if midi_parameter_controller(id).is_some() {
+        return kResultOk;
+    }
+
+    let index = match plugin.get_parameter_index(id) {
+        Some(index) => index,
+        None => return kInvalidArgument,
+    };
+
+    if let Some(parameter) = plugin.sync.patches.get_parameter_by_index(index) {
+        parameter.set_value(value as f32);
+
+        plugin
+            .sync
+            .patches
+            .parameter_change_info_gui
+            .mark_as_changed(index);
+    }
+
+    kResultOk
+}
+
+unsafe extern "system" fn set_component_handler(
+    this: *mut c_void,
+    handler: *mut IComponentHandler,
+) -> tresult {
+    let plugin = plugin_from_interface::<EDIT_CONTROLLER_OFFSET>(this);
+
+    if let Some(host) = &plugin.sync.host {
+        host.set_component_handler(handler);
+    }
+
+    kResultOk
+}
+
+#[cfg(feature = "gui")]
+unsafe extern "system" fn create_view(this: *mut c_void, name: FIDString) -> *mut c_void {
+    let plugin = plugin_from_interface::<EDIT_CONTROLLER_OFFSET>(this);
+
+    if !name.is_null() && std::ffi::CStr::from_ptr(name).to_bytes() == kEditor {
+        super::view::create_view(plugin.sync.clone())
+    } else {
+        std::ptr::null_mut()
+    }
+}
+
+#[cfg(not(feature = "gui"))]
+unsafe extern "system" fn create_view(_this: *mut c_void, _name: FIDString) -> *mut c_void {
+    std::ptr::null_mut()
+}
+
+/// Map MIDI controllers on any channel to hidden MIDI parameters
+unsafe extern "system" fn get_midi_controller_assignment(
+    _this: *mut c_void,
+    bus_index: i32,
+    _channel: i16,
+    midi_controller_number: i16,
+    id: *mut ParamID,
+) -> tresult {
+    if (bus_index == 0) & (0..kCountCtrlNumber).contains(&midi_controller_number) & !id.is_null() {
+        *id = FIRST_MIDI_PARAMETER_ID + midi_controller_number as u32;
+
+        kResultTrue
+    } else {
+        kResultFalse
+    }
+}
@@ -0,0 +1,145 @@
+use std::ffi::c_void;
+
+use crate::crate_version;
+
+use super::{bindings::*, OctaSine, CLASS_ID};
+
+#[repr(C)]
+struct Factory {
+    vtbl: *const IPluginFactory2Vtbl,
+}
+
+// SAFETY: vtable pointer only points to static data
+unsafe impl Sync for Factory {}
+
+static FACTORY: Factory = Factory {
+    vtbl: &FACTORY_VTBL,
+};
+
+static FACTORY_VTBL: IPluginFactory2Vtbl = IPluginFactory2Vtbl {
+    unknown: FUnknownVtbl {
+        queryInterface: query_interface,
+        addRef: add_ref,
+        release,
+    },
+    getFactoryInfo: get_factory_info,
+    countClasses: count_classes,
+    getClassInfo: get_class_info,
+    createInstance: create_instance,
+    getClassInfo2: get_class_info2,
+};
+
+/// Pointer returned by `GetPluginFactory` entry point
+pub fn get_plugin_factory() -> *mut c_void {
+    &FACTORY as *const Factory as *mut c_void
+}
+
+unsafe extern "system" fn query_interface(
+    this: *mut c_void,
+    iid: *const TUID,
+    obj: *mut *mut c_void,
+) -> tresult {
+    let iid = *iid;
+
+    if (iid == FUnknown_iid) | (iid == IPluginFactory_iid) | (iid == IPluginFactory2_iid) {
+        *obj = this;
+
+        kResultOk
+    } else {
+        *obj = std::ptr::null_mut();
+
+        kNoInterface
+    }
+}
+
+/// Factory is static, so reference counting is not needed
+unsafe extern "system" fn add_ref(_this: *mut c_void) -> u32 {
+    1
+}
+
+/// Factory is static, so reference counting is not needed
+unsafe extern "system" fn release(_this: *mut c_void) -> u32 {
+    1
+}
+
+unsafe extern "system" fn get_factory_info(_this: *mut c_void, info: *mut PFactoryInfo) -> tresult {
+    if info.is_null() {
+        return kInvalidArgument;
+    }
+
+    let info = &mut *info;
+
+    copy_to_c_char_arr(&mut info.vendor, b"Joakim Frostegard");
+    copy_to_c_char_arr(&mut info.url, b"https://octasine.com");
+    copy_to_c_char_arr(&mut info.email, b"");
+    info.flags = kUnicode;
+
+    kResultOk
+}
+
+unsafe extern "system" fn count_classes(_this: *mut c_void) -> i32 {
+    1
+}
+
+unsafe extern "system" fn get_class_info(
+    _this: *mut c_void,
+    index: i32,
+    info: *mut PClassInfo,
+) -> tresult {
+    if (index != 0) | info.is_null() {
+        return kInvalidArgument;
+    }
+
+    let info = &mut *info;
+
+    info.cid = CLASS_ID;
+    info.cardinality = kManyInstances;
+    copy_to_c_char_arr(&mut info.category, kVstAudioEffectClass);
+    copy_to_c_char_arr(&mut info.name, b"OctaSine");
+
+    kResultOk
+}
+
+unsafe extern "system" fn get_class_info2(
+    _this: *mut c_void,
+    index: i32,
+    info: *mut PClassInfo2,
+) -> tresult {
+    if (index != 0) | info.is_null() {
+        return kInvalidArgument;
+    }
+
+    let info = &mut *info;
+
+    info.cid = CLASS_ID;
+    info.cardinality = kManyInstances;
+    copy_to_c_char_arr(&mut info.category, kVstAudioEffectClass);
+    copy_to_c_char_arr(&mut info.name, b"OctaSine");
+    // Component and controller can't be run in separate processes
+    info.classFlags = 0;
+    copy_to_c_char_arr(&mut info.subCategories, b"Instrument|Synth");
+    copy_to_c_char_arr(&mut info.vendor, b"Joakim Frostegard");
+    copy_to_c_char_arr(&mut info.version, crate_version!().as_bytes());
+    copy_to_c_char_arr(&mut info.sdkVersion, b"VST 3.7.9");
+
+    kResultOk
+}
+
+unsafe extern "system" fn create_instance(
+    _this: *mut c_void,
+    cid: FIDString,
+    iid: FIDString,
+    obj: *mut *mut c_void,
+) -> tresult {
+    if cid.is_null() | iid.is_null() | obj.is_null() {
+        return kInvalidArgument;
+    }
+
+    if *(cid as *const TUID) != CLASS_ID {
+        *obj = std::ptr::null_mut();
+
+        return kNoInterface;
+    }
+
+    OctaSine::create_instance(iid as *const TUID, obj)
+}
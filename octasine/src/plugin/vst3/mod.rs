@@ -0,0 +1,314 @@
+//! VST3 plugin
+//!
+//! Implemented as a single component: one object is both audio processor
+//! and edit controller, sharing [AudioState] and [SyncState] like the other
+//! plugin formats. Parameter ids are derived from [ParameterKey]s.
+//!
+//! VST3 has no MIDI events. MIDI controller, channel pressure and pitch
+//! bend messages are instead received through hidden parameters, which are
+//! assigned to controller numbers through `IMidiMapping`.
+
+// Allow matching on SDK constant names
+#![allow(non_upper_case_globals)]
+
+pub mod bindings;
+mod component;
+mod controller;
+pub mod factory;
+mod sync;
+#[cfg(feature = "gui")]
+mod view;
+
+use std::{
+    ffi::c_void,
+    mem::offset_of,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    audio::AudioState,
+    osc::OscServer,
+    parameters::{ParameterKey, PARAMETERS},
+    sync::SyncState,
+    utils::init_logging,
+};
+
+use bindings::*;
+use sync::Vst3Host;
+
+/// Changing this makes hosts treat the plugin as a different one
+pub const CLASS_ID: TUID = uid(0x4F637461, 0x53696E65, 0x56535433, 0x00000001);
+
+/// Parameter id of first MIDI controller parameter. The following ones
+/// are for the remaining controller numbers, channel pressure and pitch
+/// bend, in `IMidiMapping` controller number order.
+const FIRST_MIDI_PARAMETER_ID: ParamID = 0x7FFF_0000;
+const NUM_MIDI_PARAMETERS: usize = kCountCtrlNumber as usize;
+
+/// VST3 parameter ids with the highest bit set are reserved for hosts
+pub fn parameter_id(key: ParameterKey) -> ParamID {
+    key.0 & 0x7FFF_FFFF
+}
+
+/// MIDI controller number, or `kAfterTouch` or `kPitchBend`, of parameter id
+fn midi_parameter_controller(id: ParamID) -> Option<i16> {
+    let controller = id.checked_sub(FIRST_MIDI_PARAMETER_ID)?;
+
+    if controller < NUM_MIDI_PARAMETERS as u32 {
+        Some(controller as i16)
+    } else {
+        None
+    }
+}
+
+/// Convert MIDI parameter value to MIDI message
+fn midi_parameter_message(controller: i16, value: ParamValue) -> [u8; 3] {
+    let value = value.clamp(0.0, 1.0);
+
+    match controller {
+        kPitchBend => {
+            let value = (value * 16383.0).round() as u16;
+
+            [0xE0, (value & 0x7F) as u8, (value >> 7) as u8]
+        }
+        kAfterTouch => [0xD0, (value * 127.0).round() as u8, 0],
+        controller => [0xB0, controller as u8, (value * 127.0).round() as u8],
+    }
+}
+
+#[repr(C)]
+pub struct OctaSine {
+    // Interface pointers handed to host point to these fields
+    component_vtbl: *const IComponentVtbl,
+    audio_processor_vtbl: *const IAudioProcessorVtbl,
+    edit_controller_vtbl: *const IEditControllerVtbl,
+    midi_mapping_vtbl: *const IMidiMappingVtbl,
+    ref_count: AtomicU32,
+    audio: Mutex<Box<AudioState>>,
+    sync: Arc<SyncState<Vst3Host>>,
+    /// Parameter ids with parameter indices, sorted by id
+    parameter_indices: Vec<(ParamID, usize)>,
+    _opt_osc_server: Option<OscServer>,
+}
+
+// SAFETY: vtable pointers only point to static data
+unsafe impl Send for OctaSine {}
+unsafe impl Sync for OctaSine {}
+
+impl OctaSine {
+    fn new() -> Self {
+        // If initialization of logging fails, we can't do much about it, but
+        // we shouldn't panic
+        let _ = init_logging("vst3");
+
+        let sync = Arc::new(SyncState::new(Some(Vst3Host::default())));
+
+        let mut parameter_indices: Vec<(ParamID, usize)> = PARAMETERS
+            .iter()
+            .enumerate()
+            .map(|(index, p)| (parameter_id(p.key()), index))
+            .collect();
+
+        parameter_indices.sort_unstable();
+
+        Self {
+            component_vtbl: &component::COMPONENT_VTBL,
+            audio_processor_vtbl: &component::AUDIO_PROCESSOR_VTBL,
+            edit_controller_vtbl: &controller::EDIT_CONTROLLER_VTBL,
+            midi_mapping_vtbl: &controller::MIDI_MAPPING_VTBL,
+            ref_count: AtomicU32::new(1),
+            audio: Default::default(),
+            _opt_osc_server: OscServer::start_if_enabled(sync.clone()),
+            sync,
+            parameter_indices,
+        }
+    }
+
+    /// Create instance and return requested interface pointer
+    ///
+    /// # Safety
+    ///
+    /// `iid` must point to a valid interface id and `obj` must be valid for
+    /// writes
+    pub unsafe fn create_instance(iid: *const TUID, obj: *mut *mut c_void) -> tresult {
+        let plugin = Box::into_raw(Box::new(Self::new()));
+
+        let result = Self::query_interface(plugin, iid, obj);
+
+        // Drop initial reference. Instance is freed if interface wasn't
+        // supported.
+        Self::release(plugin);
+
+        result
+    }
+
+    fn get_parameter_index(&self, id: ParamID) -> Option<usize> {
+        self.parameter_indices
+            .binary_search_by_key(&id, |(id, _)| *id)
+            .ok()
+            .map(|i| self.parameter_indices[i].1)
+    }
+
+    unsafe fn query_interface(
+        plugin: *const Self,
+        iid: *const TUID,
+        obj: *mut *mut c_void,
+    ) -> tresult {
+        let iid = *iid;
+
+        let interface: *const c_void =
+            if (iid == FUnknown_iid) | (iid == IPluginBase_iid) | (iid == IComponent_iid) {
+                &(*plugin).component_vtbl as *const _ as *const c_void
+            } else if iid == IAudioProcessor_iid {
+                &(*plugin).audio_processor_vtbl as *const _ as *const c_void
+            } else if iid == IEditController_iid {
+                &(*plugin).edit_controller_vtbl as *const _ as *const c_void
+            } else if iid == IMidiMapping_iid {
+                &(*plugin).midi_mapping_vtbl as *const _ as *const c_void
+            } else {
+                *obj = std::ptr::null_mut();
+
+                return kNoInterface;
+            };
+
+        Self::add_ref(plugin);
+
+        *obj = interface as *mut c_void;
+
+        kResultOk
+    }
+
+    unsafe fn add_ref(plugin: *const Self) -> u32 {
+        (*plugin).ref_count.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    unsafe fn release(plugin: *const Self) -> u32 {
+        let ref_count = (*plugin).ref_count.fetch_sub(1, Ordering::SeqCst) - 1;
+
+        if ref_count == 0 {
+            drop(Box::from_raw(plugin as *mut Self));
+        }
+
+        ref_count
+    }
+}
+
+const COMPONENT_OFFSET: usize = offset_of!(OctaSine, component_vtbl);
+const AUDIO_PROCESSOR_OFFSET: usize = offset_of!(OctaSine, audio_processor_vtbl);
+const EDIT_CONTROLLER_OFFSET: usize = offset_of!(OctaSine, edit_controller_vtbl);
+const MIDI_MAPPING_OFFSET: usize = offset_of!(OctaSine, midi_mapping_vtbl);
+
+/// Get plugin from pointer to interface at `OFFSET`
+///
+/// # Safety
+///
+/// `this` must be an interface pointer of a live plugin instance
+unsafe fn plugin_from_interface<'a, const OFFSET: usize>(this: *mut c_void) -> &'a OctaSine {
+    &*(this.byte_sub(OFFSET) as *const OctaSine)
+}
+
+unsafe extern "system" fn query_interface<const OFFSET: usize>(
+    this: *mut c_void,
+    iid: *const TUID,
+    obj: *mut *mut c_void,
+) -> tresult {
+    OctaSine::query_interface(this.byte_sub(OFFSET) as *const OctaSine, iid, obj)
+}
+
+unsafe extern "system" fn add_ref<const OFFSET: usize>(this: *mut c_void) -> u32 {
+    OctaSine::add_ref(this.byte_sub(OFFSET) as *const OctaSine)
+}
+
+unsafe extern "system" fn release<const OFFSET: usize>(this: *mut c_void) -> u32 {
+    OctaSine::release(this.byte_sub(OFFSET) as *const OctaSine)
+}
+
+const fn unknown_vtbl<const OFFSET: usize>() -> FUnknownVtbl {
+    FUnknownVtbl {
+        queryInterface: query_interface::<OFFSET>,
+        addRef: add_ref::<OFFSET>,
+        release: release::<OFFSET>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_parameter_ids() {
+        let ids: HashSet<ParamID> = PARAMETERS.iter().map(|p| parameter_id(p.key())).collect();
+
+        assert_eq!(ids.len(), PARAMETERS.len());
+        assert!(ids
+            .iter()
+            .all(|id| midi_parameter_controller(*id).is_none()));
+
+        assert_eq!(midi_parameter_controller(FIRST_MIDI_PARAMETER_ID), Some(0));
+        assert_eq!(
+            midi_parameter_controller(FIRST_MIDI_PARAMETER_ID + 129),
+            Some(kPitchBend)
+        );
+        assert_eq!(
+            midi_parameter_controller(FIRST_MIDI_PARAMETER_ID + 130),
+            None
+        );
+    }
+
+    #[test]
+    fn test_midi_parameter_message() {
+        assert_eq!(midi_parameter_message(1, 1.0), [0xB0, 1, 127]);
+        assert_eq!(midi_parameter_message(kAfterTouch, 0.0), [0xD0, 0, 0]);
+        assert_eq!(midi_parameter_message(kPitchBend, 0.5), [0xE0, 0, 64]);
+        assert_eq!(midi_parameter_message(kPitchBend, 1.0), [0xE0, 127, 127]);
+    }
+
+    #[test]
+    fn test_interface_pointers() {
+        unsafe {
+            let mut component: *mut c_void = std::ptr::null_mut();
+
+            assert_eq!(
+                OctaSine::create_instance(&IComponent_iid, &mut component),
+                kResultOk
+            );
+
+            let plugin = plugin_from_interface::<COMPONENT_OFFSET>(component);
+
+            assert_eq!(plugin.ref_count.load(Ordering::SeqCst), 1);
+
+            let mut controller: *mut c_void = std::ptr::null_mut();
+
+            let vtbl = &**(component as *const *const FUnknownVtbl);
+
+            assert_eq!(
+                (vtbl.queryInterface)(component, &IEditController_iid, &mut controller),
+                kResultOk
+            );
+            assert_eq!(
+                plugin_from_interface::<EDIT_CONTROLLER_OFFSET>(controller) as *const _,
+                plugin as *const _
+            );
+
+            let mut view: *mut c_void = std::ptr::null_mut();
+
+            assert_eq!(
+                (vtbl.queryInterface)(component, &IPlugView_iid, &mut view),
+                kNoInterface
+            );
+            assert!(view.is_null());
+
+            let controller_vtbl = &**(controller as *const *const FUnknownVtbl);
+
+            assert_eq!((controller_vtbl.release)(controller), 1);
+            assert_eq!((vtbl.release)(component), 0);
+        }
+    }
+}
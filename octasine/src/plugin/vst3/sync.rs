@@ -0,0 +1,352 @@
+use std::sync::atomic::{AtomicPtr, Ordering};
+#[cfg(feature = "gui")]
+use std::{path::PathBuf, sync::Arc};
+
+#[cfg(feature = "gui")]
+use compact_str::CompactString;
+
+use super::bindings::*;
+#[cfg(feature = "gui")]
+use super::parameter_id;
+#[cfg(feature = "gui")]
+use crate::{
+    parameters::{
+        chord_memory::ChordShape,
+        macros::MacroTarget,
+        midi_learn::{MidiLearnScope, MidiMappings},
+        velocity_curve::CustomVelocityCurve,
+        WrappedParameter,
+    },
+    settings::Settings,
+    sync::{change_info::MAX_NUM_PARAMETERS, snapshots::SnapshotSlot, GuiSyncHandle, SyncState},
+};
+
+/// Holds component handler set by host
+#[derive(Default)]
+pub struct Vst3Host {
+    component_handler: AtomicPtr<IComponentHandler>,
+}
+
+impl Vst3Host {
+    /// # Safety
+    ///
+    /// `handler` must be null or a valid component handler
+    pub unsafe fn set_component_handler(&self, handler: *mut IComponentHandler) {
+        if !handler.is_null() {
+            ((*(*handler).vtbl).unknown.addRef)(handler as *mut _);
+        }
+
+        let previous = self.component_handler.swap(handler, Ordering::SeqCst);
+
+        if !previous.is_null() {
+            ((*(*previous).vtbl).unknown.release)(previous as *mut _);
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl Vst3Host {
+    fn with_handler<F: FnOnce(*mut IComponentHandler, &IComponentHandlerVtbl)>(&self, f: F) {
+        let handler = self.component_handler.load(Ordering::SeqCst);
+
+        if !handler.is_null() {
+            unsafe { f(handler, &*(*handler).vtbl) }
+        }
+    }
+
+    fn begin_edit(&self, parameter: WrappedParameter) {
+        self.with_handler(|handler, vtbl| unsafe {
+            (vtbl.beginEdit)(handler, parameter_id(parameter.key()));
+        });
+    }
+
+    fn perform_edit(&self, parameter: WrappedParameter, value: f32) {
+        self.with_handler(|handler, vtbl| unsafe {
+            (vtbl.performEdit)(handler, parameter_id(parameter.key()), f64::from(value));
+        });
+    }
+
+    fn end_edit(&self, parameter: WrappedParameter) {
+        self.with_handler(|handler, vtbl| unsafe {
+            (vtbl.endEdit)(handler, parameter_id(parameter.key()));
+        });
+    }
+
+    /// Ask host to reread parameter values (`kParamValuesChanged`) and/or
+    /// names (`kParamTitlesChanged`)
+    fn restart_component(&self, flags: i32) {
+        self.with_handler(|handler, vtbl| unsafe {
+            (vtbl.restartComponent)(handler, flags);
+        });
+    }
+
+    /// Tell host that plugin state has changed and should be saved
+    fn set_dirty(&self) {
+        self.with_handler(|handler, vtbl| unsafe {
+            let mut handler2: *mut std::ffi::c_void = std::ptr::null_mut();
+
+            if (vtbl.unknown.queryInterface)(
+                handler as *mut _,
+                &IComponentHandler2_iid,
+                &mut handler2,
+            ) == kResultOk
+            {
+                let handler2 = handler2 as *mut IComponentHandler2;
+                let vtbl2 = &*(*handler2).vtbl;
+
+                (vtbl2.setDirty)(handler2, 1);
+                (vtbl2.unknown.release)(handler2 as *mut _);
+            }
+        });
+    }
+}
+
+impl Drop for Vst3Host {
+    fn drop(&mut self) {
+        unsafe { self.set_component_handler(std::ptr::null_mut()) }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl GuiSyncHandle for Arc<SyncState<Vst3Host>> {
+    fn begin_edit(&self, parameter: WrappedParameter) {
+        if let Some(host) = &self.host {
+            host.begin_edit(parameter);
+        }
+    }
+    fn end_edit(&self, parameter: WrappedParameter) {
+        if let Some(host) = &self.host {
+            host.end_edit(parameter);
+        }
+    }
+    fn set_parameter(&self, parameter: WrappedParameter, value: f32) {
+        if let Some(host) = &self.host {
+            host.perform_edit(parameter, value);
+        }
+
+        self.patches
+            .set_parameter_from_gui(parameter.index() as usize, value);
+    }
+    fn set_parameter_immediate(&self, parameter: WrappedParameter, value: f32) {
+        if let Some(host) = &self.host {
+            host.begin_edit(parameter);
+            host.perform_edit(parameter, value);
+            host.end_edit(parameter);
+        }
+
+        self.patches
+            .set_parameter_from_gui(parameter.index() as usize, value);
+    }
+    fn parse_parameter_from_text(&self, parameter: WrappedParameter, text: &str) -> Option<f32> {
+        let parser = self
+            .patches
+            .get_current_patch()
+            .parameters
+            .get(&parameter.key())?
+            .value_from_text;
+
+        parser(text)
+    }
+    fn get_parameter_text_choices(
+        &self,
+        parameter: WrappedParameter,
+    ) -> Option<Vec<CompactString>> {
+        self.patches
+            .get_current_patch()
+            .parameters
+            .get(&parameter.key())
+            .and_then(|p| p.text_choices.clone())
+    }
+    fn set_parameter_audio_only(&self, parameter: WrappedParameter, value: f32) {
+        self.patches
+            .set_parameter_from_gui(parameter.index() as usize, value);
+    }
+    fn get_parameter(&self, parameter: WrappedParameter) -> f32 {
+        self.patches
+            .get_parameter_value(parameter.index() as usize)
+            .unwrap() // FIXME: unwrap
+    }
+    fn format_parameter_value(&self, parameter: WrappedParameter, value: f32) -> CompactString {
+        self.patches
+            .format_parameter_value(parameter.index() as usize, value)
+            .unwrap() // FIXME: unwrap
+    }
+    fn get_patches(&self) -> (usize, Vec<CompactString>) {
+        let index = self.patches.get_patch_index();
+        let names = self.patches.get_patch_names();
+
+        (index, names)
+    }
+    fn set_patch_index(&self, index: usize) {
+        self.patches.set_patch_index(index);
+
+        if let Some(host) = &self.host {
+            host.restart_component(kParamValuesChanged);
+        }
+    }
+    fn get_current_patch_name(&self) -> CompactString {
+        self.patches.get_current_patch_name()
+    }
+    fn set_current_patch_name(&self, name: &str) {
+        self.patches.set_patch_name(name);
+
+        if let Some(host) = &self.host {
+            host.set_dirty();
+        }
+    }
+    fn get_changed_parameters(&self) -> Option<[Option<f32>; MAX_NUM_PARAMETERS]> {
+        self.patches.get_changed_parameters_from_gui()
+    }
+    fn have_patches_changed(&self) -> bool {
+        self.patches.have_patches_changed()
+    }
+    fn get_gui_settings(&self) -> crate::gui::GuiSettings {
+        Settings::load_or_default().gui
+    }
+    fn export_patch(&self) -> (CompactString, Vec<u8>) {
+        let name = self.patches.get_current_patch().get_fxp_filename();
+        let data = self.patches.get_current_patch().export_fxp_bytes();
+
+        (name, data)
+    }
+    fn export_bank(&self) -> Vec<u8> {
+        self.patches.export_fxb_bytes()
+    }
+    fn import_bank_or_patches_from_paths(&self, paths: &[PathBuf]) {
+        self.patches.import_bank_or_patches_from_paths(paths);
+
+        if let Some(host) = &self.host {
+            host.restart_component(kParamValuesChanged);
+        }
+    }
+    fn import_bank(&self, bytes: &[u8]) {
+        if let Err(err) = self.patches.import_bank_from_bytes(bytes) {
+            ::log::error!("failed importing patch bank: {:#}", err);
+        }
+
+        if let Some(host) = &self.host {
+            host.restart_component(kParamValuesChanged);
+        }
+    }
+    fn clear_patch(&self) {
+        self.patches.clear_current_patch();
+
+        if let Some(host) = &self.host {
+            host.restart_component(kParamValuesChanged);
+        }
+    }
+    fn clear_bank(&self) {
+        self.patches.clear_bank();
+
+        if let Some(host) = &self.host {
+            host.restart_component(kParamValuesChanged);
+        }
+    }
+    fn get_macro_targets(&self, macro_index: usize) -> Vec<MacroTarget> {
+        self.patches.get_macro_targets(macro_index)
+    }
+    fn set_macro_targets(&self, macro_index: usize, targets: Vec<MacroTarget>) {
+        self.patches.set_macro_targets(macro_index, targets);
+
+        if let Some(host) = &self.host {
+            host.restart_component(kParamTitlesChanged | kParamValuesChanged);
+            host.set_dirty();
+        }
+    }
+    fn get_custom_velocity_curve(&self) -> CustomVelocityCurve {
+        self.patches.get_custom_velocity_curve()
+    }
+    fn set_custom_velocity_curve(&self, curve: CustomVelocityCurve) {
+        self.patches.set_custom_velocity_curve(curve);
+
+        if let Some(host) = &self.host {
+            host.set_dirty();
+        }
+    }
+    fn get_chord_shape(&self) -> ChordShape {
+        self.patches.get_chord_shape()
+    }
+    fn set_chord_shape(&self, shape: ChordShape) {
+        self.patches.set_chord_shape(shape);
+
+        if let Some(host) = &self.host {
+            host.set_dirty();
+        }
+    }
+    fn request_chord_learn(&self) {
+        self.patches.request_chord_learn();
+
+        if let Some(host) = &self.host {
+            host.set_dirty();
+        }
+    }
+    fn get_midi_mapping(&self, parameter: WrappedParameter) -> Option<(u8, MidiLearnScope)> {
+        self.patches.get_midi_mapping(parameter.index() as usize)
+    }
+    fn request_midi_learn(&self, parameter: WrappedParameter, scope: MidiLearnScope) {
+        self.patches
+            .request_midi_learn(parameter.index() as usize, scope);
+    }
+    fn clear_midi_mapping(&self, parameter: WrappedParameter) {
+        self.patches.clear_midi_mapping(parameter.index() as usize);
+
+        if let Some(host) = &self.host {
+            host.set_dirty();
+        }
+    }
+    fn apply_midi_learn_result(&self) {
+        if self.patches.apply_midi_learn_result() {
+            if let Some(host) = &self.host {
+                host.set_dirty();
+            }
+        }
+    }
+    fn get_global_midi_mappings(&self) -> MidiMappings {
+        self.patches.get_global_midi_mappings()
+    }
+    fn have_global_midi_mappings_changed(&self) -> bool {
+        self.patches.have_global_midi_mappings_changed()
+    }
+    fn get_active_snapshot(&self) -> SnapshotSlot {
+        self.patches.get_active_snapshot()
+    }
+    fn switch_snapshot(&self) {
+        self.patches.switch_snapshot();
+
+        if let Some(host) = &self.host {
+            host.restart_component(kParamValuesChanged);
+            host.set_dirty();
+        }
+    }
+    fn copy_snapshot_a_to_b(&self) {
+        self.patches.copy_snapshot_a_to_b();
+
+        if let Some(host) = &self.host {
+            host.restart_component(kParamValuesChanged);
+            host.set_dirty();
+        }
+    }
+    fn is_parameter_locked(&self, parameter: WrappedParameter) -> bool {
+        self.patches.is_parameter_locked(parameter.index() as usize)
+    }
+    fn set_parameter_locked(&self, parameter: WrappedParameter, locked: bool) {
+        self.patches
+            .set_parameter_locked(parameter.index() as usize, locked);
+    }
+    fn randomize_patch(&self) {
+        self.patches.randomize_current_patch();
+
+        if let Some(host) = &self.host {
+            host.restart_component(kParamValuesChanged);
+            host.set_dirty();
+        }
+    }
+    fn mutate_patch(&self, amount: f32) {
+        self.patches.mutate_current_patch(amount);
+
+        if let Some(host) = &self.host {
+            host.restart_component(kParamValuesChanged);
+            host.set_dirty();
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! IPlugView implementation
+
+use std::{
+    ffi::{c_void, CStr},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use iced_baseview::window::WindowHandle;
+use parking_lot::Mutex;
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+
+use crate::{
+    gui::{get_iced_baseview_settings, Message, OctaSineIcedApplication, GUI_HEIGHT, GUI_WIDTH},
+    sync::SyncState,
+};
+
+use super::{bindings::*, sync::Vst3Host};
+
+#[repr(C)]
+struct View {
+    vtbl: *const IPlugViewVtbl,
+    ref_count: AtomicU32,
+    sync: Arc<SyncState<Vst3Host>>,
+    window_handle: Mutex<Option<WindowHandle<Message>>>,
+}
+
+static VIEW_VTBL: IPlugViewVtbl = IPlugViewVtbl {
+    unknown: FUnknownVtbl {
+        queryInterface: query_interface,
+        addRef: add_ref,
+        release,
+    },
+    isPlatformTypeSupported: is_platform_type_supported,
+    attached,
+    removed,
+    onWheel: on_wheel,
+    onKeyDown: on_key_down,
+    onKeyUp: on_key_up,
+    getSize: get_size,
+    onSize: on_size,
+    onFocus: on_focus,
+    setFrame: set_frame,
+    canResize: can_resize,
+    checkSizeConstraint: check_size_constraint,
+};
+
+/// Create view with a reference count of one
+pub fn create_view(sync: Arc<SyncState<Vst3Host>>) -> *mut c_void {
+    let view = View {
+        vtbl: &VIEW_VTBL,
+        ref_count: AtomicU32::new(1),
+        sync,
+        window_handle: Mutex::new(None),
+    };
+
+    Box::into_raw(Box::new(view)) as *mut c_void
+}
+
+unsafe extern "system" fn query_interface(
+    this: *mut c_void,
+    iid: *const TUID,
+    obj: *mut *mut c_void,
+) -> tresult {
+    let iid = *iid;
+
+    if (iid == FUnknown_iid) | (iid == IPlugView_iid) {
+        add_ref(this);
+
+        *obj = this;
+
+        kResultOk
+    } else {
+        *obj = std::ptr::null_mut();
+
+        kNoInterface
+    }
+}
+
+unsafe extern "system" fn add_ref(this: *mut c_void) -> u32 {
+    (*(this as *const View))
+        .ref_count
+        .fetch_add(1, Ordering::SeqCst)
+        + 1
+}
+
+unsafe extern "system" fn release(this: *mut c_void) -> u32 {
+    let ref_count = (*(this as *const View))
+        .ref_count
+        .fetch_sub(1, Ordering::SeqCst)
+        - 1;
+
+    if ref_count == 0 {
+        let view = Box::from_raw(this as *mut View);
+
+        if let Some(mut handle) = view.window_handle.lock().take() {
+            handle.close_window();
+        }
+    }
+
+    ref_count
+}
+
+#[cfg(target_os = "macos")]
+const PLATFORM_TYPE: &[u8] = kPlatformTypeNSView;
+#[cfg(target_os = "windows")]
+const PLATFORM_TYPE: &[u8] = kPlatformTypeHWND;
+#[cfg(target_os = "linux")]
+const PLATFORM_TYPE: &[u8] = kPlatformTypeX11EmbedWindowID;
+
+unsafe extern "system" fn is_platform_type_supported(
+    _this: *mut c_void,
+    type_: FIDString,
+) -> tresult {
+    if !type_.is_null() && CStr::from_ptr(type_).to_bytes() == PLATFORM_TYPE {
+        kResultTrue
+    } else {
+        kResultFalse
+    }
+}
+
+unsafe extern "system" fn attached(
+    this: *mut c_void,
+    parent: *mut c_void,
+    type_: FIDString,
+) -> tresult {
+    let view = &*(this as *const View);
+
+    if parent.is_null() || is_platform_type_supported(this, type_) != kResultTrue {
+        return kResultFalse;
+    }
+
+    let mut window_handle = view.window_handle.lock();
+
+    if window_handle.is_some() {
+        return kResultFalse;
+    }
+
+    *window_handle = Some(iced_baseview::open_parented::<
+        OctaSineIcedApplication<Arc<SyncState<Vst3Host>>>,
+        ParentWindow,
+    >(
+        &ParentWindow(parent),
+        get_iced_baseview_settings(view.sync.clone(), "OctaSine".to_string()),
+    ));
+
+    kResultOk
+}
+
+unsafe extern "system" fn removed(this: *mut c_void) -> tresult {
+    let view = &*(this as *const View);
+
+    if let Some(mut handle) = view.window_handle.lock().take() {
+        handle.close_window();
+    }
+
+    kResultOk
+}
+
+unsafe extern "system" fn on_wheel(_this: *mut c_void, _distance: f32) -> tresult {
+    kResultFalse
+}
+
+unsafe extern "system" fn on_key_down(
+    _this: *mut c_void,
+    _key: u16,
+    _key_code: i16,
+    _modifiers: i16,
+) -> tresult {
+    kResultFalse
+}
+
+unsafe extern "system" fn on_key_up(
+    _this: *mut c_void,
+    _key: u16,
+    _key_code: i16,
+    _modifiers: i16,
+) -> tresult {
+    kResultFalse
+}
+
+unsafe extern "system" fn get_size(_this: *mut c_void, size: *mut ViewRect) -> tresult {
+    if size.is_null() {
+        return kInvalidArgument;
+    }
+
+    *size = ViewRect {
+        left: 0,
+        top: 0,
+        right: GUI_WIDTH as i32,
+        bottom: GUI_HEIGHT as i32,
+    };
+
+    kResultOk
+}
+
+/// Window size is fixed
+unsafe extern "system" fn on_size(_this: *mut c_void, _new_size: *mut ViewRect) -> tresult {
+    kResultOk
+}
+
+unsafe extern "system" fn on_focus(_this: *mut c_void, _state: TBool) -> tresult {
+    kResultOk
+}
+
+unsafe extern "system" fn set_frame(_this: *mut c_void, _frame: *mut c_void) -> tresult {
+    kResultOk
+}
+
+unsafe extern "system" fn can_resize(_this: *mut c_void) -> tresult {
+    kResultFalse
+}
+
+unsafe extern "system" fn check_size_constraint(
+    _this: *mut c_void,
+    rect: *mut ViewRect,
+) -> tresult {
+    if rect.is_null() {
+        return kInvalidArgument;
+    }
+
+    let rect = &mut *rect;
+
+    rect.right = rect.left + GUI_WIDTH as i32;
+    rect.bottom = rect.top + GUI_HEIGHT as i32;
+
+    kResultTrue
+}
+
+struct ParentWindow(*mut c_void);
+
+unsafe impl HasRawWindowHandle for ParentWindow {
+    #[cfg(target_os = "macos")]
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        let mut handle = raw_window_handle::AppKitWindowHandle::empty();
+
+        handle.ns_view = self.0;
+
+        RawWindowHandle::AppKit(handle)
+    }
+
+    #[cfg(target_os = "windows")]
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        let mut handle = raw_window_handle::Win32WindowHandle::empty();
+
+        handle.hwnd = self.0;
+
+        RawWindowHandle::Win32(handle)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        let mut handle = raw_window_handle::XcbWindowHandle::empty();
+
+        handle.window = self.0 as u32;
+
+        RawWindowHandle::Xcb(handle)
+    }
+}
@@ -2,13 +2,18 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
-use crate::utils::get_file_storage_dir;
+use crate::{osc::OscSettings, parameters::midi_learn::MidiMappings, utils::get_file_storage_dir};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub schema_version: usize,
     #[cfg(feature = "gui")]
     pub gui: super::gui::GuiSettings,
+    /// MIDI CC mappings applying to all patches
+    #[serde(default)]
+    pub midi_mappings: MidiMappings,
+    #[serde(default)]
+    pub osc: OscSettings,
 }
 
 impl Default for Settings {
@@ -17,6 +22,8 @@ impl Default for Settings {
             schema_version: 1,
             #[cfg(feature = "gui")]
             gui: Default::default(),
+            midi_mappings: Default::default(),
+            osc: Default::default(),
         }
     }
 }
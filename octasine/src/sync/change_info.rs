@@ -6,7 +6,7 @@ use crate::{common::IndexMap, parameters::ParameterKey};
 
 use super::parameters::PatchParameter;
 
-const NUM_ATOMIC_U64S: usize = 2;
+const NUM_ATOMIC_U64S: usize = 3;
 pub const MAX_NUM_PARAMETERS: usize = NUM_ATOMIC_U64S * 64;
 
 /// Cache for marking parameters as changed and listing them.
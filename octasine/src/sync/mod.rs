@@ -3,11 +3,16 @@ pub mod change_info;
 mod parameters;
 mod patch_bank;
 mod serde;
+pub mod snapshots;
 
 use std::path::PathBuf;
 
 use compact_str::CompactString;
+pub use parameters::PatchParameter;
 pub use patch_bank::PatchBank;
+pub use serde::{get_patch_name_from_bytes, get_patch_names_from_bank_bytes};
+
+use crate::settings::Settings;
 
 /// Thread-safe state used for parameter and preset calls
 pub struct SyncState<H> {
@@ -19,17 +24,25 @@ pub struct SyncState<H> {
 
 impl<H> SyncState<H> {
     pub fn new(host: Option<H>) -> Self {
-        Self {
-            host,
-            patches: built_in_patch_bank(),
-        }
+        let patches = built_in_patch_bank();
+
+        patches.set_global_midi_mappings(Settings::load_or_default().midi_mappings);
+
+        Self { host, patches }
     }
 }
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "gui")] {
-        use crate::parameters::WrappedParameter;
+        use crate::parameters::{
+            chord_memory::ChordShape,
+            macros::MacroTarget,
+            midi_learn::{MidiLearnScope, MidiMappings},
+            velocity_curve::CustomVelocityCurve,
+            WrappedParameter,
+        };
         use self::change_info::MAX_NUM_PARAMETERS;
+        use self::snapshots::SnapshotSlot;
 
         /// Trait passed to GUI code for encapsulation
         pub trait GuiSyncHandle: Clone + Send + Sync + 'static {
@@ -54,8 +67,38 @@ cfg_if::cfg_if! {
             fn export_patch(&self) -> (CompactString, Vec<u8>);
             fn export_bank(&self) -> Vec<u8>;
             fn import_bank_or_patches_from_paths(&self, paths: &[PathBuf]);
+            /// Replace bank with bytes previously returned by export_bank
+            fn import_bank(&self, bytes: &[u8]);
             fn clear_patch(&self);
             fn clear_bank(&self);
+            fn get_macro_targets(&self, macro_index: usize) -> Vec<MacroTarget>;
+            fn set_macro_targets(&self, macro_index: usize, targets: Vec<MacroTarget>);
+            fn get_custom_velocity_curve(&self) -> CustomVelocityCurve;
+            fn set_custom_velocity_curve(&self, curve: CustomVelocityCurve);
+            fn get_chord_shape(&self) -> ChordShape;
+            fn set_chord_shape(&self, shape: ChordShape);
+            /// Learn chord shape from keys held on next audio processing call
+            fn request_chord_learn(&self);
+            /// Get controller mapped to parameter and scope of mapping
+            fn get_midi_mapping(&self, parameter: WrappedParameter) -> Option<(u8, MidiLearnScope)>;
+            /// Map parameter to controller of next incoming MIDI CC message
+            fn request_midi_learn(&self, parameter: WrappedParameter, scope: MidiLearnScope);
+            fn clear_midi_mapping(&self, parameter: WrappedParameter);
+            /// Store mapping learned by audio thread, if any
+            fn apply_midi_learn_result(&self);
+            fn get_global_midi_mappings(&self) -> MidiMappings;
+            /// Whether global mappings changed since last call and should be saved
+            fn have_global_midi_mappings_changed(&self) -> bool;
+            fn get_active_snapshot(&self) -> SnapshotSlot;
+            /// Store current parameter values in active A/B slot and apply the other one
+            fn switch_snapshot(&self);
+            fn copy_snapshot_a_to_b(&self);
+            fn is_parameter_locked(&self, parameter: WrappedParameter) -> bool;
+            /// Lock parameter so that randomize and mutate actions leave it alone
+            fn set_parameter_locked(&self, parameter: WrappedParameter, locked: bool);
+            fn randomize_patch(&self);
+            /// Move unlocked parameters towards random values by amount (0.0 to 1.0)
+            fn mutate_patch(&self, amount: f32);
         }
     }
 }
@@ -63,3 +106,12 @@ cfg_if::cfg_if! {
 fn built_in_patch_bank() -> PatchBank {
     PatchBank::default()
 }
+
+/// Names of patches in built-in bank, ordered by index
+pub fn get_built_in_patch_names() -> Vec<String> {
+    built_in_patch_bank()
+        .patches
+        .iter()
+        .map(|patch| patch.get_name())
+        .collect()
+}
@@ -3,10 +3,17 @@ use compact_str::CompactString;
 use crate::{
     common::IndexMap,
     parameters::{
-        glide_active::GlideActiveValue, glide_bpm_sync::GlideBpmSyncValue,
-        glide_mode::GlideModeValue, glide_retrigger::GlideRetriggerValue,
-        glide_time::GlideTimeValue, velocity_sensitivity::VelocitySensitivityValue,
-        voice_mode::VoiceModeValue, *,
+        arpeggiator_gate::ArpeggiatorGateValue, arpeggiator_latch::ArpeggiatorLatchValue,
+        arpeggiator_mode::ArpeggiatorModeValue, arpeggiator_octaves::ArpeggiatorOctavesValue,
+        arpeggiator_rate::ArpeggiatorRateValue, arpeggiator_swing::ArpeggiatorSwingValue,
+        chord_memory::ChordMemoryValue, glide_active::GlideActiveValue,
+        glide_bpm_sync::GlideBpmSyncValue, glide_mode::GlideModeValue,
+        glide_retrigger::GlideRetriggerValue, glide_time::GlideTimeValue,
+        max_polyphony::MaxPolyphonyValue, note_priority::NotePriorityValue,
+        overlapping_voices::OverlappingVoicesValue, sidechain_mod_amount::SidechainModAmountValue,
+        sidechain_mod_target::SidechainModTargetValue, velocity_curve::VelocityCurveValue,
+        velocity_sensitivity::VelocitySensitivityValue, voice_mode::VoiceModeValue,
+        voice_stealing::VoiceStealingValue, *,
     },
 };
 
@@ -60,6 +67,28 @@ impl PatchParameter {
                 MasterParameter::GlideBpmSync => Self::new::<GlideBpmSyncValue>(parameter),
                 MasterParameter::GlideMode => Self::new::<GlideModeValue>(parameter),
                 MasterParameter::GlideRetrigger => Self::new::<GlideRetriggerValue>(parameter),
+                MasterParameter::VelocityCurve => Self::new::<VelocityCurveValue>(parameter),
+                MasterParameter::MaxPolyphony => Self::new::<MaxPolyphonyValue>(parameter),
+                MasterParameter::VoiceStealing => Self::new::<VoiceStealingValue>(parameter),
+                MasterParameter::NotePriority => Self::new::<NotePriorityValue>(parameter),
+                MasterParameter::OverlappingVoices => {
+                    Self::new::<OverlappingVoicesValue>(parameter)
+                }
+                MasterParameter::ArpeggiatorMode => Self::new::<ArpeggiatorModeValue>(parameter),
+                MasterParameter::ArpeggiatorOctaves => {
+                    Self::new::<ArpeggiatorOctavesValue>(parameter)
+                }
+                MasterParameter::ArpeggiatorRate => Self::new::<ArpeggiatorRateValue>(parameter),
+                MasterParameter::ArpeggiatorSwing => Self::new::<ArpeggiatorSwingValue>(parameter),
+                MasterParameter::ArpeggiatorGate => Self::new::<ArpeggiatorGateValue>(parameter),
+                MasterParameter::ArpeggiatorLatch => Self::new::<ArpeggiatorLatchValue>(parameter),
+                MasterParameter::ChordMemory => Self::new::<ChordMemoryValue>(parameter),
+                MasterParameter::SidechainModTarget => {
+                    Self::new::<SidechainModTargetValue>(parameter)
+                }
+                MasterParameter::SidechainModAmount => {
+                    Self::new::<SidechainModAmountValue>(parameter)
+                }
             },
             Parameter::Operator(index, operator_parameter) => {
                 use OperatorParameter::*;
@@ -117,6 +146,12 @@ impl PatchParameter {
                     KeySync => Self::new::<LfoKeySyncValue>(parameter),
                 }
             }
+            Parameter::ModMatrix(_, mod_matrix_parameter) => match mod_matrix_parameter {
+                ModMatrixParameter::Source => Self::new::<ModMatrixSourceValue>(parameter),
+                ModMatrixParameter::Target => Self::new::<ModMatrixTargetValue>(parameter),
+                ModMatrixParameter::Amount => Self::new::<ModMatrixAmountValue>(parameter),
+            },
+            Parameter::Macro(_) => Self::new::<MacroValue>(parameter),
         }
     }
 
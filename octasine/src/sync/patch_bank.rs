@@ -11,16 +11,35 @@ use std::{
 use arc_swap::ArcSwap;
 use array_init::array_init;
 use compact_str::{format_compact, CompactString};
-
-use crate::{common::IndexMap, parameters::ParameterKey};
+use fastrand::Rng;
+
+use crate::{
+    common::IndexMap,
+    parameters::{
+        chord_memory::ChordShape,
+        macros::{macro_name_with_targets, MacroMappings, MacroTarget},
+        midi_learn::{is_learnable_controller, MidiLearnScope, MidiMappings},
+        randomize::{mutate_value, randomize_value},
+        velocity_curve::CustomVelocityCurve,
+        Parameter, ParameterKey,
+    },
+};
 
 use super::change_info::{ParameterChangeInfo, MAX_NUM_PARAMETERS};
 use super::parameters::PatchParameter;
 use super::serde::*;
+use super::snapshots::{PatchSnapshots, SnapshotSlot};
 
 pub struct Patch {
     name: ArcSwap<String>,
     pub parameters: IndexMap<ParameterKey, PatchParameter>,
+    macro_mappings: ArcSwap<MacroMappings>,
+    custom_velocity_curve: ArcSwap<CustomVelocityCurve>,
+    chord_shape: ArcSwap<ChordShape>,
+    midi_mappings: ArcSwap<MidiMappings>,
+    snapshots: ArcSwap<PatchSnapshots>,
+    /// Parameters left untouched by randomize and mutate actions
+    randomizer_locks: ArcSwap<Vec<ParameterKey>>,
 }
 
 impl Default for Patch {
@@ -34,6 +53,12 @@ impl Patch {
         Self {
             name: ArcSwap::new(Arc::new(Self::process_name(name))),
             parameters,
+            macro_mappings: Default::default(),
+            custom_velocity_curve: Default::default(),
+            chord_shape: Default::default(),
+            midi_mappings: Default::default(),
+            snapshots: Default::default(),
+            randomizer_locks: Default::default(),
         }
     }
 
@@ -62,12 +87,73 @@ impl Patch {
             .collect()
     }
 
+    pub fn get_macro_mappings(&self) -> Arc<MacroMappings> {
+        self.macro_mappings.load_full()
+    }
+
+    pub fn set_macro_mappings(&self, mappings: MacroMappings) {
+        self.macro_mappings.store(Arc::new(mappings));
+    }
+
+    pub fn get_custom_velocity_curve(&self) -> Arc<CustomVelocityCurve> {
+        self.custom_velocity_curve.load_full()
+    }
+
+    pub fn set_custom_velocity_curve(&self, curve: CustomVelocityCurve) {
+        self.custom_velocity_curve.store(Arc::new(curve));
+    }
+
+    pub fn get_chord_shape(&self) -> Arc<ChordShape> {
+        self.chord_shape.load_full()
+    }
+
+    pub fn set_chord_shape(&self, shape: ChordShape) {
+        self.chord_shape.store(Arc::new(shape));
+    }
+
+    pub fn get_midi_mappings(&self) -> Arc<MidiMappings> {
+        self.midi_mappings.load_full()
+    }
+
+    pub fn set_midi_mappings(&self, mappings: MidiMappings) {
+        self.midi_mappings.store(Arc::new(mappings));
+    }
+
+    pub fn get_snapshots(&self) -> Arc<PatchSnapshots> {
+        self.snapshots.load_full()
+    }
+
+    pub fn set_snapshots(&self, snapshots: PatchSnapshots) {
+        self.snapshots.store(Arc::new(snapshots));
+    }
+
+    pub fn get_randomizer_locks(&self) -> Arc<Vec<ParameterKey>> {
+        self.randomizer_locks.load_full()
+    }
+
+    pub fn set_randomizer_locks(&self, locks: Vec<ParameterKey>) {
+        self.randomizer_locks.store(Arc::new(locks));
+    }
+
+    fn get_parameter_values(&self) -> IndexMap<ParameterKey, f32> {
+        self.parameters
+            .iter()
+            .map(|(key, parameter)| (*key, parameter.get_value()))
+            .collect()
+    }
+
     fn update_from_bytes(&self, bytes: &[u8]) -> anyhow::Result<()> {
         update_patch_from_bytes(self, bytes)
     }
 
     fn set_from_patch_parameters(&self, parameters: &IndexMap<ParameterKey, PatchParameter>) {
         self.set_name("-");
+        self.set_macro_mappings(Default::default());
+        self.set_custom_velocity_curve(Default::default());
+        self.set_chord_shape(Default::default());
+        self.set_midi_mappings(Default::default());
+        self.set_snapshots(Default::default());
+        self.set_randomizer_locks(Default::default());
 
         for (parameter, default_value) in self
             .parameters
@@ -79,13 +165,36 @@ impl Patch {
     }
 }
 
+/// Value of `PatchBank::midi_learn_request` when no learning is requested.
+/// Otherwise, it holds parameter index times two, plus one for global scope.
+const NO_MIDI_LEARN_REQUEST: usize = usize::MAX;
+
+/// Value of `PatchBank::midi_learn_result` when no learned mapping is
+/// pending. Otherwise, it holds learn request times 128, plus controller.
+const NO_MIDI_LEARN_RESULT: usize = usize::MAX;
+
 pub struct PatchBank {
     pub patches: [Patch; 128],
     patch_index: AtomicUsize,
     parameter_change_info_audio: ParameterChangeInfo,
     pub parameter_change_info_gui: ParameterChangeInfo,
+    /// Parameters changed by mapped MIDI controllers or OSC messages, which
+    /// host should be notified about
+    parameter_change_info_midi_and_osc: ParameterChangeInfo,
     patches_changed: AtomicBool,
+    /// Patch index was changed by MIDI program change or OSC message, so
+    /// host should be notified
+    patch_index_changed_from_audio: AtomicBool,
     envelope_viewports_changed: AtomicBool,
+    custom_velocity_curve_changed: AtomicBool,
+    chord_shape_changed: AtomicBool,
+    chord_learn_requested: AtomicBool,
+    /// MIDI CC mappings applying to all patches
+    global_midi_mappings: ArcSwap<MidiMappings>,
+    global_midi_mappings_changed: AtomicBool,
+    midi_learn_request: AtomicUsize,
+    /// Mapping learned by audio, to be stored from GUI
+    midi_learn_result: AtomicUsize,
 }
 
 impl Default for PatchBank {
@@ -101,8 +210,17 @@ impl PatchBank {
             patch_index: AtomicUsize::new(0),
             parameter_change_info_audio: ParameterChangeInfo::default(),
             parameter_change_info_gui: ParameterChangeInfo::default(),
+            parameter_change_info_midi_and_osc: ParameterChangeInfo::default(),
             patches_changed: AtomicBool::new(false),
+            patch_index_changed_from_audio: AtomicBool::new(false),
             envelope_viewports_changed: AtomicBool::new(false),
+            custom_velocity_curve_changed: AtomicBool::new(false),
+            chord_shape_changed: AtomicBool::new(false),
+            chord_learn_requested: AtomicBool::new(false),
+            global_midi_mappings: Default::default(),
+            global_midi_mappings_changed: AtomicBool::new(false),
+            midi_learn_request: AtomicUsize::new(NO_MIDI_LEARN_REQUEST),
+            midi_learn_result: AtomicUsize::new(NO_MIDI_LEARN_RESULT),
         }
     }
 
@@ -136,6 +254,9 @@ impl PatchBank {
     fn mark_parameters_as_changed(&self) {
         self.parameter_change_info_audio.mark_all_as_changed();
         self.parameter_change_info_gui.mark_all_as_changed();
+        self.custom_velocity_curve_changed
+            .store(true, Ordering::SeqCst);
+        self.chord_shape_changed.store(true, Ordering::SeqCst);
     }
 
     // Number of patches / parameters
@@ -167,6 +288,30 @@ impl PatchBank {
             .store(true, Ordering::SeqCst);
     }
 
+    /// Select patch on MIDI program change. Doesn't allocate. Out of range
+    /// indices are ignored.
+    pub fn set_patch_index_from_audio(&self, index: usize) {
+        if index < self.patches.len() && index != self.get_patch_index() {
+            self.set_patch_index(index);
+
+            self.patch_index_changed_from_audio
+                .store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Select patch on OSC message. Host is notified like on MIDI program
+    /// change.
+    pub fn set_patch_index_from_osc(&self, index: usize) {
+        self.set_patch_index_from_audio(index);
+    }
+
+    /// Whether patch was changed by MIDI program change or OSC message since
+    /// last call
+    pub fn has_patch_index_changed_from_audio(&self) -> bool {
+        self.patch_index_changed_from_audio
+            .fetch_and(false, Ordering::SeqCst)
+    }
+
     pub fn get_patch_name(&self, index: usize) -> Option<CompactString> {
         self.patches
             .get(index)
@@ -207,6 +352,40 @@ impl PatchBank {
         self.parameter_change_info_gui
             .get_changed_parameters(&self.get_current_patch().parameters)
     }
+
+    /// Parameters changed by mapped MIDI controllers or OSC messages since
+    /// last call
+    pub fn get_changed_parameters_from_midi_and_osc(
+        &self,
+    ) -> Option<[Option<f32>; MAX_NUM_PARAMETERS]> {
+        self.parameter_change_info_midi_and_osc
+            .get_changed_parameters(&self.get_current_patch().parameters)
+    }
+
+    /// Only used from audio
+    pub fn get_changed_custom_velocity_curve(&self) -> Option<CustomVelocityCurve> {
+        if self
+            .custom_velocity_curve_changed
+            .fetch_and(false, Ordering::SeqCst)
+        {
+            Some(CustomVelocityCurve::clone(
+                &self.get_current_patch().custom_velocity_curve.load(),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Only used from audio
+    pub fn get_changed_chord_shape(&self) -> Option<ChordShape> {
+        if self.chord_shape_changed.fetch_and(false, Ordering::SeqCst) {
+            Some(ChordShape::clone(
+                &self.get_current_patch().chord_shape.load(),
+            ))
+        } else {
+            None
+        }
+    }
 }
 
 // Get parameter values
@@ -225,11 +404,21 @@ impl PatchBank {
             .map(|(_, p)| (p.get_value_text()))
     }
 
+    /// Get parameter name. Macro names include their targets.
     pub fn get_parameter_name(&self, index: usize) -> Option<CompactString> {
-        self.get_current_patch()
-            .parameters
-            .get_index(index)
-            .map(|(_, p)| p.name.clone())
+        let patch = self.get_current_patch();
+        let (_, p) = patch.parameters.get_index(index)?;
+
+        if let Parameter::Macro(macro_index) = p.parameter.parameter() {
+            let mappings = patch.macro_mappings.load();
+
+            Some(macro_name_with_targets(
+                macro_index,
+                &mappings.0[macro_index as usize],
+            ))
+        } else {
+            Some(p.name.clone())
+        }
     }
 
     pub fn format_parameter_value(&self, index: usize, value: f32) -> Option<CompactString> {
@@ -249,6 +438,7 @@ impl PatchBank {
             parameter.set_value(value.min(1.0).max(0.0));
 
             self.parameter_change_info_audio.mark_as_changed(index);
+            self.apply_macro(parameter);
         }
     }
 
@@ -260,6 +450,7 @@ impl PatchBank {
 
             self.parameter_change_info_audio.mark_as_changed(index);
             self.parameter_change_info_gui.mark_as_changed(index);
+            self.apply_macro(parameter);
         }
     }
 
@@ -270,6 +461,7 @@ impl PatchBank {
             if parameter.set_from_text(value) {
                 self.parameter_change_info_audio.mark_as_changed(index);
                 self.parameter_change_info_gui.mark_as_changed(index);
+                self.apply_macro(parameter);
 
                 return true;
             }
@@ -278,12 +470,36 @@ impl PatchBank {
         false
     }
 
+    /// Set parameter from OSC message. Host is notified of the change from
+    /// the next processing call, like of changes caused by mapped MIDI
+    /// controllers.
+    pub fn set_parameter_from_osc(&self, index: usize, value: f32) {
+        if index < self.num_parameters() {
+            self.set_parameter_from_host(index, value);
+            self.parameter_change_info_midi_and_osc
+                .mark_as_changed(index);
+        }
+    }
+
+    /// Like `set_parameter_from_osc`, but parse value from text
+    pub fn set_parameter_text_from_osc(&self, index: usize, value: &str) -> bool {
+        let changed = self.set_parameter_text_from_host(index, value);
+
+        if changed {
+            self.parameter_change_info_midi_and_osc
+                .mark_as_changed(index);
+        }
+
+        changed
+    }
+
     pub fn set_parameter_text_from_gui(&self, index: usize, value: &str) -> bool {
         let opt_parameter = self.get_parameter_by_index(index);
 
         if let Some(parameter) = opt_parameter {
             if parameter.set_from_text(value) {
                 self.parameter_change_info_audio.mark_as_changed(index);
+                self.apply_macro(parameter);
 
                 return true;
             }
@@ -291,6 +507,422 @@ impl PatchBank {
 
         false
     }
+
+    /// If parameter is a macro, set values of its targets.
+    ///
+    /// Target changes are not reported to the host as automation.
+    fn apply_macro(&self, parameter: &PatchParameter) {
+        self.set_macro_target_values(parameter, |target_index, _, _| {
+            self.parameter_change_info_audio
+                .mark_as_changed(target_index);
+        });
+    }
+
+    /// If parameter is a macro, set values of its targets and pass target
+    /// indices, parameters and values to `f`, e.g., for applying them to
+    /// audio at a specific sample offset.
+    ///
+    /// Target values are derived from the macro value. They are not
+    /// reported to the host as automation, since the host would then play
+    /// back target automation fighting the macro automation it derives from.
+    pub fn set_macro_target_values(
+        &self,
+        parameter: &PatchParameter,
+        mut f: impl FnMut(usize, &PatchParameter, f32),
+    ) {
+        let macro_index = if let Parameter::Macro(index) = parameter.parameter.parameter() {
+            index as usize
+        } else {
+            return;
+        };
+
+        let patch = self.get_current_patch();
+        let macro_value = parameter.get_value();
+        let mappings = patch.macro_mappings.load();
+
+        for target in mappings.0[macro_index].iter() {
+            if let Some((target_index, _, target_parameter)) =
+                patch.parameters.get_full(&target.key)
+            {
+                let value = target.get_target_value(macro_value);
+
+                target_parameter.set_value(value);
+
+                self.parameter_change_info_gui.mark_as_changed(target_index);
+
+                f(target_index, target_parameter, value);
+            }
+        }
+    }
+}
+
+// Velocity curve
+impl PatchBank {
+    pub fn get_custom_velocity_curve(&self) -> CustomVelocityCurve {
+        CustomVelocityCurve::clone(&self.get_current_patch().custom_velocity_curve.load())
+    }
+
+    pub fn set_custom_velocity_curve(&self, curve: CustomVelocityCurve) {
+        self.get_current_patch().set_custom_velocity_curve(curve);
+
+        self.custom_velocity_curve_changed
+            .store(true, Ordering::SeqCst);
+        self.patches_changed.store(true, Ordering::SeqCst);
+    }
+}
+
+// Chord memory
+impl PatchBank {
+    pub fn get_chord_shape(&self) -> ChordShape {
+        ChordShape::clone(&self.get_current_patch().chord_shape.load())
+    }
+
+    pub fn set_chord_shape(&self, shape: ChordShape) {
+        self.get_current_patch().set_chord_shape(shape);
+
+        self.chord_shape_changed.store(true, Ordering::SeqCst);
+        self.patches_changed.store(true, Ordering::SeqCst);
+    }
+
+    /// Store shape learned by audio thread. Allocates, but only happens
+    /// when user has requested learning.
+    pub fn set_chord_shape_from_audio(&self, shape: ChordShape) {
+        self.get_current_patch().set_chord_shape(shape);
+
+        self.patches_changed.store(true, Ordering::SeqCst);
+    }
+
+    /// Ask audio thread to learn chord shape from held keys
+    pub fn request_chord_learn(&self) {
+        self.chord_learn_requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Only used from audio
+    pub fn is_chord_learn_requested(&self) -> bool {
+        self.chord_learn_requested
+            .fetch_and(false, Ordering::SeqCst)
+    }
+}
+
+// MIDI learn
+impl PatchBank {
+    /// Get controller mapped to parameter and scope of mapping
+    pub fn get_midi_mapping(&self, index: usize) -> Option<(u8, MidiLearnScope)> {
+        let (key, _) = self.get_current_patch().parameters.get_index(index)?;
+
+        if let Some(controller) = self
+            .get_current_patch()
+            .midi_mappings
+            .load()
+            .get_controller(*key)
+        {
+            Some((controller, MidiLearnScope::Patch))
+        } else {
+            self.global_midi_mappings
+                .load()
+                .get_controller(*key)
+                .map(|controller| (controller, MidiLearnScope::Global))
+        }
+    }
+
+    /// Map parameter to controller of next incoming MIDI CC message
+    pub fn request_midi_learn(&self, index: usize, scope: MidiLearnScope) {
+        let request = index * 2 + usize::from(scope == MidiLearnScope::Global);
+
+        self.midi_learn_request.store(request, Ordering::SeqCst);
+    }
+
+    /// Remove parameter MIDI CC mapping from both patch and global mappings
+    pub fn clear_midi_mapping(&self, index: usize) {
+        if let Some((key, _)) = self.get_current_patch().parameters.get_index(index) {
+            self.set_midi_mapping(*key, None);
+        }
+    }
+
+    pub fn get_global_midi_mappings(&self) -> MidiMappings {
+        MidiMappings::clone(&self.global_midi_mappings.load())
+    }
+
+    /// Set global mappings, e.g., when loading settings
+    pub fn set_global_midi_mappings(&self, mappings: MidiMappings) {
+        self.global_midi_mappings.store(Arc::new(mappings));
+    }
+
+    /// Only used from GUI, to know when to save settings
+    pub fn have_global_midi_mappings_changed(&self) -> bool {
+        self.global_midi_mappings_changed
+            .fetch_and(false, Ordering::SeqCst)
+    }
+
+    /// Handle incoming MIDI CC message. Completes pending MIDI learn request
+    /// or sets values of mapped parameters and marks them for notifying
+    /// host.
+    ///
+    /// Only used from audio. Doesn't allocate: learned mappings are stored
+    /// by `apply_midi_learn_result`.
+    pub fn set_parameters_from_midi_controller(&self, controller: u8, value: u8) {
+        self.set_parameters_from_midi_controller_with(controller, value, |index, _, _| {
+            self.parameter_change_info_audio.mark_as_changed(index);
+        });
+    }
+
+    /// Like `set_parameters_from_midi_controller`, but instead of marking
+    /// changed parameters (including macro targets) for audio, pass their
+    /// indices, parameters and values to `f`, e.g., for applying them at
+    /// the sample offset of the MIDI event.
+    pub fn set_parameters_from_midi_controller_with(
+        &self,
+        controller: u8,
+        value: u8,
+        mut f: impl FnMut(usize, &PatchParameter, f32),
+    ) {
+        if is_learnable_controller(controller) {
+            let request = self
+                .midi_learn_request
+                .swap(NO_MIDI_LEARN_REQUEST, Ordering::SeqCst);
+
+            if request != NO_MIDI_LEARN_REQUEST {
+                self.midi_learn_result
+                    .store(request * 128 + usize::from(controller), Ordering::SeqCst);
+
+                return;
+            }
+        }
+
+        let patch = self.get_current_patch();
+        let value = f32::from(value) / 127.0;
+
+        let patch_mappings = patch.midi_mappings.load();
+        let global_mappings = self.global_midi_mappings.load();
+
+        for key in patch_mappings
+            .iter_targets(controller)
+            .chain(global_mappings.iter_targets(controller))
+        {
+            if let Some((index, _, parameter)) = patch.parameters.get_full(&key) {
+                parameter.set_value(value);
+
+                self.parameter_change_info_gui.mark_as_changed(index);
+                self.parameter_change_info_midi_and_osc
+                    .mark_as_changed(index);
+
+                f(index, parameter, value);
+
+                self.set_macro_target_values(parameter, &mut f);
+            }
+        }
+    }
+
+    /// Store mapping learned by audio, if any. Returns true if a mapping
+    /// was stored.
+    ///
+    /// Only used from GUI / main thread
+    pub fn apply_midi_learn_result(&self) -> bool {
+        let result = self
+            .midi_learn_result
+            .swap(NO_MIDI_LEARN_RESULT, Ordering::SeqCst);
+
+        if result == NO_MIDI_LEARN_RESULT {
+            return false;
+        }
+
+        let request = result / 128;
+        let controller = (result % 128) as u8;
+
+        let scope = if request % 2 == 1 {
+            MidiLearnScope::Global
+        } else {
+            MidiLearnScope::Patch
+        };
+
+        if let Some((key, _)) = self.get_current_patch().parameters.get_index(request / 2) {
+            self.set_midi_mapping(*key, Some((controller, scope)));
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Set parameter mapping in given scope and remove it from the other
+    fn set_midi_mapping(&self, key: ParameterKey, opt_mapping: Option<(u8, MidiLearnScope)>) {
+        let patch = self.get_current_patch();
+
+        let mut patch_mappings = MidiMappings::clone(&patch.midi_mappings.load());
+        let mut global_mappings = self.get_global_midi_mappings();
+
+        patch_mappings.remove(key);
+        let global_changed = global_mappings.remove(key);
+
+        match opt_mapping {
+            Some((controller, MidiLearnScope::Patch)) => {
+                patch_mappings.set(key, controller);
+            }
+            Some((controller, MidiLearnScope::Global)) => {
+                global_mappings.set(key, controller);
+            }
+            None => (),
+        }
+
+        if global_changed || matches!(opt_mapping, Some((_, MidiLearnScope::Global))) {
+            self.set_global_midi_mappings(global_mappings);
+            self.global_midi_mappings_changed
+                .store(true, Ordering::SeqCst);
+        }
+
+        patch.set_midi_mappings(patch_mappings);
+
+        self.patches_changed.store(true, Ordering::SeqCst);
+    }
+}
+
+// A/B snapshots
+impl PatchBank {
+    pub fn get_active_snapshot(&self) -> SnapshotSlot {
+        self.get_current_patch().snapshots.load().active
+    }
+
+    /// Store current parameter values in active slot and apply values of
+    /// the other one, all at once. If the other slot is empty, it starts out
+    /// as a copy of the active one.
+    pub fn switch_snapshot(&self) {
+        let patch = self.get_current_patch();
+        let snapshots = patch.get_snapshots();
+        let current_values = patch.get_parameter_values();
+
+        if let Some(values) = snapshots.inactive.as_ref() {
+            self.set_parameter_values(values);
+        }
+
+        patch.set_snapshots(PatchSnapshots {
+            active: snapshots.active.other(),
+            inactive: Some(current_values),
+        });
+
+        self.patches_changed.store(true, Ordering::SeqCst);
+    }
+
+    /// Replace slot B with slot A
+    pub fn copy_snapshot_a_to_b(&self) {
+        let patch = self.get_current_patch();
+        let snapshots = patch.get_snapshots();
+
+        match (snapshots.active, snapshots.inactive.as_ref()) {
+            (SnapshotSlot::A, _) => {
+                patch.set_snapshots(PatchSnapshots {
+                    active: SnapshotSlot::A,
+                    inactive: Some(patch.get_parameter_values()),
+                });
+            }
+            (SnapshotSlot::B, Some(values)) => {
+                self.set_parameter_values(values);
+            }
+            (SnapshotSlot::B, None) => (),
+        }
+
+        self.patches_changed.store(true, Ordering::SeqCst);
+    }
+
+    /// Set values of current patch parameters and mark all of them as
+    /// changed
+    fn set_parameter_values(&self, values: &IndexMap<ParameterKey, f32>) {
+        for (key, parameter) in self.get_current_patch().parameters.iter() {
+            if let Some(value) = values.get(key) {
+                parameter.set_value(*value);
+            }
+        }
+
+        self.mark_parameters_as_changed();
+        self.envelope_viewports_changed
+            .store(true, Ordering::SeqCst);
+    }
+}
+
+// Randomizer
+impl PatchBank {
+    pub fn is_parameter_locked(&self, index: usize) -> bool {
+        let patch = self.get_current_patch();
+
+        match patch.parameters.get_index(index) {
+            Some((key, _)) => patch.randomizer_locks.load().contains(key),
+            None => false,
+        }
+    }
+
+    /// Lock parameter so that randomize and mutate actions leave it alone
+    pub fn set_parameter_locked(&self, index: usize, locked: bool) {
+        let patch = self.get_current_patch();
+
+        if let Some((key, _)) = patch.parameters.get_index(index) {
+            let mut locks = Vec::clone(&patch.randomizer_locks.load());
+
+            locks.retain(|k| k != key);
+
+            if locked {
+                locks.push(*key);
+            }
+
+            patch.set_randomizer_locks(locks);
+
+            self.patches_changed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Set random values for unlocked parameters of current patch
+    pub fn randomize_current_patch(&self) {
+        self.apply_randomizer(|rng, parameter, _| randomize_value(rng, parameter));
+    }
+
+    /// Move unlocked parameters of current patch towards random values by
+    /// amount (0.0 to 1.0)
+    pub fn mutate_current_patch(&self, amount: f32) {
+        self.apply_randomizer(|rng, parameter, value| mutate_value(rng, parameter, value, amount));
+    }
+
+    fn apply_randomizer(&self, mut f: impl FnMut(&mut Rng, Parameter, f32) -> Option<f32>) {
+        let mut rng = Rng::new();
+
+        let patch = self.get_current_patch();
+        let locks = patch.randomizer_locks.load();
+
+        let values = patch
+            .parameters
+            .iter()
+            .filter(|(key, _)| !locks.contains(key))
+            .filter_map(|(key, p)| {
+                f(&mut rng, p.parameter.parameter(), p.get_value()).map(|v| (*key, v))
+            })
+            .collect();
+
+        self.set_parameter_values(&values);
+    }
+}
+
+// Macros
+impl PatchBank {
+    pub fn get_macro_targets(&self, macro_index: usize) -> Vec<MacroTarget> {
+        self.get_current_patch().macro_mappings.load().0[macro_index].clone()
+    }
+
+    /// Set macro targets and apply current macro value to them
+    pub fn set_macro_targets(&self, macro_index: usize, targets: Vec<MacroTarget>) {
+        let patch = self.get_current_patch();
+
+        let mut mappings = MacroMappings::clone(&patch.macro_mappings.load());
+
+        mappings.0[macro_index] = targets;
+
+        patch.set_macro_mappings(mappings);
+
+        if let Some(parameter) = patch
+            .parameters
+            .get(&Parameter::Macro(macro_index as u8).key())
+        {
+            self.apply_macro(parameter);
+        }
+
+        self.patches_changed.store(true, Ordering::SeqCst);
+    }
 }
 
 // Import / export
@@ -374,6 +1006,41 @@ impl PatchBank {
         }
     }
 
+    /// Import patch file bytes, or patch at index in bank file bytes, into
+    /// current patch
+    pub fn import_preset_into_current_patch(
+        &self,
+        bytes: &[u8],
+        opt_bank_index: Option<usize>,
+    ) -> anyhow::Result<()> {
+        let patch = self.get_current_patch();
+
+        match opt_bank_index {
+            Some(index) => update_patch_from_bank_bytes(patch, bytes, index)?,
+            None => patch.update_from_bytes(bytes)?,
+        }
+
+        self.mark_parameters_as_changed();
+        self.patches_changed.store(true, Ordering::SeqCst);
+        self.envelope_viewports_changed
+            .store(true, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// Import patch at index in built-in bank into current patch
+    pub fn import_built_in_patch_into_current_patch(&self, index: usize) -> anyhow::Result<()> {
+        let bank = super::built_in_patch_bank();
+
+        let bytes = bank
+            .patches
+            .get(index)
+            .ok_or_else(|| anyhow::anyhow!("no built-in patch with index {}", index))?
+            .export_fxp_bytes();
+
+        self.import_preset_into_current_patch(&bytes, None)
+    }
+
     pub fn import_bytes_into_current_patch(&self, bytes: &[u8]) {
         match self.get_current_patch().update_from_bytes(bytes) {
             Ok(()) => {
@@ -496,6 +1163,277 @@ pub mod tests {
         }
     }
 
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_macro_targets() {
+        use crate::parameters::{macros::MacroTarget, MasterParameter};
+
+        let bank_1 = PatchBank::default();
+
+        let target_parameter = Parameter::Master(MasterParameter::Volume);
+        let target_index = target_parameter.to_index() as usize;
+        let macro_index = Parameter::Macro(2).to_index() as usize;
+
+        let target = MacroTarget {
+            min: 0.25,
+            max: 0.75,
+            ..MacroTarget::new(target_parameter)
+        };
+
+        bank_1.set_macro_targets(2, vec![target]);
+        bank_1.set_parameter_from_host(macro_index, 0.5);
+
+        assert_eq!(bank_1.get_parameter_value(target_index), Some(0.5));
+
+        // GUI is told about target changes, also when macro is changed from
+        // GUI
+        bank_1.get_changed_parameters_from_gui();
+        bank_1.set_parameter_from_gui(macro_index, 1.0);
+
+        let changed = bank_1.get_changed_parameters_from_gui().unwrap();
+
+        assert_eq!(changed[target_index], Some(0.75));
+
+        assert_eq!(
+            bank_1.get_parameter_name(macro_index).unwrap().as_str(),
+            "Macro 3 (Master volume)"
+        );
+
+        let bank_2 = PatchBank::new_from_bytes(&bank_1.export_plain_bytes());
+
+        assert_eq!(bank_2.get_macro_targets(2), vec![target]);
+        assert!(bank_2.get_macro_targets(0).is_empty());
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_midi_learn() {
+        use crate::parameters::MasterParameter;
+
+        let bank_1 = PatchBank::default();
+
+        let volume_index = Parameter::Master(MasterParameter::Volume).to_index() as usize;
+        let frequency_index = Parameter::Master(MasterParameter::Frequency).to_index() as usize;
+
+        // Channel mode messages can't be learned
+        bank_1.request_midi_learn(volume_index, MidiLearnScope::Patch);
+        bank_1.set_parameters_from_midi_controller(123, 0);
+        bank_1.set_parameters_from_midi_controller(7, 0);
+
+        // Mappings are only stored once result is applied from GUI
+        assert_eq!(bank_1.get_midi_mapping(volume_index), None);
+        assert!(bank_1.apply_midi_learn_result());
+        assert!(!bank_1.apply_midi_learn_result());
+
+        bank_1.request_midi_learn(frequency_index, MidiLearnScope::Global);
+        bank_1.set_parameters_from_midi_controller(8, 0);
+        assert!(bank_1.apply_midi_learn_result());
+
+        assert_eq!(
+            bank_1.get_midi_mapping(volume_index),
+            Some((7, MidiLearnScope::Patch))
+        );
+        assert_eq!(
+            bank_1.get_midi_mapping(frequency_index),
+            Some((8, MidiLearnScope::Global))
+        );
+        assert!(bank_1.have_global_midi_mappings_changed());
+        assert!(!bank_1.have_global_midi_mappings_changed());
+
+        bank_1.set_parameters_from_midi_controller(7, 127);
+
+        assert_eq!(bank_1.get_parameter_value(volume_index), Some(1.0));
+
+        let changes = bank_1.get_changed_parameters_from_midi_and_osc().unwrap();
+
+        assert_eq!(changes[volume_index], Some(1.0));
+        assert_eq!(changes.iter().flatten().count(), 1);
+        assert!(bank_1.get_changed_parameters_from_midi_and_osc().is_none());
+
+        // Changes passed on for applying at sample offset aren't marked for
+        // audio
+        bank_1.get_changed_parameters_from_audio();
+
+        let mut passed_changes = Vec::new();
+
+        bank_1.set_parameters_from_midi_controller_with(7, 0, |index, _, value| {
+            passed_changes.push((index, value));
+        });
+
+        assert_eq!(passed_changes, [(volume_index, 0.0)]);
+        assert!(bank_1.get_changed_parameters_from_audio().is_none());
+
+        // Global mappings apply to all patches, patch mappings don't
+        bank_1.set_patch_index(1);
+        bank_1.set_parameters_from_midi_controller(7, 0);
+        bank_1.set_parameters_from_midi_controller(8, 0);
+
+        assert_ne!(bank_1.get_parameter_value(volume_index), Some(0.0));
+        assert_eq!(bank_1.get_parameter_value(frequency_index), Some(0.0));
+
+        bank_1.set_patch_index(0);
+
+        let bank_2 = PatchBank::new_from_bytes(&bank_1.export_plain_bytes());
+
+        assert_eq!(
+            bank_2.get_midi_mapping(volume_index),
+            Some((7, MidiLearnScope::Patch))
+        );
+        assert_eq!(bank_2.get_midi_mapping(frequency_index), None);
+
+        bank_1.clear_midi_mapping(frequency_index);
+
+        assert_eq!(bank_1.get_midi_mapping(frequency_index), None);
+        assert!(bank_1.have_global_midi_mappings_changed());
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_snapshots() {
+        use crate::parameters::MasterParameter;
+
+        let bank_1 = PatchBank::default();
+
+        let volume_index = Parameter::Master(MasterParameter::Volume).to_index() as usize;
+
+        bank_1.set_parameter_from_gui(volume_index, 0.25);
+
+        // Slot B starts out as a copy of slot A
+        bank_1.switch_snapshot();
+
+        assert_eq!(bank_1.get_active_snapshot(), SnapshotSlot::B);
+        assert_eq!(bank_1.get_parameter_value(volume_index), Some(0.25));
+
+        bank_1.set_parameter_from_gui(volume_index, 0.75);
+        bank_1.switch_snapshot();
+
+        assert_eq!(bank_1.get_active_snapshot(), SnapshotSlot::A);
+        assert_eq!(bank_1.get_parameter_value(volume_index), Some(0.25));
+
+        // Both slots are kept in plugin state
+        let bank_2 = PatchBank::new_from_bytes(&bank_1.export_plain_bytes());
+
+        assert_eq!(bank_2.get_active_snapshot(), SnapshotSlot::A);
+        assert_eq!(bank_2.get_parameter_value(volume_index), Some(0.25));
+
+        bank_2.switch_snapshot();
+
+        assert_eq!(bank_2.get_parameter_value(volume_index), Some(0.75));
+
+        // Copying A to B while B is active applies A
+        bank_2.copy_snapshot_a_to_b();
+
+        assert_eq!(bank_2.get_active_snapshot(), SnapshotSlot::B);
+        assert_eq!(bank_2.get_parameter_value(volume_index), Some(0.25));
+
+        bank_2.switch_snapshot();
+
+        assert_eq!(bank_2.get_parameter_value(volume_index), Some(0.25));
+
+        // Copying A to B while A is active stores current values in B
+        bank_1.copy_snapshot_a_to_b();
+        bank_1.set_parameter_from_gui(volume_index, 0.5);
+        bank_1.switch_snapshot();
+
+        assert_eq!(bank_1.get_parameter_value(volume_index), Some(0.25));
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_randomizer_locks() {
+        use crate::parameters::OperatorParameter;
+
+        let bank_1 = PatchBank::default();
+
+        let ratio_index =
+            Parameter::Operator(0, OperatorParameter::FrequencyRatio).to_index() as usize;
+        let wave_type_index =
+            Parameter::Operator(0, OperatorParameter::WaveType).to_index() as usize;
+
+        bank_1.set_parameter_from_gui(ratio_index, 0.123);
+        bank_1.set_parameter_from_gui(wave_type_index, 0.0);
+        bank_1.set_parameter_locked(ratio_index, true);
+
+        assert!(bank_1.is_parameter_locked(ratio_index));
+        assert!(!bank_1.is_parameter_locked(wave_type_index));
+
+        // Mutating by zero changes nothing
+        bank_1.mutate_current_patch(0.0);
+
+        assert_eq!(bank_1.get_parameter_value(wave_type_index), Some(0.0));
+
+        for _ in 0..10 {
+            bank_1.randomize_current_patch();
+
+            assert_eq!(bank_1.get_parameter_value(ratio_index), Some(0.123));
+        }
+
+        let bank_2 = PatchBank::new_from_bytes(&bank_1.export_plain_bytes());
+
+        assert!(bank_2.is_parameter_locked(ratio_index));
+
+        bank_1.set_parameter_locked(ratio_index, false);
+
+        assert!(!bank_1.is_parameter_locked(ratio_index));
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_import_preset_into_current_patch() {
+        use crate::sync::{get_patch_name_from_bytes, get_patch_names_from_bank_bytes};
+
+        let bank_1 = PatchBank::default();
+
+        bank_1.patches[3].set_name("Bell");
+        bank_1.patches[3].parameters[0].set_value(0.25);
+
+        let bank_bytes = bank_1.export_fxb_bytes();
+        let patch_bytes = bank_1.patches[3].export_fxp_bytes();
+
+        assert_eq!(
+            get_patch_names_from_bank_bytes(&bank_bytes).unwrap()[3].as_str(),
+            "Bell"
+        );
+        assert_eq!(
+            get_patch_name_from_bytes(&patch_bytes).unwrap().as_str(),
+            "Bell"
+        );
+
+        for (bytes, opt_bank_index) in [(&bank_bytes, Some(3)), (&patch_bytes, None)] {
+            let bank_2 = PatchBank::default();
+
+            bank_2.set_patch_index(1);
+            bank_2
+                .import_preset_into_current_patch(bytes, opt_bank_index)
+                .unwrap();
+
+            assert_eq!(bank_2.get_current_patch_name().as_str(), "Bell");
+            assert_eq!(bank_2.get_parameter_value(0), Some(0.25));
+            assert_eq!(bank_2.patches[0].get_name(), "-");
+        }
+
+        assert!(PatchBank::default()
+            .import_preset_into_current_patch(&bank_bytes, Some(128))
+            .is_err());
+
+        let built_in_bank = built_in_patch_bank();
+
+        bank_1.set_patch_index(3);
+        bank_1.import_built_in_patch_into_current_patch(1).unwrap();
+
+        assert_eq!(
+            bank_1.get_current_patch().get_name(),
+            built_in_bank.patches[1].get_name()
+        );
+        assert_eq!(
+            bank_1.get_parameter_value(0),
+            Some(built_in_bank.patches[1].parameters[0].get_value())
+        );
+        assert!(bank_1
+            .import_built_in_patch_into_current_patch(128)
+            .is_err());
+    }
+
     #[test]
     fn test_load_built_in_patches() {
         let preset_bank = built_in_patch_bank();
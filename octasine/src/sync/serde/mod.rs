@@ -4,25 +4,35 @@ mod v2;
 
 use std::io::Write;
 
+use compact_str::CompactString;
+
 use super::patch_bank::{Patch, PatchBank};
 
 /// Remember to update relevant metadata if changes were indeed made
 pub fn update_bank_from_bytes(bank: &PatchBank, bytes: &[u8]) -> anyhow::Result<Option<u8>> {
-    let serde_bank = if v2::bytes_are_v2(bytes) {
-        v2::SerdePatchBank::from_bytes(bytes)?
-    } else {
-        v2::SerdePatchBank::from_v1(v1::SerdePatchBank::from_bytes(bytes)?)?
-    };
+    let serde_bank = deserialize_bank(bytes)?;
 
     let default_serde_patch = v2::SerdePatch::new(&Patch::default());
 
     for (index, patch) in bank.patches.iter().enumerate() {
         let serde_patch = if let Some(serde_patch) = serde_bank.patches.get(index) {
             patch.set_name(serde_patch.name.as_str());
+            patch.set_macro_mappings(serde_patch.macro_mappings.clone());
+            patch.set_custom_velocity_curve(serde_patch.get_custom_velocity_curve());
+            patch.set_chord_shape(serde_patch.get_chord_shape());
+            patch.set_midi_mappings(serde_patch.midi_mappings.clone());
+            patch.set_snapshots(serde_patch.get_snapshots());
+            patch.set_randomizer_locks(serde_patch.randomizer_locks.clone());
 
             serde_patch
         } else {
             patch.set_name("");
+            patch.set_macro_mappings(Default::default());
+            patch.set_custom_velocity_curve(Default::default());
+            patch.set_chord_shape(Default::default());
+            patch.set_midi_mappings(Default::default());
+            patch.set_snapshots(Default::default());
+            patch.set_randomizer_locks(Default::default());
 
             &default_serde_patch
         };
@@ -39,21 +49,72 @@ pub fn update_bank_from_bytes(bank: &PatchBank, bytes: &[u8]) -> anyhow::Result<
 
 /// Remember to update relevant metadata if changes were indeed made
 pub fn update_patch_from_bytes(patch: &Patch, bytes: &[u8]) -> anyhow::Result<()> {
-    let serde_patch = if v2::bytes_are_v2(bytes) {
-        v2::SerdePatch::from_bytes(bytes)?
+    update_patch_from_serde_patch(patch, deserialize_patch(bytes)?);
+
+    Ok(())
+}
+
+/// Update patch from patch at index in bank
+///
+/// Remember to update relevant metadata if changes were indeed made
+pub fn update_patch_from_bank_bytes(
+    patch: &Patch,
+    bytes: &[u8],
+    index: usize,
+) -> anyhow::Result<()> {
+    let serde_patch = deserialize_bank(bytes)?
+        .patches
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| anyhow::anyhow!("no patch with index {} in bank", index))?;
+
+    update_patch_from_serde_patch(patch, serde_patch);
+
+    Ok(())
+}
+
+pub fn get_patch_name_from_bytes(bytes: &[u8]) -> anyhow::Result<CompactString> {
+    Ok(deserialize_patch(bytes)?.name)
+}
+
+pub fn get_patch_names_from_bank_bytes(bytes: &[u8]) -> anyhow::Result<Vec<CompactString>> {
+    Ok(deserialize_bank(bytes)?
+        .patches
+        .into_iter()
+        .map(|patch| patch.name)
+        .collect())
+}
+
+fn deserialize_bank(bytes: &[u8]) -> anyhow::Result<v2::SerdePatchBank> {
+    if v2::bytes_are_v2(bytes) {
+        v2::SerdePatchBank::from_bytes(bytes)
+    } else {
+        v2::SerdePatchBank::from_v1(v1::SerdePatchBank::from_bytes(bytes)?)
+    }
+}
+
+fn deserialize_patch(bytes: &[u8]) -> anyhow::Result<v2::SerdePatch> {
+    if v2::bytes_are_v2(bytes) {
+        v2::SerdePatch::from_bytes(bytes)
     } else {
-        v2::SerdePatch::from_v1(v1::SerdePatch::from_bytes(bytes)?)?
-    };
+        v2::SerdePatch::from_v1(v1::SerdePatch::from_bytes(bytes)?)
+    }
+}
 
+fn update_patch_from_serde_patch(patch: &Patch, serde_patch: v2::SerdePatch) {
     patch.set_name(serde_patch.name.as_str());
+    patch.set_custom_velocity_curve(serde_patch.get_custom_velocity_curve());
+    patch.set_chord_shape(serde_patch.get_chord_shape());
+    patch.set_snapshots(serde_patch.get_snapshots());
+    patch.set_macro_mappings(serde_patch.macro_mappings);
+    patch.set_midi_mappings(serde_patch.midi_mappings);
+    patch.set_randomizer_locks(serde_patch.randomizer_locks);
 
     for (key, parameter) in patch.parameters.iter() {
         if let Some(serde_parameter) = serde_patch.parameters.get(key) {
             parameter.set_value(serde_parameter.value_patch);
         }
     }
-
-    Ok(())
 }
 
 pub fn serialize_bank_plain_bytes<W: Write>(
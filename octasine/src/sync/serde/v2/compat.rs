@@ -1,11 +1,16 @@
 use semver::Version;
 
-use crate::parameters::{OperatorParameter, Parameter, SerializableRepresentation};
+use crate::parameters::{
+    LfoParameter, LfoShapeValue, OperatorParameter, Parameter, ParameterValue,
+    SerializableRepresentation,
+};
 
 use super::SerdePatch;
 
-pub const COMPATIBILITY_CHANGES: &[(Version, fn(&mut SerdePatch))] =
-    &[(Version::new(0, 8, 5), compat_0_8_5)];
+pub const COMPATIBILITY_CHANGES: &[(Version, fn(&mut SerdePatch))] = &[
+    (Version::new(0, 8, 5), compat_0_8_5),
+    (Version::new(0, 9, 2), compat_0_9_2),
+];
 
 /// New operator wave forms
 ///
@@ -49,3 +54,121 @@ pub fn compat_0_8_5(patch: &mut SerdePatch) {
         }
     }
 }
+
+/// New LFO shapes
+///
+/// Adding steps changes the patch values of the previously existing shapes,
+/// so recalculate them from their text representations
+pub fn compat_0_9_2(patch: &mut SerdePatch) {
+    let parameter_keys = [
+        Parameter::Lfo(0, LfoParameter::Shape).key(),
+        Parameter::Lfo(1, LfoParameter::Shape).key(),
+        Parameter::Lfo(2, LfoParameter::Shape).key(),
+        Parameter::Lfo(3, LfoParameter::Shape).key(),
+    ];
+
+    for key in parameter_keys {
+        let p = patch.parameters.get_mut(&key).unwrap();
+
+        match &p.value_serializable {
+            SerializableRepresentation::Other(s) => match LfoShapeValue::new_from_text(s) {
+                Some(value) => {
+                    p.value_patch = value.to_patch();
+                }
+                None => {
+                    ::log::error!(
+                        "converting patch for 0.9.2 compatibility: unrecognized lfo shape: {}",
+                        s
+                    );
+                }
+            },
+            SerializableRepresentation::Float(v) => {
+                ::log::error!(
+                    "converting patch for 0.9.2 compatibility: incorrect serializable representation for lfo shape: {}",
+                    v
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parameters::lfo_shape::LFO_SHAPE_STEPS,
+        sync::{patch_bank::Patch, snapshots::PatchSnapshots},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_compat_0_9_2_lfo_shapes() {
+        // Shapes and their step order prior to 0.9.2
+        let old_steps = &LFO_SHAPE_STEPS[..8];
+
+        for (i, shape) in old_steps.iter().copied().enumerate() {
+            // Inactive A/B snapshot slot needs conversion too
+            let source_patch = Patch::default();
+
+            source_patch.set_snapshots(PatchSnapshots {
+                active: Default::default(),
+                inactive: Some(
+                    source_patch
+                        .parameters
+                        .iter()
+                        .map(|(k, p)| (*k, p.get_value()))
+                        .collect(),
+                ),
+            });
+
+            let mut patch = SerdePatch::new(&source_patch);
+
+            patch.octasine_version = Version::new(0, 9, 1);
+
+            let inactive = patch.snapshots.inactive.as_mut().unwrap();
+
+            for parameters in [&mut patch.parameters, inactive] {
+                for lfo_index in 0..4 {
+                    let key = Parameter::Lfo(lfo_index, LfoParameter::Shape).key();
+                    let p = parameters.get_mut(&key).unwrap();
+
+                    p.value_patch = i as f32 / (old_steps.len() - 1) as f32;
+                    p.value_serializable =
+                        SerializableRepresentation::Other(LfoShapeValue(shape).get_formatted());
+                }
+            }
+
+            patch.run_compatibility_changes();
+
+            let inactive = patch.snapshots.inactive.as_ref().unwrap();
+
+            for parameters in [&patch.parameters, inactive] {
+                for lfo_index in 0..4 {
+                    let key = Parameter::Lfo(lfo_index, LfoParameter::Shape).key();
+                    let value = LfoShapeValue::new_from_patch(parameters[&key].value_patch);
+
+                    assert_eq!(value.get(), shape);
+                    assert_eq!(value.get_formatted(), LfoShapeValue(shape).get_formatted());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_compat_0_9_2_leaves_current_patches_alone() {
+        for shape in LFO_SHAPE_STEPS.iter().copied() {
+            let source_patch = Patch::default();
+            let key = Parameter::Lfo(0, LfoParameter::Shape).key();
+
+            source_patch.parameters[&key].set_value(LfoShapeValue(shape).to_patch());
+
+            let mut patch = SerdePatch::new(&source_patch);
+
+            patch.run_compatibility_changes();
+
+            let value = LfoShapeValue::new_from_patch(patch.parameters[&key].value_patch);
+
+            assert_eq!(value.get(), shape);
+        }
+    }
+}
@@ -9,8 +9,17 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
     common::IndexMap,
-    parameters::{Parameter, ParameterKey, SerializableRepresentation},
-    sync::patch_bank::{Patch, PatchBank},
+    parameters::{
+        chord_memory::{ChordNote, ChordShape},
+        macros::MacroMappings,
+        midi_learn::MidiMappings,
+        velocity_curve::{CustomVelocityCurve, VelocityCurveBreakpoint},
+        Parameter, ParameterKey, SerializableRepresentation,
+    },
+    sync::{
+        patch_bank::{Patch, PatchBank},
+        snapshots::{PatchSnapshots, SnapshotSlot},
+    },
 };
 
 use self::compat::COMPATIBILITY_CHANGES;
@@ -82,6 +91,18 @@ pub struct SerdePatch {
     octasine_version: Version,
     pub name: CompactString,
     pub parameters: IndexMap<ParameterKey, SerdePatchParameter>,
+    #[serde(default)]
+    pub macro_mappings: MacroMappings,
+    #[serde(default)]
+    pub velocity_curve_breakpoints: Vec<VelocityCurveBreakpoint>,
+    #[serde(default)]
+    pub chord_notes: Vec<ChordNote>,
+    #[serde(default)]
+    pub midi_mappings: MidiMappings,
+    #[serde(default)]
+    pub snapshots: SerdePatchSnapshots,
+    #[serde(default)]
+    pub randomizer_locks: Vec<ParameterKey>,
 }
 
 impl SerdePatch {
@@ -105,6 +126,34 @@ impl SerdePatch {
             octasine_version: get_octasine_version(),
             name: patch.get_name().into(),
             parameters,
+            macro_mappings: MacroMappings::clone(&patch.get_macro_mappings()),
+            velocity_curve_breakpoints: patch.get_custom_velocity_curve().breakpoints().to_vec(),
+            chord_notes: patch.get_chord_shape().notes().to_vec(),
+            midi_mappings: MidiMappings::clone(&patch.get_midi_mappings()),
+            snapshots: SerdePatchSnapshots::new(patch),
+            randomizer_locks: Vec::clone(&patch.get_randomizer_locks()),
+        }
+    }
+
+    /// Invalid breakpoints result in default custom curve
+    pub fn get_custom_velocity_curve(&self) -> CustomVelocityCurve {
+        CustomVelocityCurve::new(self.velocity_curve_breakpoints.clone()).unwrap_or_default()
+    }
+
+    /// Invalid notes result in default (empty) chord shape
+    pub fn get_chord_shape(&self) -> ChordShape {
+        ChordShape::new(self.chord_notes.clone()).unwrap_or_default()
+    }
+
+    pub fn get_snapshots(&self) -> PatchSnapshots {
+        PatchSnapshots {
+            active: self.snapshots.active,
+            inactive: self.snapshots.inactive.as_ref().map(|parameters| {
+                parameters
+                    .iter()
+                    .map(|(key, parameter)| (*key, parameter.value_patch))
+                    .collect()
+            }),
         }
     }
 
@@ -133,6 +182,12 @@ impl SerdePatch {
             octasine_version,
             name: v1.name.into(),
             parameters: v2_parameters,
+            macro_mappings: Default::default(),
+            velocity_curve_breakpoints: Default::default(),
+            chord_notes: Default::default(),
+            midi_mappings: Default::default(),
+            snapshots: Default::default(),
+            randomizer_locks: Default::default(),
         };
 
         patch.run_compatibility_changes();
@@ -160,13 +215,59 @@ impl SerdePatch {
         for (changed_in_version, f) in COMPATIBILITY_CHANGES {
             if self.octasine_version < *changed_in_version {
                 f(self);
-            } else {
-                break;
+
+                // Apply change to inactive snapshot parameters too by
+                // temporarily swapping them in
+                if let Some(mut parameters) = self.snapshots.inactive.take() {
+                    ::std::mem::swap(&mut self.parameters, &mut parameters);
+                    f(self);
+                    ::std::mem::swap(&mut self.parameters, &mut parameters);
+
+                    self.snapshots.inactive = Some(parameters);
+                }
             }
         }
     }
 }
 
+#[derive(Default, Serialize, Deserialize)]
+pub struct SerdePatchSnapshots {
+    pub active: SnapshotSlot,
+    /// Parameters of inactive slot, stored in the same way as patch
+    /// parameters so that compatibility changes can be run on them
+    pub inactive: Option<IndexMap<ParameterKey, SerdePatchParameter>>,
+}
+
+impl SerdePatchSnapshots {
+    fn new(patch: &Patch) -> Self {
+        let snapshots = patch.get_snapshots();
+
+        let inactive = snapshots.inactive.as_ref().map(|values| {
+            patch
+                .parameters
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (k, p))| {
+                    let value = *values.get(k)?;
+
+                    let parameter = SerdePatchParameter {
+                        index: i,
+                        value_patch: value,
+                        value_serializable: (p.get_serializable)(value),
+                    };
+
+                    Some((*k, parameter))
+                })
+                .collect()
+        });
+
+        Self {
+            active: snapshots.active,
+            inactive,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SerdePatchParameter {
     index: usize,
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{common::IndexMap, parameters::ParameterKey};
+
+/// Slot for A/B comparison of patch edits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapshotSlot {
+    #[default]
+    A,
+    B,
+}
+
+impl SnapshotSlot {
+    pub fn other(self) -> Self {
+        match self {
+            Self::A => Self::B,
+            Self::B => Self::A,
+        }
+    }
+}
+
+/// A/B snapshots of patch parameter values. Values of the active slot are
+/// the live patch parameters, so only the inactive slot is stored here.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PatchSnapshots {
+    pub active: SnapshotSlot,
+    /// Parameter values of inactive slot. None until slots are switched or
+    /// copied for the first time.
+    pub inactive: Option<IndexMap<ParameterKey, f32>>,
+}
@@ -9,7 +9,24 @@ macro_rules! crate_version {
     };
 }
 
+/// Handle MIDI CC messages processed by audio since last call. Call before
+/// `update_audio_parameters`, so that changes to mapped parameters are
+/// applied right away.
+///
+/// Not needed by plugin formats that handle MIDI CC messages before
+/// enqueuing them, in order to apply changes at their sample offsets.
+pub fn update_parameters_from_midi_controllers<T>(audio: &mut AudioState, sync: &SyncState<T>) {
+    for (controller, value) in audio.take_midi_controller_changes() {
+        sync.patches
+            .set_parameters_from_midi_controller(controller, value);
+    }
+}
+
 pub fn update_audio_parameters<T>(audio: &mut AudioState, sync: &SyncState<T>) {
+    if let Some(index) = audio.take_program_change_patch_index() {
+        sync.patches.set_patch_index_from_audio(index);
+    }
+
     if let Some(indeces) = sync.patches.get_changed_parameters_from_audio() {
         for (index, opt_new_value) in indeces.iter().enumerate() {
             if let Some(new_value) = opt_new_value {
@@ -19,6 +36,22 @@ pub fn update_audio_parameters<T>(audio: &mut AudioState, sync: &SyncState<T>) {
             }
         }
     }
+
+    if let Some(curve) = sync.patches.get_changed_custom_velocity_curve() {
+        audio.set_custom_velocity_curve(curve);
+    }
+
+    if let Some(shape) = sync.patches.get_changed_chord_shape() {
+        audio.set_chord_shape(shape);
+    }
+
+    if sync.patches.is_chord_learn_requested() {
+        audio.learn_chord_shape();
+
+        if let Some(shape) = audio.take_learned_chord_shape() {
+            sync.patches.set_chord_shape_from_audio(shape);
+        }
+    }
 }
 
 pub fn init_logging(plugin_type: &str) -> anyhow::Result<()> {
@@ -1,3 +1,3 @@
 #!/bin/sh
 
-cargo +nightly clippy --workspace --all-targets --features "vst2 clap"
+cargo +nightly clippy --workspace --all-targets --features "vst2 vst3 clap lv2"
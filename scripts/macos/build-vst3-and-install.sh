@@ -0,0 +1,16 @@
+#!/bin/bash
+
+# Validate with the validator in the VST3 SDK
+
+set -e
+
+cargo xtask bundle -p octasine --profile "release-debug" --features "vst3"
+
+TARGET="/Library/Audio/Plug-Ins/VST3/OctaSine.vst3"
+
+if [ -d "$TARGET" ]; then
+	rm -r "$TARGET"
+fi
+
+cp -r "./target/bundled/octasine.vst3" "$TARGET"
+echo "Copied VST3 bundle to $TARGET"
@@ -0,0 +1,16 @@
+#!/bin/sh
+
+# Run standalone OctaSine without GUI against a JACK server using the dummy
+# driver, checking that audio is processed
+
+jackd --no-realtime -d dummy -r 44100 -p 256 &
+JACKD_PID=$!
+
+sleep 1
+
+cargo run -p octasine-cli --no-default-features --features jack -- run-gui --no-gui --quit-after 5
+STATUS=$?
+
+kill $JACKD_PID
+
+exit $STATUS
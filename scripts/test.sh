@@ -1,3 +1,3 @@
 #!/bin/sh
 
-RUSTFLAGS="-C target-cpu=native" cargo test --features "vst2 clap"
\ No newline at end of file
+RUSTFLAGS="-C target-cpu=native" cargo test --features "vst2 vst3 clap lv2"
\ No newline at end of file